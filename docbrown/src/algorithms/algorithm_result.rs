@@ -0,0 +1,315 @@
+//! A common result type for algorithms that compute a value for every vertex of a graph.
+//!
+//! Values are keyed by the global vertex id, and the result also remembers the names of the
+//! vertices so values can be looked up, sorted and exported by name.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use docbrown::algorithms::reciprocity::all_local_reciprocity;
+//! use docbrown::db::graph::Graph;
+//!
+//! let g = Graph::new(2);
+//! let edges = vec![("alice", "bob"), ("bob", "alice"), ("alice", "carol")];
+//!
+//! for (src, dst) in edges {
+//!     g.add_edge(0, src, dst, &vec![], None).unwrap();
+//! }
+//!
+//! let result = all_local_reciprocity(&g);
+//! assert_eq!(result.get("bob"), Some(&1.0));
+//!
+//! let top = result.top_k(1);
+//! assert_eq!(result.name(top[0].0), Some("bob"));
+//! ```
+
+use crate::core::vertex::InputVertex;
+use crate::core::Prop;
+use crate::db::view_api::*;
+use num_traits::ToPrimitive;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io;
+
+/// The values computed by an algorithm for every vertex, keyed by global vertex id.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlgorithmResult<V> {
+    values: FxHashMap<u64, V>,
+    names: FxHashMap<u64, String>,
+    ids: FxHashMap<String, u64>,
+}
+
+impl<V> AlgorithmResult<V> {
+    /// Wraps the values computed over `g`, looking up the names of the vertices in the graph.
+    /// Vertices without a name are named after their id.
+    pub fn new<G: GraphViewOps>(g: &G, values: FxHashMap<u64, V>) -> Self {
+        let names = values
+            .keys()
+            .map(|v_id| {
+                let name = g
+                    .vertex(*v_id)
+                    .map(|v| v.name())
+                    .unwrap_or_else(|| v_id.to_string());
+                (*v_id, name)
+            })
+            .collect::<FxHashMap<_, _>>();
+        let ids = names
+            .iter()
+            .map(|(v_id, name)| (name.clone(), *v_id))
+            .collect();
+        Self { values, names, ids }
+    }
+
+    /// Returns the value of a vertex, given either its name or its id. Names are looked up in
+    /// the names of the vertices of the result, as the id of a name is not always its hash (see
+    /// `docbrown::core::vertex_names`).
+    pub fn get<T: InputVertex>(&self, v: T) -> Option<&V> {
+        let v_id = match v.name_prop() {
+            Some(Prop::Str(name)) => self.ids.get(&name).copied().unwrap_or_else(|| v.id()),
+            _ => v.id(),
+        };
+        self.values.get(&v_id)
+    }
+
+    /// Returns the name of the vertex with the given id.
+    pub fn name(&self, v_id: u64) -> Option<&str> {
+        self.names.get(&v_id).map(|name| name.as_str())
+    }
+
+    /// Returns the values keyed by global vertex id.
+    pub fn get_all(&self) -> &FxHashMap<u64, V> {
+        &self.values
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn iter(&self) -> std::collections::hash_map::Iter<'_, u64, V> {
+        self.values.iter()
+    }
+
+    /// Applies `f` to every value, keeping the vertex names.
+    pub fn map<U, F: Fn(&V) -> U>(&self, f: F) -> AlgorithmResult<U> {
+        AlgorithmResult {
+            values: self.values.iter().map(|(k, v)| (*k, f(v))).collect(),
+            names: self.names.clone(),
+            ids: self.ids.clone(),
+        }
+    }
+
+    /// Returns the values keyed by vertex name, vertices without a name are keyed by their id.
+    pub fn to_named_map(&self) -> HashMap<String, V>
+    where
+        V: Clone,
+    {
+        self.values
+            .iter()
+            .map(|(v_id, value)| (self.names[v_id].clone(), value.clone()))
+            .collect()
+    }
+
+    /// Returns the `(id, value)` pairs sorted by value, ties are broken on the vertex id.
+    /// Values that can't be compared (i.e. NaN) are treated as equal.
+    pub fn sort_by_value(&self, reverse: bool) -> Vec<(u64, &V)>
+    where
+        V: PartialOrd,
+    {
+        let mut sorted = self.values.iter().map(|(k, v)| (*k, v)).collect::<Vec<_>>();
+        sorted.sort_by(|(k1, v1), (k2, v2)| {
+            let by_value = v1.partial_cmp(v2).unwrap_or(Ordering::Equal);
+            let by_value = if reverse {
+                by_value.reverse()
+            } else {
+                by_value
+            };
+            by_value.then(k1.cmp(k2))
+        });
+        sorted
+    }
+
+    /// Returns the `k` vertices with the largest values, largest first.
+    pub fn top_k(&self, k: usize) -> Vec<(u64, &V)>
+    where
+        V: PartialOrd,
+    {
+        let mut sorted = self.sort_by_value(true);
+        sorted.truncate(k);
+        sorted
+    }
+
+    /// Groups the vertex ids by value, the ids in every group are sorted.
+    pub fn group_by(&self) -> FxHashMap<V, Vec<u64>>
+    where
+        V: Hash + Eq + Clone,
+    {
+        let mut groups: FxHashMap<V, Vec<u64>> = FxHashMap::default();
+        for (v_id, value) in &self.values {
+            groups.entry(value.clone()).or_default().push(*v_id);
+        }
+        for ids in groups.values_mut() {
+            ids.sort();
+        }
+        groups
+    }
+
+    /// Counts the values in `bins` equal width bins spanning the smallest to the largest value.
+    ///
+    /// # Returns
+    ///
+    /// The bins as `(start, end, count)`, the last bin includes its end. Values that are not
+    /// numbers are left out.
+    pub fn histogram(&self, bins: usize) -> Vec<(f64, f64, usize)>
+    where
+        V: ToPrimitive,
+    {
+        let values = self
+            .values
+            .values()
+            .filter_map(|v| v.to_f64())
+            .filter(|v| !v.is_nan())
+            .collect::<Vec<_>>();
+
+        if bins == 0 || values.is_empty() {
+            return vec![];
+        }
+
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let width = (max - min) / bins as f64;
+
+        let mut counts = vec![0; bins];
+        for v in values {
+            let bin = if width > 0.0 {
+                (((v - min) / width) as usize).min(bins - 1)
+            } else {
+                0
+            };
+            counts[bin] += 1;
+        }
+
+        counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, count)| {
+                let start = min + width * i as f64;
+                let end = if i + 1 == bins { max } else { start + width };
+                (start, end, count)
+            })
+            .collect()
+    }
+
+    /// Writes the result as CSV rows of `id,name,value` sorted by id, without a header.
+    /// Tuple values are written as one column per element.
+    pub fn write_csv<W: io::Write>(&self, writer: W) -> Result<(), csv::Error>
+    where
+        V: Serialize,
+    {
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(writer);
+        let mut ids = self.values.keys().collect::<Vec<_>>();
+        ids.sort();
+        for v_id in ids {
+            writer.serialize((v_id, &self.names[v_id], &self.values[v_id]))?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl<V> IntoIterator for AlgorithmResult<V> {
+    type Item = (u64, V);
+    type IntoIter = std::collections::hash_map::IntoIter<u64, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.into_iter()
+    }
+}
+
+impl<'a, V> IntoIterator for &'a AlgorithmResult<V> {
+    type Item = (&'a u64, &'a V);
+    type IntoIter = std::collections::hash_map::Iter<'a, u64, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.iter()
+    }
+}
+
+#[cfg(test)]
+mod algorithm_result_tests {
+    use super::*;
+    use crate::core::vertex_names::VertexIds;
+    use crate::db::graph::Graph;
+
+    fn result() -> AlgorithmResult<u64> {
+        let g = Graph::new(2);
+        g.add_edge(0, "a", "b", &vec![], None).unwrap();
+        g.add_edge(0, 3, 4, &vec![], None).unwrap();
+
+        let values = vec![("a".id(), 5), ("b".id(), 1), (3, 5), (4, 2)]
+            .into_iter()
+            .collect();
+        AlgorithmResult::new(&g, values)
+    }
+
+    #[test]
+    fn lookup_and_sort() {
+        let res = result();
+        assert_eq!(res.get("a"), Some(&5));
+        assert_eq!(res.get(4), Some(&2));
+        assert_eq!(res.get("c"), None);
+        assert_eq!(res.name(3), Some("3"));
+        assert_eq!(res.name("b".id()), Some("b"));
+
+        let sorted = res
+            .sort_by_value(false)
+            .into_iter()
+            .map(|(v_id, _)| res.name(v_id).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(sorted[..2], ["b", "4"]);
+
+        let top = res
+            .top_k(2)
+            .into_iter()
+            .map(|(_, v)| *v)
+            .collect::<Vec<_>>();
+        assert_eq!(top, vec![5, 5]);
+
+        assert_eq!(res.group_by()[&5].len(), 2);
+        assert_eq!(res.to_named_map()["a"], 5);
+    }
+
+    #[test]
+    fn names_are_looked_up_in_the_names_of_the_graph() {
+        let g = Graph::new_with_vertex_ids(2, VertexIds::Dense);
+        g.add_edge(0, "Alice", "Bob", &vec![], None).unwrap();
+        let values = vec![(0, 5), (1, 2)].into_iter().collect();
+        let res = AlgorithmResult::new(&g, values);
+
+        assert_eq!(res.get("Alice"), Some(&5));
+        assert_eq!(res.get("Bob"), Some(&2));
+        assert_eq!(res.get(1), Some(&2));
+        assert_eq!(res.get("Carol"), None);
+    }
+
+    #[test]
+    fn histogram_and_csv() {
+        let res = result();
+        assert_eq!(res.histogram(2), vec![(1.0, 3.0, 2), (3.0, 5.0, 2)]);
+        assert!(res.map(|_| 1).histogram(3)[0].2 == 4);
+
+        let mut out = vec![];
+        res.map(|v| (*v, *v * 2)).write_csv(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("3,3,5,10\n4,4,2,4\n"));
+        assert_eq!(out.lines().count(), 4);
+    }
+}
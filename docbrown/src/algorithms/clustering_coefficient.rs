@@ -1,4 +1,4 @@
-use crate::algorithms::triangle_count::{TriangleCountS1, TriangleCountS2};
+use crate::algorithms::triangle_count::{TemporalTriangleCountS2, TriangleCountS1};
 use crate::algorithms::triplet_count::TripletCount;
 use crate::db::program::{GlobalEvalState, Program};
 use crate::db::view_api::GraphViewOps;
@@ -40,9 +40,9 @@ pub fn clustering_coefficient<G: GraphViewOps>(g: &G) -> f64 {
     let mut gs = GlobalEvalState::new(g.clone(), false);
     let tc = TriangleCountS1 {};
     tc.run_step(g, &mut gs);
-    let tc = TriangleCountS2 {};
+    let tc = TemporalTriangleCountS2 { delta: None };
     tc.run_step(g, &mut gs);
-    let tc_val = tc.produce_output(g, &gs).unwrap_or(0);
+    let tc_val = tc.produce_output(g, &gs).global;
 
     let mut gss = GlobalEvalState::new(g.clone(), false);
     let triplets = TripletCount {};
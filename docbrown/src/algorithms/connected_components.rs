@@ -1,36 +1,151 @@
+//! Weakly connected components, i.e. the components of the graph when the direction of the
+//! edges is ignored.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use docbrown::algorithms::connected_components::weakly_connected_components;
+//! use docbrown::db::graph::Graph;
+//! use docbrown::db::view_api::*;
+//!
+//! let g = Graph::new(2);
+//! let edges = vec![("Gandalf", "Frodo"), ("Frodo", "Sam"), ("Saruman", "Grima")];
+//!
+//! for (src, dst) in edges {
+//!     g.add_edge(0, src, dst, &vec![], None).unwrap();
+//! }
+//!
+//! let components = weakly_connected_components(&g);
+//! assert_eq!(components.num_components(), 2);
+//! assert_eq!(components.component("Gandalf"), components.component("Sam"));
+//!
+//! let lcc = components.filter_to_largest_component();
+//! assert_eq!(lcc.num_vertices(), 3);
+//! assert!(lcc.has_vertex("Frodo"));
+//! ```
+
 use crate::{
-    core::state,
-    db::graph::Graph,
+    algorithms::algorithm_result::AlgorithmResult,
+    core::{cancel::Budget, state, tgraph_shard::errors::GraphError, vertex::InputVertex},
     db::program::{GlobalEvalState, LocalState, Program},
+    db::subgraph::SubGraph,
     db::view_api::GraphViewOps,
 };
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// The weakly connected components of a graph view. Components are identified by the smallest
+/// global vertex id they contain.
+#[derive(Debug, Clone)]
+pub struct ConnectedComponents<G: GraphViewOps> {
+    graph: G,
+    assignments: AlgorithmResult<u64>,
+    members: FxHashMap<u64, Vec<u64>>,
+}
+
+impl<G: GraphViewOps> ConnectedComponents<G> {
+    fn new(graph: G, assignments: AlgorithmResult<u64>) -> Self {
+        let members = assignments.group_by();
+        Self {
+            graph,
+            assignments,
+            members,
+        }
+    }
+
+    /// Returns the component of a vertex, given either its name or its id.
+    pub fn component<T: InputVertex>(&self, v: T) -> Option<u64> {
+        self.assignments.get(v).copied()
+    }
 
-/// Computes the connected components of a graph using the Simple Connected Components algorithm
+    /// Returns the global ids of the vertices in a component, sorted.
+    pub fn members(&self, component: u64) -> Option<&[u64]> {
+        self.members.get(&component).map(|vs| vs.as_slice())
+    }
+
+    /// Returns the component of every vertex.
+    pub fn assignments(&self) -> &AlgorithmResult<u64> {
+        &self.assignments
+    }
+
+    /// Returns the members of every component, keyed by component id.
+    pub fn components(&self) -> &FxHashMap<u64, Vec<u64>> {
+        &self.members
+    }
+
+    pub fn num_components(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns the component with the most vertices, ties are broken on the smallest component
+    /// id. Returns `None` for an empty graph.
+    pub fn largest_component(&self) -> Option<u64> {
+        self.members
+            .iter()
+            .max_by(|(c1, m1), (c2, m2)| m1.len().cmp(&m2.len()).then(c2.cmp(c1)))
+            .map(|(c, _)| *c)
+    }
+
+    /// Returns a view of the graph restricted to the vertices of its largest component.
+    pub fn filter_to_largest_component(&self) -> SubGraph<G> {
+        let vertices: FxHashSet<u64> = self
+            .largest_component()
+            .and_then(|c| self.members(c))
+            .unwrap_or_default()
+            .iter()
+            .copied()
+            .collect();
+        SubGraph::new(self.graph.clone(), Some(vertices), None)
+    }
+}
+
+/// Computes the weakly connected components of a graph view using the Simple Connected
+/// Components algorithm. The algorithm runs until no vertex changes component.
 ///
 /// # Arguments
 ///
-/// * `g` - A reference to the graph
-/// * `window` - A range indicating the temporal window to consider
-/// * `iter_count` - The number of iterations to run
+/// * `g` - A reference to the graph view
 ///
 /// # Returns
 ///
-/// A hash map containing the mapping from component ID to the number of vertices in the component
+/// A `ConnectedComponents` holding the component of every vertex and the members of every
+/// component
+///
+pub fn weakly_connected_components<G: GraphViewOps>(g: &G) -> ConnectedComponents<G> {
+    let cc = WeaklyConnectedComponents {};
+
+    let gs = cc.run(g, true, usize::MAX);
+
+    ConnectedComponents::new(g.clone(), cc.produce_output(g, &gs))
+}
+
+/// Computes the components like `weakly_connected_components`, stopping between supersteps
+/// once `budget` runs out.
+///
+/// # Errors
 ///
-pub fn weakly_connected_components(g: &Graph, iter_count: usize) -> FxHashMap<u64, u64> {
+/// Returns `GraphError::Cancelled` or `GraphError::DeadlineExceeded` if the budget ran out.
+pub fn weakly_connected_components_with_budget<G: GraphViewOps>(
+    g: &G,
+    budget: &Budget,
+) -> Result<ConnectedComponents<G>, GraphError> {
     let cc = WeaklyConnectedComponents {};
 
-    let gs = cc.run(g, true, iter_count);
+    let (gs, report) = cc.run_with_budget(g, true, usize::MAX, budget);
+    if let Some(err) = report.termination.error() {
+        return Err(err);
+    }
 
-    cc.produce_output(g, &gs)
+    Ok(ConnectedComponents::new(
+        g.clone(),
+        cc.produce_output(g, &gs),
+    ))
 }
 
 #[derive(Default)]
 struct WeaklyConnectedComponents {}
 
 impl Program for WeaklyConnectedComponents {
-    type Out = FxHashMap<u64, u64>;
+    type Out = AlgorithmResult<u64>;
 
     fn local_eval<G: GraphViewOps>(&self, c: &LocalState<G>) {
         let min = c.agg(state::def::min(0));
@@ -74,13 +189,15 @@ impl Program for WeaklyConnectedComponents {
                 })
             });
 
-        results
+        AlgorithmResult::new(g, results)
     }
 }
 
 #[cfg(test)]
 mod cc_test {
     use super::*;
+    use crate::db::graph::Graph;
+    use crate::db::view_api::*;
     use itertools::*;
     use std::{cmp::Reverse, iter::once};
 
@@ -190,7 +307,9 @@ mod cc_test {
             graph.add_edge(ts, src, dst, &vec![], None).unwrap();
         }
 
-        let results: FxHashMap<u64, u64> = weakly_connected_components(&graph, usize::MAX)
+        let results: FxHashMap<u64, u64> = weakly_connected_components(&graph)
+            .assignments()
+            .clone()
             .into_iter()
             .map(|(k, v)| (k, v as u64))
             .collect();
@@ -246,7 +365,9 @@ mod cc_test {
             graph.add_edge(ts, src, dst, &vec![], None).unwrap();
         }
 
-        let results: FxHashMap<u64, u64> = weakly_connected_components(&graph, usize::MAX)
+        let results: FxHashMap<u64, u64> = weakly_connected_components(&graph)
+            .assignments()
+            .clone()
             .into_iter()
             .map(|(k, v)| (k, v as u64))
             .collect();
@@ -271,6 +392,39 @@ mod cc_test {
         );
     }
 
+    #[test]
+    fn components_members_and_largest_component() {
+        let graph = Graph::new(3);
+
+        let edges = vec![
+            (1, 2, 1),
+            (2, 3, 2),
+            (4, 5, 3),
+            (6, 7, 4),
+            (7, 8, 5),
+            (8, 9, 6),
+        ];
+
+        for (src, dst, ts) in edges {
+            graph.add_edge(ts, src, dst, &vec![], None).unwrap();
+        }
+
+        let components = weakly_connected_components(&graph);
+        assert_eq!(components.num_components(), 3);
+        assert_eq!(components.component(9), Some(6));
+        assert_eq!(components.members(1), Some([1, 2, 3].as_slice()));
+        assert_eq!(components.largest_component(), Some(6));
+
+        let lcc = components.filter_to_largest_component();
+        assert_eq!(lcc.vertices().id().sorted().collect_vec(), vec![6, 7, 8, 9]);
+        assert_eq!(lcc.num_edges(), 3);
+
+        // the last edge is outside the window so the two largest components tie
+        let components = weakly_connected_components(&graph.window(0, 6));
+        assert_eq!(components.largest_component(), Some(1));
+        assert_eq!(components.filter_to_largest_component().num_vertices(), 3);
+    }
+
     // connected components on a graph with 1 node and a self loop
     #[test]
     fn simple_connected_components_3() {
@@ -282,14 +436,32 @@ mod cc_test {
             graph.add_edge(ts, src, dst, &vec![], None).unwrap();
         }
 
-        let results: FxHashMap<u64, u64> = weakly_connected_components(&graph, usize::MAX);
+        let results = weakly_connected_components(&graph);
 
         assert_eq!(
-            results,
+            *results.assignments().get_all(),
             vec![(1, 1),].into_iter().collect::<FxHashMap<u64, u64>>()
         );
     }
 
+    #[test]
+    fn connected_components_stop_at_the_deadline() {
+        let graph = Graph::new(2);
+        for (src, dst) in [(1, 2), (2, 3), (4, 5)] {
+            graph.add_edge(0, src, dst, &vec![], None).unwrap();
+        }
+
+        let budget = Budget::default().with_deadline(std::time::Instant::now());
+        let result = weakly_connected_components_with_budget(&graph, &budget);
+        assert!(matches!(result, Err(GraphError::DeadlineExceeded)));
+
+        let components = weakly_connected_components_with_budget(&graph, &Budget::default());
+        assert_eq!(
+            components.unwrap().assignments().get_all(),
+            weakly_connected_components(&graph).assignments().get_all()
+        );
+    }
+
     #[quickcheck]
     fn circle_graph_the_smallest_value_is_the_cc(vs: Vec<u64>) {
         if vs.len() > 0 {
@@ -316,9 +488,10 @@ mod cc_test {
 
             // now we do connected components over window 0..1
 
-            let components: FxHashMap<u64, u64> = weakly_connected_components(&graph, usize::MAX);
+            let components = weakly_connected_components(&graph);
 
             let actual = components
+                .assignments()
                 .iter()
                 .group_by(|(_, cc)| *cc)
                 .into_iter()
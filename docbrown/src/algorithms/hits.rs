@@ -1,7 +1,10 @@
+use crate::algorithms::algorithm_result::AlgorithmResult;
 use crate::algorithms::*;
 use crate::core::agg::*;
+use crate::core::cancel::Budget;
 use crate::core::state::def::*;
 use crate::core::state::*;
+use crate::core::tgraph_shard::errors::GraphError;
 use crate::db::graph::Graph;
 use crate::db::program::*;
 use crate::db::view_api::GraphViewOps;
@@ -243,56 +246,75 @@ impl Program for HitsS3 {
 // HubScore of a vertex (A) = Sum of AuthScore of all vertices pointing away from vertex (A) from previous iteration /
 //     Sum of AuthScore of all vertices in the current iteration
 
+/// Computes the hub and authority scores of every vertex of `g`.
+///
+/// Every iteration is one superstep. The scores are updated until no hub or authority score
+/// changes by more than 0.01 in an iteration, or for at most `iter_count + 2` iterations.
+pub fn hits(g: &Graph, window: Range<i64>, iter_count: usize) -> AlgorithmResult<(f32, f32)> {
+    hits_with_budget(g, window, iter_count, &Budget::default())
+        .expect("an unlimited budget never runs out")
+}
+
+/// Runs `hits`, stopping early if `budget` runs out.
+///
+/// # Errors
+///
+/// Returns `GraphError::Cancelled` or `GraphError::DeadlineExceeded` if the budget ran out.
+pub fn hits_with_budget(
+    g: &Graph,
+    window: Range<i64>,
+    iter_count: usize,
+    budget: &Budget,
+) -> Result<AlgorithmResult<(f32, f32)>, GraphError> {
+    run_hits(g, window, iter_count, budget, false)
+}
+
+/// Runs `hits` with the scores merged in a fixed order, so the results are bit-identical across
+/// runs and thread counts, see `GlobalEvalState::deterministic`.
+pub fn hits_deterministic(
+    g: &Graph,
+    window: Range<i64>,
+    iter_count: usize,
+) -> AlgorithmResult<(f32, f32)> {
+    run_hits(g, window, iter_count, &Budget::default(), true)
+        .expect("an unlimited budget never runs out")
+}
+
 #[allow(unused_variables)]
-pub fn hits(g: &Graph, window: Range<i64>, iter_count: usize) -> FxHashMap<u64, (f32, f32)> {
-    let mut c = GlobalEvalState::new(g.clone(), true);
+fn run_hits(
+    g: &Graph,
+    window: Range<i64>,
+    iter_count: usize,
+    budget: &Budget,
+    deterministic: bool,
+) -> Result<AlgorithmResult<(f32, f32)>, GraphError> {
+    let mut c = GlobalEvalState::new(g.clone(), true).deterministic(deterministic);
     let hits_s0 = HitsS0::new();
     let hits_s1 = HitsS1::new();
     let hits_s2 = HitsS2::new();
     let hits_s3 = HitsS3::new();
 
-    hits_s0.run_step(g, &mut c);
-
     let max_diff_hub_score = 0.01f32;
     let max_diff_auth_score = max_diff_hub_score;
-    let mut i = 0;
-
-    loop {
-        hits_s1.run_step(g, &mut c);
-        println!("step1, hub: {:?}", c.read_vec_partitions(&val::<MulF32>(0)));
-        println!(
-            "step1, auth: {:?}",
-            c.read_vec_partitions(&val::<MulF32>(1))
-        );
 
-        hits_s2.run_step(g, &mut c);
-        hits_s3.run_step(g, &mut c);
-        println!(
-            "total_hub = {:?}",
-            c.read_global_state(&sum::<SumF32>(4)).unwrap()
-        );
-        println!(
-            "total_auth = {:?}",
-            c.read_global_state(&sum::<SumF32>(5)).unwrap()
-        );
-
-        let r1 = c.read_global_state(&max::<f32>(6)).unwrap();
-        println!("max_diff_hub = {:?}", r1);
-        let r2 = c.read_global_state(&max::<f32>(7)).unwrap();
-        println!("max_diff_auth = {:?}", r2);
-
-        if (r1 <= max_diff_hub_score && r2 <= max_diff_auth_score) || i > iter_count {
-            break;
-        }
-
-        if c.keep_past_state {
-            c.ss += 1;
-        }
-        i += 1;
+    let report = Runner::new()
+        .init(&hits_s0)
+        .step(&hits_s1)
+        .step(&hits_s2)
+        .step(&hits_s3)
+        .converged_when(|c| {
+            let r1 = c.read_global_state(&max::<f32>(6)).unwrap();
+            let r2 = c.read_global_state(&max::<f32>(7)).unwrap();
+            r1 <= max_diff_hub_score && r2 <= max_diff_auth_score
+        })
+        .max_supersteps(iter_count + 2)
+        .budget(budget)
+        .run(g, &mut c)?;
+
+    if let Some(err) = report.termination.error() {
+        return Err(err);
     }
 
-    println!("i = {}", i);
-
     let mut results: FxHashMap<u64, (f32, f32)> = FxHashMap::default();
 
     (0..g.nr_shards)
@@ -309,7 +331,7 @@ pub fn hits(g: &Graph, window: Range<i64>, iter_count: usize) -> FxHashMap<u64,
             })
         });
 
-    results
+    Ok(AlgorithmResult::new(g, results))
 }
 
 #[cfg(test)]
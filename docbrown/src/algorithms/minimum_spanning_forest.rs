@@ -0,0 +1,233 @@
+//! Computes a minimum spanning forest of a graph view, treating edges as undirected and taking
+//! the weight of each edge from a numeric edge property.
+//!
+//! The property is temporal, so its updates within the view are reduced to a single weight
+//! using a `WeightAggregate`. Edges without a numeric value for the property are ignored.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use docbrown::algorithms::minimum_spanning_forest::{minimum_spanning_forest, WeightAggregate};
+//! use docbrown::core::Prop;
+//! use docbrown::db::graph::Graph;
+//! use docbrown::db::view_api::*;
+//!
+//! let g = Graph::new(2);
+//! let edges = vec![(1, 1, 2, 1.0), (2, 2, 3, 2.0), (3, 3, 1, 3.0), (4, 3, 1, 0.5)];
+//!
+//! for (t, src, dst, w) in edges {
+//!     g.add_edge(t, src, dst, &vec![("weight".to_string(), Prop::F64(w))], None)
+//!         .unwrap();
+//! }
+//!
+//! // the latest weight of 3 -> 1 is 0.5 so it replaces 2 -> 3
+//! let forest = minimum_spanning_forest(&g.window(0, 10), "weight", WeightAggregate::Latest);
+//! assert_eq!(forest, vec![(3, 1, 0.5), (1, 2, 1.0)]);
+//!
+//! // within the first 3 time units 3 -> 1 has a weight of 3.0
+//! let forest = minimum_spanning_forest(&g.window(0, 4), "weight", WeightAggregate::Latest);
+//! assert_eq!(forest, vec![(1, 2, 1.0), (2, 3, 2.0)]);
+//! ```
+
+use crate::algorithms::prop_as_f64;
+use crate::db::subgraph::SubGraph;
+use crate::db::view_api::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// How the updates of the weight property within the view are reduced to a single weight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeightAggregate {
+    /// The last value of the property
+    Latest,
+    /// The smallest value of the property
+    Min,
+    /// The largest value of the property
+    Max,
+    /// The sum of all the values of the property
+    Sum,
+    /// The mean of all the values of the property
+    Mean,
+}
+
+impl WeightAggregate {
+    fn apply(&self, values: &[f64]) -> Option<f64> {
+        let last = *values.last()?;
+        let weight = match self {
+            WeightAggregate::Latest => last,
+            WeightAggregate::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+            WeightAggregate::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            WeightAggregate::Sum => values.iter().sum(),
+            WeightAggregate::Mean => values.iter().sum::<f64>() / values.len() as f64,
+        };
+        Some(weight)
+    }
+}
+
+/// Union-find over global vertex ids
+#[derive(Default)]
+struct DisjointSet {
+    parents: FxHashMap<u64, u64>,
+}
+
+impl DisjointSet {
+    fn find(&mut self, v: u64) -> u64 {
+        let mut root = v;
+        while let Some(&parent) = self.parents.get(&root) {
+            if parent == root {
+                break;
+            }
+            root = parent;
+        }
+
+        // compress the path so later lookups are direct
+        let mut current = v;
+        while current != root {
+            let next = self.parents.get(&current).copied().unwrap_or(root);
+            self.parents.insert(current, root);
+            current = next;
+        }
+        root
+    }
+
+    fn union(&mut self, a: u64, b: u64) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            false
+        } else {
+            self.parents.insert(a.max(b), a.min(b));
+            true
+        }
+    }
+}
+
+/// Computes a minimum spanning forest of the view with Kruskal's algorithm.
+///
+/// # Arguments
+///
+/// * `g` - A reference to the graph view
+/// * `weight` - The name of the numeric edge property holding the weight
+/// * `aggregate` - How the updates of `weight` within the view are combined
+///
+/// # Returns
+///
+/// The edges of the forest as `(src, dst, weight)` in the order they were picked, i.e. by
+/// increasing weight. Edges keep the direction they have in the graph.
+pub fn minimum_spanning_forest<G: GraphViewOps>(
+    g: &G,
+    weight: &str,
+    aggregate: WeightAggregate,
+) -> Vec<(u64, u64, f64)> {
+    let mut edges = g
+        .edges()
+        .filter_map(|e| {
+            let mut values = e
+                .property_history(weight.to_string())
+                .into_iter()
+                .filter_map(|(_, prop)| prop_as_f64(prop))
+                .collect::<Vec<_>>();
+            if values.is_empty() {
+                values.extend(e.static_property(weight.to_string()).and_then(prop_as_f64));
+            }
+            let w = aggregate.apply(&values)?;
+            Some((e.src().id(), e.dst().id(), w))
+        })
+        .collect::<Vec<_>>();
+
+    // ties are broken on the ids so the forest does not depend on the shard iteration order
+    edges.sort_by(|(s1, d1, w1), (s2, d2, w2)| w1.total_cmp(w2).then((s1, d1).cmp(&(s2, d2))));
+
+    let mut components = DisjointSet::default();
+    edges
+        .into_iter()
+        .filter(|(src, dst, _)| components.union(*src, *dst))
+        .collect()
+}
+
+/// Computes a minimum spanning forest of the view and returns it as a view over the graph that
+/// only keeps the edges of the forest. See [`minimum_spanning_forest`] for the arguments.
+pub fn minimum_spanning_forest_view<G: GraphViewOps>(
+    g: &G,
+    weight: &str,
+    aggregate: WeightAggregate,
+) -> SubGraph<G> {
+    let edges: FxHashSet<(u64, u64)> = minimum_spanning_forest(g, weight, aggregate)
+        .into_iter()
+        .map(|(src, dst, _)| (src, dst))
+        .collect();
+    SubGraph::new(g.clone(), None, Some(edges))
+}
+
+#[cfg(test)]
+mod msf_tests {
+    use super::*;
+    use crate::core::Prop;
+    use crate::db::graph::Graph;
+
+    fn load(edges: Vec<(i64, u64, u64, i64)>) -> Graph {
+        let g = Graph::new(3);
+        for (t, src, dst, w) in edges {
+            g.add_edge(t, src, dst, &vec![("w".to_string(), Prop::I64(w))], None)
+                .unwrap();
+        }
+        g
+    }
+
+    #[test]
+    fn spans_every_component() {
+        let g = load(vec![
+            (1, 1, 2, 4),
+            (1, 2, 3, 1),
+            (1, 3, 1, 2),
+            (1, 3, 4, 7),
+            (1, 4, 1, 8),
+            // a second component
+            (1, 5, 6, 3),
+        ]);
+
+        let forest = minimum_spanning_forest(&g.window(0, 2), "w", WeightAggregate::Latest);
+        assert_eq!(
+            forest,
+            vec![(2, 3, 1.0), (3, 1, 2.0), (5, 6, 3.0), (3, 4, 7.0)]
+        );
+
+        let view = minimum_spanning_forest_view(&g.window(0, 2), "w", WeightAggregate::Latest);
+        assert_eq!(view.num_vertices(), 6);
+        assert_eq!(view.num_edges(), 4);
+        assert!(!view.has_edge(1, 2, None));
+        assert_eq!(view.vertex(3).unwrap().degree(), 3);
+    }
+
+    #[test]
+    fn temporal_aggregates() {
+        let g = load(vec![
+            (1, 1, 2, 10),
+            (2, 1, 2, 1),
+            (1, 2, 3, 4),
+            (2, 2, 3, 4),
+            (1, 3, 1, 6),
+        ]);
+        let w = g.window(0, 3);
+
+        let total = |agg| {
+            minimum_spanning_forest(&w, "w", agg)
+                .iter()
+                .map(|(_, _, w)| w)
+                .sum::<f64>()
+        };
+
+        assert_eq!(total(WeightAggregate::Latest), 5.0);
+        assert_eq!(total(WeightAggregate::Min), 5.0);
+        assert_eq!(total(WeightAggregate::Max), 10.0);
+        assert_eq!(total(WeightAggregate::Sum), 14.0);
+        assert_eq!(total(WeightAggregate::Mean), 9.5);
+    }
+
+    #[test]
+    fn edges_without_weight_are_ignored() {
+        let g = load(vec![(1, 1, 2, 1)]);
+        g.add_edge(1, 2, 3, &vec![], None).unwrap();
+
+        let forest = minimum_spanning_forest(&g.window(0, 2), "w", WeightAggregate::Latest);
+        assert_eq!(forest, vec![(1, 2, 1.0)]);
+    }
+}
@@ -26,6 +26,7 @@
 //! println!("average_degree: {:?}", average_degree(&g));
 //! ```
 
+pub mod algorithm_result;
 pub mod clustering_coefficient;
 pub mod connected_components;
 pub mod degree;
@@ -33,15 +34,37 @@ pub mod directed_graph_density;
 pub mod hits;
 pub mod local_clustering_coefficient;
 pub mod local_triangle_count;
+pub mod minimum_spanning_forest;
 pub mod pagerank;
 pub mod reciprocity;
+pub mod temporal_cycles;
 pub mod triangle_count;
 pub mod triplet_count;
 
+use crate::core::Prop;
 use num_traits::{abs, Bounded, Zero};
+use serde::{Deserialize, Serialize};
 use std::ops::{Add, AddAssign, Div, Mul, Range, Sub};
 
-#[derive(PartialEq, PartialOrd, Copy, Clone, Debug)]
+/// Reads a numeric property as `f64`, returns `None` for non numeric properties.
+pub(crate) fn prop_as_f64(prop: Prop) -> Option<f64> {
+    match prop {
+        Prop::I32(v) => Some(v as f64),
+        Prop::I64(v) => Some(v as f64),
+        Prop::U32(v) => Some(v as f64),
+        Prop::U64(v) => Some(v as f64),
+        Prop::F32(v) => Some(v as f64),
+        Prop::F64(v) => Some(v),
+        Prop::Str(_)
+        | Prop::Bool(_)
+        | Prop::DTime(_)
+        | Prop::List(_)
+        | Prop::Map(_)
+        | Prop::Bytes(_) => None,
+    }
+}
+
+#[derive(PartialEq, PartialOrd, Copy, Clone, Debug, Serialize, Deserialize)]
 struct MulF32(f32);
 
 const MUL_F32_ZERO: MulF32 = MulF32(1.0f32);
@@ -108,7 +131,7 @@ impl Bounded for MulF32 {
     }
 }
 
-#[derive(PartialEq, PartialOrd, Copy, Clone, Debug)]
+#[derive(PartialEq, PartialOrd, Copy, Clone, Debug, Serialize, Deserialize)]
 struct SumF32(f32);
 
 impl Zero for SumF32 {
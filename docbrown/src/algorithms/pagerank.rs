@@ -1,14 +1,17 @@
+use crate::algorithms::algorithm_result::AlgorithmResult;
 use crate::algorithms::*;
 use crate::core::{
-    agg::{MaxDef, SumDef, ValDef},
+    agg::{MaxDef, StableSumDef, ValDef},
+    cancel::Budget,
     state::{
-        def::{max, sum, val},
+        def::{max, stable_sum, val},
         AccId,
     },
+    tgraph_shard::errors::GraphError,
 };
 use crate::db::{
     graph::Graph,
-    program::{AggRef, GlobalEvalState, LocalState, Program},
+    program::{AggRef, GlobalEvalState, LocalState, Program, Runner},
     view_api::GraphViewOps,
 };
 use num_traits::abs;
@@ -21,10 +24,10 @@ struct UnweightedPageRankS0 {
 }
 
 impl UnweightedPageRankS0 {
-    fn new(total_vertices: usize) -> Self {
+    fn new(total_vertices: usize, score: AccId<MulF32, MulF32, MulF32, ValDef<MulF32>>) -> Self {
         Self {
             total_vertices,
-            score: val(0),
+            score,
         }
     }
 }
@@ -52,15 +55,15 @@ impl Program for UnweightedPageRankS0 {
 
 struct UnweightedPageRankS1 {
     score: AccId<MulF32, MulF32, MulF32, ValDef<MulF32>>,
-    recv_score: AccId<SumF32, SumF32, SumF32, SumDef<SumF32>>,
+    recv_score: AccId<(f32, f32), f32, f32, StableSumDef<f32>>,
 }
 
 impl UnweightedPageRankS1 {
-    fn new() -> Self {
-        Self {
-            score: val(0),
-            recv_score: sum(1),
-        }
+    fn new(
+        score: AccId<MulF32, MulF32, MulF32, ValDef<MulF32>>,
+        recv_score: AccId<(f32, f32), f32, f32, StableSumDef<f32>>,
+    ) -> Self {
+        Self { score, recv_score }
     }
 }
 
@@ -76,7 +79,7 @@ impl Program for UnweightedPageRankS1 {
             if out_degree > 0 {
                 let new_score = s.read(&score).0 / out_degree as f32;
                 for t in s.neighbours_out() {
-                    t.update(&recv_score, SumF32(new_score))
+                    t.update(&recv_score, new_score)
                 }
             }
         });
@@ -97,16 +100,20 @@ impl Program for UnweightedPageRankS1 {
 
 struct UnweightedPageRankS2 {
     score: AccId<MulF32, MulF32, MulF32, ValDef<MulF32>>,
-    recv_score: AccId<SumF32, SumF32, SumF32, SumDef<SumF32>>,
+    recv_score: AccId<(f32, f32), f32, f32, StableSumDef<f32>>,
     max_diff: AccId<f32, f32, f32, MaxDef<f32>>,
 }
 
 impl UnweightedPageRankS2 {
-    fn new() -> Self {
+    fn new(
+        score: AccId<MulF32, MulF32, MulF32, ValDef<MulF32>>,
+        recv_score: AccId<(f32, f32), f32, f32, StableSumDef<f32>>,
+        max_diff: AccId<f32, f32, f32, MaxDef<f32>>,
+    ) -> Self {
         Self {
-            score: val(0),
-            recv_score: sum(1),
-            max_diff: max(2),
+            score,
+            recv_score,
+            max_diff,
         }
     }
 }
@@ -123,7 +130,7 @@ impl Program for UnweightedPageRankS2 {
         c.step(|s| {
             s.update(
                 &score,
-                MulF32((1f32 - damping_factor) + (damping_factor * s.read(&recv_score).0)),
+                MulF32((1f32 - damping_factor) + (damping_factor * s.read(&recv_score))),
             );
             let prev = s.read_prev(&score);
             let curr = s.read(&score);
@@ -146,39 +153,83 @@ impl Program for UnweightedPageRankS2 {
     }
 }
 
-#[allow(unused_variables)]
+/// Computes the PageRank score of every vertex of `g`, with every edge weighing the same.
+///
+/// Every iteration is one superstep. The scores are updated until no score changes by more than
+/// 0.01 in an iteration, or for at most `iter_count + 2` iterations.
 pub fn unweighted_page_rank(
     g: &Graph,
     window: Range<i64>,
     iter_count: usize,
-) -> FxHashMap<u64, f32> {
-    let mut c = GlobalEvalState::new(g.clone(), true);
-    let pg_s0 = UnweightedPageRankS0::new(g.num_vertices());
-    let pg_s1 = UnweightedPageRankS1::new();
-    let pg_s2 = UnweightedPageRankS2::new();
-
-    let max_diff = 0.01f32;
-    let mut i = 0;
-
-    pg_s0.run_step(g, &mut c);
+) -> AlgorithmResult<f32> {
+    unweighted_page_rank_with_budget(g, window, iter_count, &Budget::default())
+        .expect("an unlimited budget never runs out")
+}
 
-    loop {
-        pg_s1.run_step(g, &mut c);
-        println!("vec parts: {:?}", c.read_vec_partitions(&val::<MulF32>(0)));
+/// Runs `unweighted_page_rank`, stopping early if `budget` runs out.
+///
+/// # Errors
+///
+/// Returns `GraphError::Cancelled` or `GraphError::DeadlineExceeded` if the budget ran out.
+pub fn unweighted_page_rank_with_budget(
+    g: &Graph,
+    window: Range<i64>,
+    iter_count: usize,
+    budget: &Budget,
+) -> Result<AlgorithmResult<f32>, GraphError> {
+    page_rank(g, window, iter_count, budget, false)
+}
 
-        pg_s2.run_step(g, &mut c);
+/// Runs `unweighted_page_rank` with the scores merged in a fixed order, so the results are
+/// bit-identical across runs and thread counts, see `GlobalEvalState::deterministic`.
+pub fn unweighted_page_rank_deterministic(
+    g: &Graph,
+    window: Range<i64>,
+    iter_count: usize,
+) -> AlgorithmResult<f32> {
+    page_rank(g, window, iter_count, &Budget::default(), true)
+        .expect("an unlimited budget never runs out")
+}
 
-        let r = c.read_global_state(&max::<f32>(2)).unwrap();
-        println!("max_diff = {:?}", r);
+#[allow(unused_variables)]
+fn page_rank(
+    g: &Graph,
+    window: Range<i64>,
+    iter_count: usize,
+    budget: &Budget,
+    deterministic: bool,
+) -> Result<AlgorithmResult<f32>, GraphError> {
+    let mut c = GlobalEvalState::new(g.clone(), true).deterministic(deterministic);
+    let score = c
+        .accumulator("score", val::<MulF32>)
+        .expect("accumulators are registered once");
+    let recv_score = c
+        .accumulator("recv_score", stable_sum::<f32>)
+        .expect("accumulators are registered once");
+    let max_diff_acc = c
+        .accumulator("max_diff", max::<f32>)
+        .expect("accumulators are registered once");
+
+    let pg_s0 = UnweightedPageRankS0::new(g.num_vertices(), score);
+    let pg_s1 = UnweightedPageRankS1::new(score, recv_score);
+    let pg_s2 = UnweightedPageRankS2::new(score, recv_score, max_diff_acc);
 
-        if r <= max_diff || i > iter_count {
-            break;
-        }
+    let max_diff = 0.01f32;
 
-        if c.keep_past_state {
-            c.ss += 1;
-        }
-        i += 1;
+    let report = Runner::new()
+        .init(&pg_s0)
+        .step(&pg_s1)
+        .step(&pg_s2)
+        .converged_when(|c| {
+            c.read_global_state(&max_diff_acc)
+                .map_or(false, |r| r <= max_diff)
+        })
+        .max_supersteps(iter_count + 2)
+        .budget(budget)
+        .run(g, &mut c)?;
+
+    if let Some(err) = report.termination.error() {
+        return Err(err);
     }
 
     let mut results: FxHashMap<u64, f32> = FxHashMap::default();
@@ -186,13 +237,13 @@ pub fn unweighted_page_rank(
     (0..g.nr_shards)
         .into_iter()
         .fold(&mut results, |res, part_id| {
-            c.fold_state(&val::<MulF32>(0), part_id, res, |res, v_id, sc| {
+            c.fold_state(&score, part_id, res, |res, v_id, sc| {
                 res.insert(*v_id, sc.0);
                 res
             })
         });
 
-    results
+    Ok(AlgorithmResult::new(g, results))
 }
 
 #[cfg(test)]
@@ -253,6 +304,46 @@ mod page_rank_tests {
         test_page_rank(3);
     }
 
+    #[test]
+    fn test_page_rank_deterministic_across_thread_counts() {
+        let graph = Graph::new(4);
+        for i in 0..200u64 {
+            for j in [1, 7, 31] {
+                graph
+                    .add_edge(0, i, (i * j + 13) % 200, &vec![], None)
+                    .unwrap();
+            }
+        }
+
+        let run = |threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| unweighted_page_rank_deterministic(&graph, 0..10, 20))
+        };
+
+        let expected = run(1);
+        for threads in [2, 3, 8] {
+            let actual = run(threads);
+            // compare the bits, so a difference in the last place fails the test
+            for (v, score) in expected.get_all() {
+                assert_eq!(actual.get(*v).map(|s| s.to_bits()), Some(score.to_bits()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_page_rank_cancelled() {
+        let graph = load_graph(2);
+        let token = crate::core::cancel::CancellationToken::new();
+        token.cancel();
+
+        let budget = Budget::default().with_token(token);
+        let result = unweighted_page_rank_with_budget(&graph, 0..10, 20, &budget);
+        assert_eq!(result, Err(GraphError::Cancelled));
+    }
+
     #[test]
     #[ignore]
     fn test_page_rank_steps() {
@@ -260,14 +351,14 @@ mod page_rank_tests {
         let graph_2 = load_graph(2);
 
         let mut c_g1 = GlobalEvalState::new(graph_1.clone(), true);
-        let pg_s0_g1 = UnweightedPageRankS0::new(graph_1.num_vertices());
-        let pg_s1_g1 = UnweightedPageRankS1::new();
-        let pg_s2_g1 = UnweightedPageRankS2::new();
+        let pg_s0_g1 = UnweightedPageRankS0::new(graph_1.num_vertices(), val(0));
+        let pg_s1_g1 = UnweightedPageRankS1::new(val(0), stable_sum(1));
+        let pg_s2_g1 = UnweightedPageRankS2::new(val(0), stable_sum(1), max(2));
 
         let mut c_g2 = GlobalEvalState::new(graph_2.clone(), true);
-        let pg_s0_g2 = UnweightedPageRankS0::new(graph_2.num_vertices());
-        let pg_s1_g2 = UnweightedPageRankS1::new();
-        let pg_s2_g2 = UnweightedPageRankS2::new();
+        let pg_s0_g2 = UnweightedPageRankS0::new(graph_2.num_vertices(), val(0));
+        let pg_s1_g2 = UnweightedPageRankS1::new(val(0), stable_sum(1));
+        let pg_s2_g2 = UnweightedPageRankS2::new(val(0), stable_sum(1), max(2));
 
         // run step1 for graph1
         pg_s0_g1.run_step(&graph_1, &mut c_g1);
@@ -301,7 +392,7 @@ mod page_rank_tests {
         assert_partitions_data_equal_post_step(actual_g1_part0, actual_g2, true);
     }
 
-    fn lift_state<A: 'static, IN, OUT: StateType, ACC: Accumulator<A, IN, OUT>>(
+    fn lift_state<A: StateType, IN, OUT: StateType, ACC: Accumulator<A, IN, OUT>>(
         acc_id: AccId<A, IN, OUT, ACC>,
         c_g1: &GlobalEvalState<Graph>,
         c_g2: &GlobalEvalState<Graph>,
@@ -44,10 +44,12 @@
 //! println!("all_local_reciprocity: {:?}", all_local_reciprocity(&g));
 //! println!("global_reciprocity: {:?}", global_reciprocity(&g));
 //! ```
+use crate::algorithms::algorithm_result::AlgorithmResult;
 use crate::core::state;
 use crate::db::program::{EvalVertexView, GlobalEvalState, LocalState, Program};
 use crate::db::view_api::GraphViewOps;
-use std::collections::{HashMap, HashSet};
+use rustc_hash::FxHashMap;
+use std::collections::HashSet;
 
 struct GlobalReciprocity {}
 
@@ -112,7 +114,7 @@ impl Program for GlobalReciprocity {
 struct AllLocalReciprocity {}
 
 impl Program for AllLocalReciprocity {
-    type Out = HashMap<u64, f64>;
+    type Out = AlgorithmResult<f64>;
 
     fn local_eval<G: GraphViewOps>(&self, c: &LocalState<G>) {
         let min = c.agg(state::def::sum(0));
@@ -139,7 +141,7 @@ impl Program for AllLocalReciprocity {
     {
         let agg = state::def::sum::<f64>(0);
 
-        let mut results: HashMap<u64, f64> = HashMap::default();
+        let mut results: FxHashMap<u64, f64> = FxHashMap::default();
 
         (0..g.num_shards())
             .into_iter()
@@ -150,7 +152,7 @@ impl Program for AllLocalReciprocity {
                 })
             });
 
-        results
+        AlgorithmResult::new(g, results)
     }
 }
 
@@ -162,8 +164,8 @@ pub fn global_reciprocity<G: GraphViewOps>(g: &G) -> f64 {
     gr.produce_output(g, &gs)
 }
 
-/// returns the reciprocity of every vertex in the graph as a tuple of
-pub fn all_local_reciprocity<G: GraphViewOps>(g: &G) -> HashMap<u64, f64> {
+/// returns the reciprocity of every vertex in the graph
+pub fn all_local_reciprocity<G: GraphViewOps>(g: &G) -> AlgorithmResult<f64> {
     let mut gs = GlobalEvalState::new(g.clone(), false);
     let gr = AllLocalReciprocity {};
     gr.run_step(g, &mut gs);
@@ -205,6 +207,9 @@ mod reciprocity_test {
         let map_names_by_id: HashMap<u64, f64> = expected_vec.iter().map(|x| (x.0, x.1)).collect();
 
         let actual = all_local_reciprocity(&graph);
-        assert_eq!(actual, map_names_by_id);
+        assert_eq!(
+            actual.into_iter().collect::<HashMap<_, _>>(),
+            map_names_by_id
+        );
     }
 }
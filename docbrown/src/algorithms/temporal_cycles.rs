@@ -0,0 +1,277 @@
+//! Enumerates simple temporal cycles, i.e. closed paths whose edge updates happen at strictly
+//! increasing times. This is the typical shape of money being round-tripped in a transaction graph.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use docbrown::algorithms::temporal_cycles::temporal_cycles;
+//! use docbrown::core::Prop;
+//! use docbrown::db::graph::Graph;
+//! use docbrown::db::view_api::*;
+//!
+//! let g = Graph::new(2);
+//! let transfers = vec![(1, 1, 2, 100), (2, 2, 3, 95), (3, 3, 1, 90), (4, 3, 1, 10)];
+//!
+//! for (t, src, dst, amount) in transfers {
+//!     g.add_edge(t, src, dst, &vec![("amount".to_string(), Prop::U64(amount))], None)
+//!         .unwrap();
+//! }
+//!
+//! let cycles = temporal_cycles(&g.window(0, 10), 3, None, None);
+//! assert_eq!(cycles.len(), 2);
+//!
+//! // every hop has to forward at least 90% of the amount it received
+//! let cycles = temporal_cycles(&g.window(0, 10), 3, None, Some(("amount", 0.9)));
+//! assert_eq!(cycles, vec![vec![(1, 2, 1), (2, 3, 2), (3, 1, 3)]]);
+//! ```
+
+use crate::algorithms::prop_as_f64;
+use crate::core::{cancel::Budget, tgraph_shard::errors::GraphError};
+use crate::db::view_api::*;
+use rayon::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// A temporal cycle as the list of its hops `(src, dst, time)`, starting with the earliest hop.
+pub type TemporalCycle = Vec<(u64, u64, i64)>;
+
+/// A single update of an out-edge: destination, time and amount if requested.
+#[derive(Debug, Clone, Copy)]
+struct Hop {
+    dst: u64,
+    t: i64,
+    amount: Option<f64>,
+}
+
+struct CycleSearch<'a> {
+    out_hops: &'a FxHashMap<u64, Vec<Hop>>,
+    max_length: usize,
+    max_span: Option<i64>,
+    min_ratio: Option<f64>,
+    budget: &'a Budget,
+}
+
+impl<'a> CycleSearch<'a> {
+    fn cycles_from(&self, start: u64) -> Result<Vec<TemporalCycle>, GraphError> {
+        let mut cycles = vec![];
+        let mut path: Vec<(u64, Hop)> = vec![];
+        let mut on_path = FxHashSet::default();
+        on_path.insert(start);
+        self.extend(start, start, &mut path, &mut on_path, &mut cycles)?;
+        Ok(cycles)
+    }
+
+    fn extend(
+        &self,
+        start: u64,
+        v: u64,
+        path: &mut Vec<(u64, Hop)>,
+        on_path: &mut FxHashSet<u64>,
+        cycles: &mut Vec<TemporalCycle>,
+    ) -> Result<(), GraphError> {
+        // the search can blow up on dense graphs, so every step checks the budget
+        self.budget.check()?;
+
+        let hops = match self.out_hops.get(&v) {
+            Some(hops) => hops,
+            None => return Ok(()),
+        };
+
+        // hops are sorted by time, so we only look at the ones after the last hop on the path
+        let last = path.last().map(|(_, hop)| *hop);
+        let first = match last {
+            Some(last) => hops.partition_point(|hop| hop.t <= last.t),
+            None => 0,
+        };
+
+        for hop in &hops[first..] {
+            if let (Some(span), Some((_, first_hop))) = (self.max_span, path.first()) {
+                if hop.t - first_hop.t > span {
+                    break;
+                }
+            }
+
+            if let (Some(ratio), Some(last)) = (self.min_ratio, last) {
+                match (hop.amount, last.amount) {
+                    (Some(amount), Some(prev)) if amount >= ratio * prev => {}
+                    _ => continue,
+                }
+            }
+
+            if self.min_ratio.is_some() && hop.amount.is_none() {
+                continue;
+            }
+
+            if hop.dst == start {
+                if !path.is_empty() {
+                    cycles.push(
+                        path.iter()
+                            .map(|(src, hop)| (*src, hop.dst, hop.t))
+                            .chain(std::iter::once((v, hop.dst, hop.t)))
+                            .collect(),
+                    );
+                }
+            } else if path.len() + 1 < self.max_length && !on_path.contains(&hop.dst) {
+                path.push((v, *hop));
+                on_path.insert(hop.dst);
+                self.extend(start, hop.dst, path, on_path, cycles)?;
+                on_path.remove(&hop.dst);
+                path.pop();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Enumerates all simple cycles of at most `max_length` hops whose hops happen at strictly
+/// increasing times. Every update of an edge is treated as a separate hop, so the same static
+/// cycle can be reported several times with different timestamps.
+///
+/// # Arguments
+///
+/// * `g` - A reference to the graph view, usually a `WindowedGraph`
+/// * `max_length` - The maximum number of hops in a cycle
+/// * `max_span` - An optional maximum time between the first and the last hop of a cycle
+/// * `amount` - An optional `(property, ratio)` pair, every hop must carry at least `ratio`
+///   times the value of `property` on the previous hop. Hops without a numeric value for the
+///   property are ignored when this is set.
+///
+/// # Returns
+///
+/// The cycles as lists of `(src, dst, time)` hops, each starting with its earliest hop and with
+/// self-loops excluded.
+pub fn temporal_cycles<G: GraphViewOps>(
+    g: &G,
+    max_length: usize,
+    max_span: Option<i64>,
+    amount: Option<(&str, f64)>,
+) -> Vec<TemporalCycle> {
+    temporal_cycles_with_budget(g, max_length, max_span, amount, &Budget::default())
+        .expect("an unlimited budget never runs out")
+}
+
+/// Enumerates the cycles like `temporal_cycles`, stopping once `budget` runs out.
+///
+/// # Errors
+///
+/// Returns `GraphError::Cancelled` or `GraphError::DeadlineExceeded` if the budget ran out.
+pub fn temporal_cycles_with_budget<G: GraphViewOps>(
+    g: &G,
+    max_length: usize,
+    max_span: Option<i64>,
+    amount: Option<(&str, f64)>,
+    budget: &Budget,
+) -> Result<Vec<TemporalCycle>, GraphError> {
+    let out_hops: FxHashMap<u64, Vec<Hop>> = g
+        .vertices()
+        .iter()
+        .map(|v| {
+            let mut hops = v
+                .out_edges()
+                .flat_map(|e| e.explode())
+                .filter_map(|e| {
+                    Some(Hop {
+                        dst: e.dst().id(),
+                        t: e.time()?,
+                        amount: amount.and_then(|(name, _)| {
+                            e.property(name.to_string(), false).and_then(prop_as_f64)
+                        }),
+                    })
+                })
+                .collect::<Vec<_>>();
+            hops.sort_by_key(|hop| hop.t);
+            (v.id(), hops)
+        })
+        .collect();
+
+    let search = CycleSearch {
+        out_hops: &out_hops,
+        max_length,
+        max_span,
+        min_ratio: amount.map(|(_, ratio)| ratio),
+        budget,
+    };
+
+    let mut cycles = out_hops
+        .keys()
+        .collect::<Vec<_>>()
+        .par_iter()
+        .map(|v| search.cycles_from(**v))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    cycles.sort();
+    Ok(cycles)
+}
+
+#[cfg(test)]
+mod temporal_cycles_tests {
+    use super::*;
+    use crate::core::Prop;
+    use crate::db::graph::Graph;
+
+    fn load(edges: Vec<(i64, u64, u64, u64)>) -> Graph {
+        let g = Graph::new(2);
+        for (t, src, dst, amount) in edges {
+            g.add_edge(
+                t,
+                src,
+                dst,
+                &vec![("amount".to_string(), Prop::U64(amount))],
+                None,
+            )
+            .unwrap();
+        }
+        g
+    }
+
+    #[test]
+    fn only_time_respecting_cycles_are_found() {
+        let g = load(vec![
+            (1, 1, 2, 10),
+            (2, 2, 3, 10),
+            (3, 3, 1, 10),
+            // goes back in time so 4 -> 5 -> 6 -> 4 is not a temporal cycle
+            (5, 4, 5, 10),
+            (4, 5, 6, 10),
+            (6, 6, 4, 10),
+            // self loops are not reported
+            (7, 7, 7, 10),
+        ]);
+
+        let cycles = temporal_cycles(&g.window(0, 10), 5, None, None);
+        assert_eq!(cycles, vec![vec![(1, 2, 1), (2, 3, 2), (3, 1, 3)]]);
+    }
+
+    #[test]
+    fn length_and_span_limits() {
+        let g = load(vec![
+            (1, 1, 2, 10),
+            (2, 2, 1, 10),
+            (3, 2, 3, 10),
+            (4, 3, 4, 10),
+            (20, 4, 1, 10),
+        ]);
+
+        let w = g.window(0, 30);
+        assert_eq!(temporal_cycles(&w, 4, None, None).len(), 2);
+        assert_eq!(
+            temporal_cycles(&w, 3, None, None),
+            vec![vec![(1, 2, 1), (2, 1, 2)]]
+        );
+        assert_eq!(temporal_cycles(&w, 4, Some(10), None).len(), 1);
+
+        // the window cuts off the closing hop
+        assert_eq!(temporal_cycles(&g.window(0, 20), 4, None, None).len(), 1);
+    }
+
+    #[test]
+    fn amount_ratio_is_checked_on_every_hop() {
+        let g = load(vec![(1, 1, 2, 100), (2, 2, 3, 80), (3, 3, 1, 79)]);
+
+        let w = g.window(0, 10);
+        assert_eq!(temporal_cycles(&w, 3, None, Some(("amount", 0.8))).len(), 1);
+        assert!(temporal_cycles(&w, 3, None, Some(("amount", 0.9))).is_empty());
+        assert!(temporal_cycles(&w, 3, None, Some(("missing", 0.1))).is_empty());
+    }
+}
@@ -1,12 +1,11 @@
-use crate::core::{state, tgraph_shard::errors::GraphError};
+use crate::algorithms::algorithm_result::AlgorithmResult;
+use crate::core::{cancel::Budget, state, tgraph_shard::errors::GraphError};
 use crate::db::{
-    graph::Graph,
     program::{GlobalEvalState, LocalState, Program},
     view_api::*,
 };
 use itertools::Itertools;
-use rayon::prelude::*;
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 pub fn local_triangle_count<G: GraphViewOps>(graph: &G, v: u64) -> Result<usize, GraphError> {
     let vertex = graph.vertex(v).unwrap();
@@ -34,31 +33,21 @@ pub fn local_triangle_count<G: GraphViewOps>(graph: &G, v: u64) -> Result<usize,
     Ok(count)
 }
 
+/// Counts the triangles of `graph`, see `triangle_count`.
 pub fn global_triangle_count<G: GraphViewOps>(graph: &G) -> Result<usize, GraphError> {
-    let r: Result<Vec<_>, GraphError> = graph
-        .vertices()
-        .into_iter()
-        .par_bridge()
-        .map(|v| {
-            let r: Result<Vec<_>, _> = v
-                .neighbours()
-                .id()
-                .into_iter()
-                .combinations(2)
-                .filter_map(|nb| match graph.has_edge(nb[0], nb[1], None) {
-                    true => Some(Ok(nb)),
-                    false => match graph.has_edge(nb[1], nb[0], None) {
-                        true => Some(Ok(nb)),
-                        false => None,
-                    },
-                })
-                .collect();
-            r.map(|t| t.len())
-        })
-        .collect();
+    global_triangle_count_with_budget(graph, &Budget::default())
+}
 
-    let count: usize = r?.into_iter().sum();
-    Ok(count / 3)
+/// Counts the triangles like `global_triangle_count`, stopping once `budget` runs out.
+///
+/// # Errors
+///
+/// Returns `GraphError::Cancelled` or `GraphError::DeadlineExceeded` if the budget ran out.
+pub fn global_triangle_count_with_budget<G: GraphViewOps>(
+    graph: &G,
+    budget: &Budget,
+) -> Result<usize, GraphError> {
+    triangle_count_with_budget(graph, None, budget).map(|counts| counts.global)
 }
 
 pub struct TriangleCountS1 {}
@@ -91,17 +80,15 @@ impl Program for TriangleCountS1 {
     }
 }
 
-/// Computes the number of triangles in a graph using a fast algorithm
+/// Computes the number of triangles in a graph, see `triangle_count`.
 ///
 /// # Arguments
 ///
 /// * `g` - A reference to the graph
-/// * `window` - A range indicating the temporal window to consider
 ///
 /// # Returns
 ///
-/// An optional integer containing the number of triangles in the graph. If the computation failed,
-/// the function returns `None`.
+/// The number of triangles in the graph, always `Some`.
 ///
 /// # Example
 /// ```rust
@@ -135,67 +122,8 @@ impl Program for TriangleCountS1 {
 /// let actual_tri_count = triangle_counting_fast(&graph);
 /// ```
 ///
-pub fn triangle_counting_fast(g: &Graph) -> Option<usize> {
-    let mut gs = GlobalEvalState::new(g.clone(), false);
-    let tc = TriangleCountS1 {};
-
-    tc.run_step(g, &mut gs);
-
-    let tc = TriangleCountS2 {};
-
-    tc.run_step(g, &mut gs);
-
-    tc.produce_output(g, &gs)
-}
-
-pub struct TriangleCountS2 {}
-
-impl Program for TriangleCountS2 {
-    type Out = Option<usize>;
-    fn local_eval<G: GraphViewOps>(&self, c: &LocalState<G>) {
-        let neighbors_set = c.agg(state::def::hash_set::<u64>(0));
-        let count = c.global_agg(state::def::sum::<usize>(1));
-
-        c.step(|s| {
-            for t in s.neighbours() {
-                if s.global_id() > t.global_id() {
-                    let intersection_count = {
-                        // when using entry() we need to make sure the reference is released before we can update the state, otherwise we break the Rc<RefCell<_>> invariant
-                        // where there can either be one mutable or many immutable references
-
-                        match (
-                            s.entry(&neighbors_set)
-                                .read_ref()
-                                .unwrap_or(&FxHashSet::default()),
-                            t.entry(&neighbors_set)
-                                .read_ref()
-                                .unwrap_or(&FxHashSet::default()),
-                        ) {
-                            (s_set, t_set) => {
-                                let intersection = s_set.intersection(t_set);
-                                intersection.count()
-                            }
-                        }
-                    };
-
-                    s.global_update(&count, intersection_count);
-                }
-            }
-        });
-    }
-
-    fn post_eval<G: GraphViewOps>(&self, c: &mut GlobalEvalState<G>) {
-        let _ = c.global_agg(state::def::sum::<usize>(1));
-        c.step(|_| false)
-    }
-
-    #[allow(unused_variables)]
-    fn produce_output<G: GraphViewOps>(&self, g: &G, gs: &GlobalEvalState<G>) -> Self::Out
-    where
-        Self: Sync,
-    {
-        gs.read_global_state(&state::def::sum::<usize>(1))
-    }
+pub fn triangle_counting_fast<G: GraphViewOps>(g: &G) -> Option<usize> {
+    Some(triangle_count(g, None).global)
 }
 
 pub struct TriangleCountSlowS2 {}
@@ -246,9 +174,228 @@ impl Program for TriangleCountSlowS2 {
     }
 }
 
+/// The result of [`triangle_count`]: the number of triangles in the graph and the number of
+/// triangles each vertex participates in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriangleCounts {
+    /// Total number of triangles in the graph
+    pub global: usize,
+    /// Number of triangles each vertex is part of
+    pub local: AlgorithmResult<usize>,
+}
+
+/// Counts the triangles of any graph view using the distributed `Program` engine.
+///
+/// Edges are treated as undirected. If `delta` is set, a triangle is only counted when its three
+/// edges have updates with timestamps that all lie within `delta` of each other.
+///
+/// # Arguments
+///
+/// * `g` - A reference to the graph view
+/// * `delta` - An optional maximum time span between the updates of the three edges
+///
+/// # Returns
+///
+/// A `TriangleCounts` holding the global triangle count and the per-vertex counts. Every vertex
+/// in the view is present in the per-vertex counts, vertices with no triangles map to 0.
+///
+/// # Example
+/// ```rust
+/// use docbrown::db::graph::Graph;
+/// use docbrown::db::view_api::*;
+/// use docbrown::algorithms::triangle_count::triangle_count;
+///
+/// let graph = Graph::new(2);
+///
+/// let edges = vec![(1, 2, 1), (2, 3, 2), (3, 1, 10)];
+///
+/// for (src, dst, ts) in edges {
+///     graph.add_edge(ts, src, dst, &vec![], None).unwrap();
+/// }
+///
+/// let counts = triangle_count(&graph.window(0, 20), None);
+/// assert_eq!(counts.global, 1);
+/// assert_eq!(counts.local.get(1), Some(&1));
+///
+/// // the edges of the triangle are 9 time units apart
+/// let counts = triangle_count(&graph.window(0, 20), Some(5));
+/// assert_eq!(counts.global, 0);
+/// ```
+pub fn triangle_count<G: GraphViewOps>(g: &G, delta: Option<i64>) -> TriangleCounts {
+    triangle_count_with_budget(g, delta, &Budget::default())
+        .expect("an unlimited budget never runs out")
+}
+
+/// Runs `triangle_count`, stopping before each of its steps once `budget` runs out.
+///
+/// # Errors
+///
+/// Returns `GraphError::Cancelled` or `GraphError::DeadlineExceeded` if the budget ran out.
+pub fn triangle_count_with_budget<G: GraphViewOps>(
+    g: &G,
+    delta: Option<i64>,
+    budget: &Budget,
+) -> Result<TriangleCounts, GraphError> {
+    let mut gs = GlobalEvalState::new(g.clone(), false);
+
+    budget.check()?;
+    let tc = TriangleCountS1 {};
+    tc.run_step(g, &mut gs);
+
+    budget.check()?;
+    let tc = TemporalTriangleCountS2 { delta };
+    tc.run_step(g, &mut gs);
+
+    Ok(tc.produce_output(g, &gs))
+}
+
+/// Second step of [`triangle_count`], closes the wedges collected by `TriangleCountS1` and
+/// accumulates the global count and the count of every vertex in the triangle.
+pub struct TemporalTriangleCountS2 {
+    pub delta: Option<i64>,
+}
+
+impl Program for TemporalTriangleCountS2 {
+    type Out = TriangleCounts;
+
+    fn local_eval<G: GraphViewOps>(&self, c: &LocalState<G>) {
+        let neighbors_set = c.agg(state::def::hash_set::<u64>(0));
+        let count = c.global_agg(state::def::sum::<usize>(1));
+        let vertex_count = c.agg(state::def::sum::<usize>(2));
+
+        c.step(|s| {
+            // the third vertex of each triangle is only reachable as a neighbour of s,
+            // so we record how many triangles it closes and update it at the end
+            let mut closing_counts: FxHashMap<u64, usize> = FxHashMap::default();
+
+            for t in s.neighbours() {
+                if s.global_id() > t.global_id() {
+                    let closing = {
+                        // the entry references need to be released before we update the state
+                        let s_entry = s.entry(&neighbors_set);
+                        let t_entry = t.entry(&neighbors_set);
+                        let empty = FxHashSet::default();
+                        let s_set = s_entry.read_ref().unwrap_or(&empty);
+                        let t_set = t_entry.read_ref().unwrap_or(&empty);
+                        s_set.intersection(t_set).copied().collect_vec()
+                    };
+
+                    let closing = match self.delta {
+                        None => closing,
+                        Some(delta) => {
+                            let g = s.graph();
+                            let st = undirected_edge_history(g, s.global_id(), t.global_id());
+                            closing
+                                .into_iter()
+                                .filter(|w| {
+                                    within_delta(
+                                        &st,
+                                        &undirected_edge_history(g, s.global_id(), *w),
+                                        &undirected_edge_history(g, t.global_id(), *w),
+                                        delta,
+                                    )
+                                })
+                                .collect_vec()
+                        }
+                    };
+
+                    if !closing.is_empty() {
+                        s.global_update(&count, closing.len());
+                        s.update(&vertex_count, closing.len());
+                        t.update(&vertex_count, closing.len());
+                        for w in closing {
+                            *closing_counts.entry(w).or_default() += 1;
+                        }
+                    }
+                }
+            }
+
+            if !closing_counts.is_empty() {
+                for w in s.neighbours() {
+                    if let Some(n) = closing_counts.remove(&w.global_id()) {
+                        w.update(&vertex_count, n);
+                    }
+                }
+            }
+        });
+    }
+
+    fn post_eval<G: GraphViewOps>(&self, c: &mut GlobalEvalState<G>) {
+        let _ = c.global_agg(state::def::sum::<usize>(1));
+        let _ = c.agg(state::def::sum::<usize>(2));
+        c.step(|_| false)
+    }
+
+    fn produce_output<G: GraphViewOps>(&self, g: &G, gs: &GlobalEvalState<G>) -> Self::Out
+    where
+        Self: Sync,
+    {
+        let agg = state::def::sum::<usize>(2);
+
+        let mut local: FxHashMap<u64, usize> = g.vertices().id().map(|v| (v, 0)).collect();
+
+        (0..g.num_shards()).fold(&mut local, |res, part_id| {
+            gs.fold_state(&agg, part_id, res, |res, v_id, count| {
+                res.insert(*v_id, count);
+                res
+            })
+        });
+
+        TriangleCounts {
+            global: gs
+                .read_global_state(&state::def::sum::<usize>(1))
+                .unwrap_or(0),
+            local: AlgorithmResult::new(g, local),
+        }
+    }
+}
+
+/// Timestamps of all the updates between `a` and `b` in either direction, in every layer like
+/// the neighbours the triangles are found with.
+fn undirected_edge_history<G: GraphViewOps>(g: &G, a: u64, b: u64) -> Vec<i64> {
+    // out edges explode into their updates, in edges from another shard don't
+    let out_edges = |src: u64, dst: u64| {
+        g.vertex(src)
+            .into_iter()
+            .flat_map(move |v| v.out_edges().filter(move |e| e.dst().id() == dst))
+    };
+    out_edges(a, b)
+        .chain(out_edges(b, a))
+        .flat_map(|e| e.explode())
+        .flat_map(|e| e.time())
+        .collect()
+}
+
+/// Checks if there is a time span of at most `delta` containing one timestamp from each of `a`,
+/// `b` and `c`.
+fn within_delta(a: &[i64], b: &[i64], c: &[i64], delta: i64) -> bool {
+    let mut events = a
+        .iter()
+        .map(|t| (*t, 0))
+        .chain(b.iter().map(|t| (*t, 1)))
+        .chain(c.iter().map(|t| (*t, 2)))
+        .collect_vec();
+    events.sort_unstable();
+
+    let mut seen = [0usize; 3];
+    let mut start = 0;
+    for &(t, edge) in &events {
+        seen[edge] += 1;
+        while t - events[start].0 > delta {
+            seen[events[start].1] -= 1;
+            start += 1;
+        }
+        if seen.iter().all(|n| *n > 0) {
+            return true;
+        }
+    }
+    false
+}
+
 #[cfg(test)]
 mod triangle_count_tests {
     use super::*;
+    use crate::core::cancel::CancellationToken;
     use crate::db::graph::Graph;
 
     #[test]
@@ -480,4 +627,122 @@ mod triangle_count_tests {
 
         assert_eq!(actual_tri_count, Some(8))
     }
+
+    #[test]
+    fn triangle_count_per_vertex_on_window() {
+        let graph = Graph::new(3);
+
+        let edges = vec![
+            (1, 2, 1),
+            (2, 3, 2),
+            (3, 1, 3),
+            (3, 4, 4),
+            (4, 1, 5),
+            (4, 5, 20),
+            (5, 1, 21),
+        ];
+
+        for (src, dst, ts) in edges {
+            graph.add_edge(ts, src, dst, &vec![], None).unwrap();
+        }
+
+        let counts = triangle_count(&graph.window(0, 10), None);
+
+        assert_eq!(counts.global, 2);
+        let expected: FxHashMap<u64, usize> =
+            FxHashMap::from_iter([(1, 2), (2, 1), (3, 2), (4, 1)]);
+        assert_eq!(*counts.local.get_all(), expected);
+
+        let counts = triangle_count(&graph.window(0, 25), None);
+        assert_eq!(counts.global, 3);
+        assert_eq!(counts.local.get(5), Some(&1));
+        assert_eq!(
+            counts.global,
+            triangle_counting_fast(&graph.window(0, 25)).unwrap()
+        );
+    }
+
+    #[test]
+    fn temporal_triangle_count_with_delta() {
+        let graph = Graph::new(2);
+
+        let edges = vec![
+            // closes within 2 time units
+            (1, 2, 1),
+            (2, 3, 2),
+            (3, 1, 3),
+            // closes after 10 time units
+            (4, 5, 1),
+            (5, 6, 5),
+            (6, 4, 11),
+            // a later update on an edge brings it within range
+            (7, 8, 1),
+            (8, 9, 20),
+            (9, 7, 21),
+            (8, 7, 19),
+        ];
+
+        for (src, dst, ts) in edges {
+            graph.add_edge(ts, src, dst, &vec![], None).unwrap();
+        }
+
+        let window = graph.window(0, 30);
+
+        assert_eq!(triangle_count(&window, None).global, 3);
+        assert_eq!(triangle_count(&window, Some(10)).global, 3);
+
+        let counts = triangle_count(&window, Some(2));
+        assert_eq!(counts.global, 2);
+        assert_eq!(counts.local.get(1), Some(&1));
+        assert_eq!(counts.local.get(4), Some(&0));
+        assert_eq!(counts.local.get(9), Some(&1));
+    }
+
+    #[test]
+    fn delta_mode_sees_the_edges_of_every_layer() {
+        let graph = Graph::new(2);
+        graph.add_edge(1, 1, 2, &vec![], None).unwrap();
+        graph.add_edge(2, 2, 3, &vec![], Some("friends")).unwrap();
+        graph
+            .add_edge(3, 3, 1, &vec![], Some("colleagues"))
+            .unwrap();
+        // only the default layer edge is within range of the others
+        graph.add_edge(20, 3, 1, &vec![], None).unwrap();
+
+        assert_eq!(triangle_count(&graph, None).global, 1);
+        assert_eq!(triangle_count(&graph, Some(2)).global, 1);
+        assert_eq!(
+            triangle_count(&graph.layer("friends").unwrap(), Some(2)).global,
+            0
+        );
+    }
+
+    #[test]
+    fn delta_window_requires_all_three_edges() {
+        assert!(within_delta(&[1, 10], &[12], &[11], 2));
+        assert!(!within_delta(&[1, 10], &[12], &[11], 1));
+        assert!(!within_delta(&[1], &[3], &[5], 3));
+        assert!(within_delta(&[1], &[3], &[5], 4));
+        assert!(!within_delta(&[], &[3], &[5], 4));
+    }
+
+    #[test]
+    fn global_counts_are_the_triangle_count() {
+        let graph = Graph::new(3);
+        for (src, dst, ts) in [(1, 2, 1), (2, 3, 2), (3, 1, 3), (3, 4, 4), (4, 1, 5)] {
+            graph.add_edge(ts, src, dst, &vec![], None).unwrap();
+        }
+
+        assert_eq!(triangle_count(&graph, None).global, 2);
+        assert_eq!(global_triangle_count(&graph), Ok(2));
+        assert_eq!(triangle_counting_fast(&graph), Some(2));
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let budget = Budget::default().with_token(token);
+        assert_eq!(
+            global_triangle_count_with_budget(&graph, &budget),
+            Err(GraphError::Cancelled)
+        );
+    }
 }
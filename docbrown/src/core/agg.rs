@@ -5,7 +5,7 @@ use std::{
     ops::{AddAssign, Div},
 };
 
-use num_traits::{Bounded, Zero};
+use num_traits::{Bounded, Float, Zero};
 
 use crate::core::state::StateType;
 
@@ -101,6 +101,42 @@ where
     }
 }
 
+/// Sums floating point values with compensated (Neumaier) summation. The state is the sum and
+/// the error of its rounding, so the result depends far less on the order the values are added
+/// and merged in than with `SumDef`.
+#[derive(Clone, Debug, Copy)]
+pub struct StableSumDef<A: StateType + Float> {
+    _marker: PhantomData<A>,
+}
+
+impl<A> Accumulator<(A, A), A, A> for StableSumDef<A>
+where
+    A: StateType + Float,
+{
+    fn zero() -> (A, A) {
+        (A::zero(), A::zero())
+    }
+
+    fn add0((sum, error): &mut (A, A), a: A) {
+        let t = *sum + a;
+        if sum.abs() >= a.abs() {
+            *error = *error + ((*sum - t) + a);
+        } else {
+            *error = *error + ((a - t) + *sum);
+        }
+        *sum = t;
+    }
+
+    fn combine(a1: &mut (A, A), a2: &(A, A)) {
+        Self::add0(a1, a2.0);
+        a1.1 = a1.1 + a2.1;
+    }
+
+    fn finish((sum, error): &(A, A)) -> A {
+        *sum + *error
+    }
+}
+
 #[derive(Clone, Debug, Copy)]
 pub struct ValDef<A: StateType + Zero> {
     _marker: PhantomData<A>,
@@ -157,6 +193,161 @@ where
     }
 }
 
+/// Counts the inputs.
+#[derive(Clone, Debug, Copy)]
+pub struct CountDef<A: StateType> {
+    _marker: PhantomData<A>,
+}
+
+impl<A> Accumulator<usize, A, usize> for CountDef<A>
+where
+    A: StateType,
+{
+    fn zero() -> usize {
+        0
+    }
+
+    fn add0(a1: &mut usize, _a: A) {
+        *a1 += 1;
+    }
+
+    fn combine(a1: &mut usize, a2: &usize) {
+        *a1 += a2;
+    }
+
+    fn finish(a: &usize) -> usize {
+        *a
+    }
+}
+
+/// Keeps the `(key, payload)` pair with the smallest key. Ties are broken by the smallest payload
+/// so the result does not depend on the order the inputs arrive in.
+#[derive(Clone, Debug, Copy)]
+pub struct ArgMinDef<K: StateType + PartialOrd, V: StateType + PartialOrd> {
+    _marker: PhantomData<(K, V)>,
+}
+
+impl<K, V> Accumulator<Option<(K, V)>, (K, V), Option<(K, V)>> for ArgMinDef<K, V>
+where
+    K: StateType + PartialOrd,
+    V: StateType + PartialOrd,
+{
+    fn zero() -> Option<(K, V)> {
+        None
+    }
+
+    fn add0(a1: &mut Option<(K, V)>, a: (K, V)) {
+        let replace = match a1 {
+            None => true,
+            Some((k, v)) => a.0 < *k || (a.0 == *k && a.1 < *v),
+        };
+        if replace {
+            *a1 = Some(a);
+        }
+    }
+
+    fn combine(a1: &mut Option<(K, V)>, a2: &Option<(K, V)>) {
+        if let Some(a) = a2 {
+            Self::add0(a1, a.clone());
+        }
+    }
+
+    fn finish(a: &Option<(K, V)>) -> Option<(K, V)> {
+        a.clone()
+    }
+}
+
+/// Keeps the `(key, payload)` pair with the largest key. Ties are broken by the smallest payload
+/// so the result does not depend on the order the inputs arrive in.
+#[derive(Clone, Debug, Copy)]
+pub struct ArgMaxDef<K: StateType + PartialOrd, V: StateType + PartialOrd> {
+    _marker: PhantomData<(K, V)>,
+}
+
+impl<K, V> Accumulator<Option<(K, V)>, (K, V), Option<(K, V)>> for ArgMaxDef<K, V>
+where
+    K: StateType + PartialOrd,
+    V: StateType + PartialOrd,
+{
+    fn zero() -> Option<(K, V)> {
+        None
+    }
+
+    fn add0(a1: &mut Option<(K, V)>, a: (K, V)) {
+        let replace = match a1 {
+            None => true,
+            Some((k, v)) => a.0 > *k || (a.0 == *k && a.1 < *v),
+        };
+        if replace {
+            *a1 = Some(a);
+        }
+    }
+
+    fn combine(a1: &mut Option<(K, V)>, a2: &Option<(K, V)>) {
+        if let Some(a) = a2 {
+            Self::add0(a1, a.clone());
+        }
+    }
+
+    fn finish(a: &Option<(K, V)>) -> Option<(K, V)> {
+        a.clone()
+    }
+}
+
+/// Keeps the value of the earliest `(time, value)` input.
+#[derive(Clone, Debug, Copy)]
+pub struct FirstDef<A: StateType + PartialOrd> {
+    _marker: PhantomData<A>,
+}
+
+impl<A> Accumulator<Option<(i64, A)>, (i64, A), Option<A>> for FirstDef<A>
+where
+    A: StateType + PartialOrd,
+{
+    fn zero() -> Option<(i64, A)> {
+        None
+    }
+
+    fn add0(a1: &mut Option<(i64, A)>, a: (i64, A)) {
+        <ArgMinDef<i64, A> as Accumulator<_, _, _>>::add0(a1, a)
+    }
+
+    fn combine(a1: &mut Option<(i64, A)>, a2: &Option<(i64, A)>) {
+        <ArgMinDef<i64, A> as Accumulator<_, _, _>>::combine(a1, a2)
+    }
+
+    fn finish(a: &Option<(i64, A)>) -> Option<A> {
+        a.as_ref().map(|(_, v)| v.clone())
+    }
+}
+
+/// Keeps the value of the latest `(time, value)` input.
+#[derive(Clone, Debug, Copy)]
+pub struct LastDef<A: StateType + PartialOrd> {
+    _marker: PhantomData<A>,
+}
+
+impl<A> Accumulator<Option<(i64, A)>, (i64, A), Option<A>> for LastDef<A>
+where
+    A: StateType + PartialOrd,
+{
+    fn zero() -> Option<(i64, A)> {
+        None
+    }
+
+    fn add0(a1: &mut Option<(i64, A)>, a: (i64, A)) {
+        <ArgMaxDef<i64, A> as Accumulator<_, _, _>>::add0(a1, a)
+    }
+
+    fn combine(a1: &mut Option<(i64, A)>, a2: &Option<(i64, A)>) {
+        <ArgMaxDef<i64, A> as Accumulator<_, _, _>>::combine(a1, a2)
+    }
+
+    fn finish(a: &Option<(i64, A)>) -> Option<A> {
+        a.as_ref().map(|(_, v)| v.clone())
+    }
+}
+
 pub mod set {
     use super::*;
     use crate::core::state::StateType;
@@ -273,9 +464,471 @@ pub mod topk {
     }
 }
 
+pub mod quantile {
+    //! Approximate quantiles with a merging t-digest (Dunning & Ertl), accurate at the tails and
+    //! small enough to keep one per vertex.
+
+    use super::*;
+    use crate::core::state::StateType;
+    use num_traits::ToPrimitive;
+    use serde::{Deserialize, Serialize};
+    use std::f64::consts::PI;
+
+    /// Larger values keep more centroids and give more accurate quantiles.
+    const COMPRESSION: f64 = 100.0;
+    /// The number of centroids buffered before the digest is compressed.
+    const BUFFER: usize = 500;
+
+    /// A sketch of a distribution of `f64` values as a list of weighted centroids.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    pub struct TDigest {
+        centroids: Vec<(f64, f64)>,
+        min: f64,
+        max: f64,
+    }
+
+    impl Default for TDigest {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl TDigest {
+        pub fn new() -> Self {
+            Self {
+                centroids: vec![],
+                min: f64::INFINITY,
+                max: f64::NEG_INFINITY,
+            }
+        }
+
+        pub fn insert(&mut self, x: f64) {
+            if x.is_nan() {
+                return;
+            }
+            self.min = self.min.min(x);
+            self.max = self.max.max(x);
+            self.centroids.push((x, 1.0));
+            if self.centroids.len() > BUFFER {
+                self.compress();
+            }
+        }
+
+        pub fn merge(&mut self, other: &TDigest) {
+            self.min = self.min.min(other.min);
+            self.max = self.max.max(other.max);
+            self.centroids.extend_from_slice(&other.centroids);
+            if self.centroids.len() > BUFFER {
+                self.compress();
+            }
+        }
+
+        /// The number of values inserted into the digest.
+        pub fn count(&self) -> f64 {
+            self.centroids.iter().map(|(_, w)| w).sum()
+        }
+
+        /// Estimates the value below which a fraction `q` of the values fall, `None` if the digest
+        /// is empty.
+        pub fn quantile(&self, q: f64) -> Option<f64> {
+            let mut centroids = self.centroids.clone();
+            centroids.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let (first, last) = (centroids.first()?, centroids.last()?);
+            let total = self.count();
+            let target = q.clamp(0.0, 1.0) * total;
+
+            // below the first and above the last centroid interpolate towards the extremes
+            if target < first.1 / 2.0 {
+                return Some(self.min + (first.0 - self.min) * target / (first.1 / 2.0));
+            }
+            if target > total - last.1 / 2.0 {
+                let rest = (total - target) / (last.1 / 2.0);
+                return Some(self.max - (self.max - last.0) * rest);
+            }
+
+            let mut seen = 0.0;
+            for w in centroids.windows(2) {
+                let (left, right) = (w[0], w[1]);
+                let left_mid = seen + left.1 / 2.0;
+                let right_mid = seen + left.1 + right.1 / 2.0;
+                if target <= right_mid {
+                    if right_mid == left_mid {
+                        return Some(left.0);
+                    }
+                    let ratio = (target - left_mid) / (right_mid - left_mid);
+                    return Some(left.0 + (right.0 - left.0) * ratio);
+                }
+                seen += left.1;
+            }
+            Some(last.0)
+        }
+
+        fn k(q: f64) -> f64 {
+            COMPRESSION / (2.0 * PI) * (2.0 * q - 1.0).asin()
+        }
+
+        fn k_inv(k: f64) -> f64 {
+            if k >= COMPRESSION / 4.0 {
+                1.0
+            } else {
+                ((2.0 * PI * k / COMPRESSION).sin() + 1.0) / 2.0
+            }
+        }
+
+        /// Merges neighbouring centroids as long as they stay within the size the scale function
+        /// allows at their quantile.
+        fn compress(&mut self) {
+            self.centroids.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let total = self.count();
+
+            let mut merged: Vec<(f64, f64)> = Vec::with_capacity(COMPRESSION as usize);
+            let mut centroids = self.centroids.drain(..);
+            let mut current = match centroids.next() {
+                Some(c) => c,
+                None => return,
+            };
+            let mut seen = 0.0;
+            let mut limit = Self::k_inv(Self::k(0.0) + 1.0) * total;
+
+            for c in centroids {
+                if seen + current.1 + c.1 <= limit {
+                    let weight = current.1 + c.1;
+                    current.0 += (c.0 - current.0) * c.1 / weight;
+                    current.1 = weight;
+                } else {
+                    seen += current.1;
+                    merged.push(current);
+                    limit = Self::k_inv(Self::k(seen / total) + 1.0) * total;
+                    current = c;
+                }
+            }
+            merged.push(current);
+            self.centroids = merged;
+        }
+    }
+
+    /// Builds a `TDigest` of the inputs to query any quantile of them.
+    pub struct Quantiles<A: StateType + ToPrimitive> {
+        _marker: PhantomData<A>,
+    }
+
+    impl<A> Accumulator<TDigest, A, TDigest> for Quantiles<A>
+    where
+        A: StateType + ToPrimitive,
+    {
+        fn zero() -> TDigest {
+            TDigest::new()
+        }
+
+        fn add0(a1: &mut TDigest, a: A) {
+            if let Some(x) = a.to_f64() {
+                a1.insert(x)
+            }
+        }
+
+        fn combine(a1: &mut TDigest, a2: &TDigest) {
+            a1.merge(a2)
+        }
+
+        fn finish(a: &TDigest) -> TDigest {
+            a.clone()
+        }
+    }
+
+    /// The approximate median of the inputs, `None` if there were none.
+    pub struct Median<A: StateType + ToPrimitive> {
+        _marker: PhantomData<A>,
+    }
+
+    impl<A> Accumulator<TDigest, A, Option<f64>> for Median<A>
+    where
+        A: StateType + ToPrimitive,
+    {
+        fn zero() -> TDigest {
+            TDigest::new()
+        }
+
+        fn add0(a1: &mut TDigest, a: A) {
+            <Quantiles<A> as Accumulator<_, _, _>>::add0(a1, a)
+        }
+
+        fn combine(a1: &mut TDigest, a2: &TDigest) {
+            a1.merge(a2)
+        }
+
+        fn finish(a: &TDigest) -> Option<f64> {
+            a.quantile(0.5)
+        }
+    }
+}
+
+pub mod hll {
+    //! Approximate distinct counts with HyperLogLog (Flajolet et al.).
+
+    use super::*;
+    use crate::core::{state::StateType, utils::calculate_hash};
+    use serde::{Deserialize, Serialize};
+    use std::hash::Hash;
+
+    /// A HyperLogLog sketch with `2^precision` registers of one byte each, the standard error of
+    /// the estimate is about `1.04 / sqrt(2^precision)`.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    pub struct HyperLogLog {
+        registers: Vec<u8>,
+    }
+
+    impl HyperLogLog {
+        /// # Panics
+        ///
+        /// Panics if `precision` is not between 4 and 16.
+        pub fn new(precision: usize) -> Self {
+            assert!(
+                (4..=16).contains(&precision),
+                "HyperLogLog precision must be between 4 and 16, got {precision}"
+            );
+            Self {
+                registers: vec![0; 1 << precision],
+            }
+        }
+
+        fn precision(&self) -> u32 {
+            self.registers.len().trailing_zeros()
+        }
+
+        pub fn insert<A: Hash>(&mut self, a: &A) {
+            let p = self.precision();
+            let hash = calculate_hash(a);
+            let index = (hash >> (64 - p)) as usize;
+            // the guard bit caps the rank when the remaining bits are all zero
+            let rank = ((hash << p) | (1 << (p - 1))).leading_zeros() as u8 + 1;
+            if rank > self.registers[index] {
+                self.registers[index] = rank;
+            }
+        }
+
+        /// # Panics
+        ///
+        /// Panics if the sketches were created with different precisions.
+        pub fn merge(&mut self, other: &HyperLogLog) {
+            assert_eq!(
+                self.registers.len(),
+                other.registers.len(),
+                "cannot merge HyperLogLog sketches of different precisions"
+            );
+            for (r, o) in self.registers.iter_mut().zip(&other.registers) {
+                *r = (*r).max(*o);
+            }
+        }
+
+        /// Estimates the number of distinct values inserted into the sketch.
+        pub fn estimate(&self) -> u64 {
+            let m = self.registers.len() as f64;
+            let alpha = match self.registers.len() {
+                16 => 0.673,
+                32 => 0.697,
+                64 => 0.709,
+                _ => 0.7213 / (1.0 + 1.079 / m),
+            };
+            let sum: f64 = self.registers.iter().map(|r| 2f64.powi(-(*r as i32))).sum();
+            let estimate = alpha * m * m / sum;
+
+            // small cardinalities are estimated better by counting the empty registers
+            let zeros = self.registers.iter().filter(|r| **r == 0).count();
+            if estimate <= 2.5 * m && zeros > 0 {
+                (m * (m / zeros as f64).ln()).round() as u64
+            } else {
+                estimate.round() as u64
+            }
+        }
+    }
+
+    /// Estimates the number of distinct inputs with a `HyperLogLog` of `2^P` registers.
+    pub struct DistinctCount<A: StateType + Hash, const P: usize> {
+        _marker: PhantomData<A>,
+    }
+
+    impl<A, const P: usize> Accumulator<HyperLogLog, A, u64> for DistinctCount<A, P>
+    where
+        A: StateType + Hash,
+    {
+        fn zero() -> HyperLogLog {
+            HyperLogLog::new(P)
+        }
+
+        fn add0(a1: &mut HyperLogLog, a: A) {
+            a1.insert(&a)
+        }
+
+        fn combine(a1: &mut HyperLogLog, a2: &HyperLogLog) {
+            a1.merge(a2)
+        }
+
+        fn finish(a: &HyperLogLog) -> u64 {
+            a.estimate()
+        }
+    }
+}
+
+/// Collects every input in a list, used for messages that are not combined.
+#[derive(Clone, Debug, Copy)]
+pub struct MessageList<A: StateType> {
+    _marker: PhantomData<A>,
+}
+
+impl<A> Accumulator<Vec<A>, A, Vec<A>> for MessageList<A>
+where
+    A: StateType,
+{
+    fn zero() -> Vec<A> {
+        Vec::new()
+    }
+
+    fn add0(a1: &mut Vec<A>, a: A) {
+        a1.push(a);
+    }
+
+    fn combine(a1: &mut Vec<A>, a2: &Vec<A>) {
+        a1.extend(a2.iter().cloned())
+    }
+
+    fn finish(a: &Vec<A>) -> Vec<A> {
+        a.clone()
+    }
+}
+
 #[cfg(test)]
 mod agg_test {
 
+    #[test]
+    fn message_list() {
+        use crate::core::agg::{Accumulator, MessageList};
+
+        let mut inbox = MessageList::<u64>::zero();
+        let mut other = MessageList::<u64>::zero();
+
+        <MessageList<u64> as Accumulator<Vec<u64>, u64, Vec<u64>>>::add0(&mut inbox, 1);
+        <MessageList<u64> as Accumulator<Vec<u64>, u64, Vec<u64>>>::add0(&mut other, 2);
+        <MessageList<u64> as Accumulator<Vec<u64>, u64, Vec<u64>>>::add0(&mut other, 1);
+        <MessageList<u64> as Accumulator<Vec<u64>, u64, Vec<u64>>>::combine(&mut inbox, &other);
+
+        assert_eq!(
+            <MessageList<u64> as Accumulator<Vec<u64>, u64, Vec<u64>>>::finish(&inbox),
+            vec![1, 2, 1]
+        );
+    }
+
+    #[test]
+    fn stable_sum_keeps_the_rounding_error() {
+        use crate::core::agg::{Accumulator, StableSumDef, SumDef};
+
+        type Stable = StableSumDef<f32>;
+        let values = [1e8f32, 1.0, -1e8, 1.0];
+
+        let mut sum = <SumDef<f32> as Accumulator<_, _, _>>::zero();
+        let mut stable = <Stable as Accumulator<_, _, _>>::zero();
+        for v in values {
+            <SumDef<f32> as Accumulator<_, _, _>>::add0(&mut sum, v);
+            <Stable as Accumulator<_, _, _>>::add0(&mut stable, v);
+        }
+        assert_eq!(sum, 1.0);
+        assert_eq!(<Stable as Accumulator<_, _, _>>::finish(&stable), 2.0);
+
+        // merging partial sums in another order gives the same result
+        let mut left = <Stable as Accumulator<_, _, _>>::zero();
+        let mut right = <Stable as Accumulator<_, _, _>>::zero();
+        for v in &values[..2] {
+            <Stable as Accumulator<_, _, _>>::add0(&mut left, *v);
+        }
+        for v in &values[2..] {
+            <Stable as Accumulator<_, _, _>>::add0(&mut right, *v);
+        }
+        <Stable as Accumulator<_, _, _>>::combine(&mut right, &left);
+        assert_eq!(<Stable as Accumulator<_, _, _>>::finish(&right), 2.0);
+    }
+
+    #[test]
+    fn arg_max_and_last() {
+        use crate::core::agg::{Accumulator, ArgMaxDef, ArgMinDef, CountDef, LastDef};
+
+        type ArgMax = ArgMaxDef<i64, u64>;
+        type ArgMin = ArgMinDef<i64, u64>;
+
+        let mut max = <ArgMax as Accumulator<_, _, _>>::zero();
+        let mut min = <ArgMin as Accumulator<_, _, _>>::zero();
+        let mut last = <LastDef<u64> as Accumulator<_, _, _>>::zero();
+        let mut count = <CountDef<u64> as Accumulator<_, _, _>>::zero();
+
+        for (k, v) in [(3, 30), (7, 71), (1, 10), (7, 70)] {
+            <ArgMax as Accumulator<_, _, _>>::add0(&mut max, (k, v));
+            <ArgMin as Accumulator<_, _, _>>::add0(&mut min, (k, v));
+            <LastDef<u64> as Accumulator<_, _, _>>::add0(&mut last, (k, v));
+            <CountDef<u64> as Accumulator<_, _, _>>::add0(&mut count, v);
+        }
+
+        // ties on the key keep the smallest payload
+        assert_eq!(
+            <ArgMax as Accumulator<_, _, _>>::finish(&max),
+            Some((7, 70))
+        );
+        assert_eq!(
+            <ArgMin as Accumulator<_, _, _>>::finish(&min),
+            Some((1, 10))
+        );
+        assert_eq!(
+            <LastDef<u64> as Accumulator<_, _, _>>::finish(&last),
+            Some(70)
+        );
+        assert_eq!(<CountDef<u64> as Accumulator<_, _, _>>::finish(&count), 4);
+    }
+
+    #[test]
+    fn median_is_close_after_merging() {
+        use crate::core::agg::{quantile::Median, Accumulator};
+
+        let mut left = <Median<u64> as Accumulator<_, _, _>>::zero();
+        let mut right = <Median<u64> as Accumulator<_, _, _>>::zero();
+        assert_eq!(<Median<u64> as Accumulator<_, _, _>>::finish(&left), None);
+
+        for i in 0..10_000u64 {
+            let part = if i % 3 == 0 { &mut left } else { &mut right };
+            <Median<u64> as Accumulator<_, _, _>>::add0(part, i);
+        }
+        <Median<u64> as Accumulator<_, _, _>>::combine(&mut left, &right);
+
+        let median = <Median<u64> as Accumulator<_, _, _>>::finish(&left).unwrap();
+        assert!((median - 5000.0).abs() < 50.0, "median was {median}");
+        assert_eq!(left.count(), 10_000.0);
+        assert_eq!(left.quantile(0.0), Some(0.0));
+        assert_eq!(left.quantile(1.0), Some(9999.0));
+    }
+
+    #[test]
+    fn distinct_count_estimate() {
+        use crate::core::agg::{hll::DistinctCount, Accumulator};
+
+        type Hll = DistinctCount<u64, 12>;
+
+        let mut left = <Hll as Accumulator<_, _, _>>::zero();
+        let mut right = <Hll as Accumulator<_, _, _>>::zero();
+        for i in 0..5 {
+            <Hll as Accumulator<_, _, _>>::add0(&mut left, i);
+        }
+        assert_eq!(<Hll as Accumulator<_, _, _>>::finish(&left), 5);
+
+        for i in 0..100_000 {
+            <Hll as Accumulator<_, _, _>>::add0(&mut left, i);
+            <Hll as Accumulator<_, _, _>>::add0(&mut right, i + 50_000);
+        }
+        <Hll as Accumulator<_, _, _>>::combine(&mut left, &right);
+
+        let estimate = <Hll as Accumulator<_, _, _>>::finish(&left) as f64;
+        assert!(
+            (estimate - 150_000.0).abs() / 150_000.0 < 0.05,
+            "estimate was {estimate}"
+        );
+    }
+
     #[test]
     fn avg_def() {
         use crate::core::agg::{
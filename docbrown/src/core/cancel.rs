@@ -0,0 +1,142 @@
+//! Cancellation and time budgets for long-running computations.
+//!
+//! A `Budget` combines an optional `CancellationToken` with an optional deadline. Programs run
+//! with a `Runner` check it between supersteps, sequential algorithms check it between vertices,
+//! and iterators such as a `WindowSet` can be wrapped to stop once it runs out.
+//!
+//! The algorithms that take a budget are the iterative and enumerating ones:
+//! `unweighted_page_rank_with_budget`, `hits_with_budget`,
+//! `weakly_connected_components_with_budget`, `global_triangle_count_with_budget`,
+//! `triangle_count_with_budget` and `temporal_cycles_with_budget`. The others make a single
+//! pass over the vertices or edges and run to completion.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use docbrown::core::cancel::{Budget, CancellationToken};
+//! use docbrown::core::tgraph_shard::errors::GraphError;
+//! use std::time::Duration;
+//!
+//! let token = CancellationToken::new();
+//! let budget = Budget::default()
+//!     .with_token(token.clone())
+//!     .with_timeout(Duration::from_secs(60));
+//! assert_eq!(budget.check(), Ok(()));
+//!
+//! // e.g. from another thread serving a request to stop the job
+//! token.cancel();
+//! assert_eq!(budget.check(), Err(GraphError::Cancelled));
+//! ```
+
+use crate::core::tgraph_shard::errors::GraphError;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A flag shared between a computation and the code that wants to stop it, clones share the
+/// same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks every computation holding a clone of this token to stop.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The limits a computation has to stay within, the default budget is unlimited.
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    token: Option<CancellationToken>,
+    deadline: Option<Instant>,
+}
+
+impl Budget {
+    /// Stops the computation when `token` is cancelled.
+    pub fn with_token(mut self, token: CancellationToken) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Stops the computation once `deadline` has passed.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Stops the computation once `timeout` has passed from now.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// Checks whether the computation can go on.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::Cancelled` if the token was cancelled and
+    /// `GraphError::DeadlineExceeded` if the deadline has passed.
+    pub fn check(&self) -> Result<(), GraphError> {
+        if self.token.as_ref().map_or(false, |t| t.is_cancelled()) {
+            return Err(GraphError::Cancelled);
+        }
+        if self.deadline.map_or(false, |d| Instant::now() >= d) {
+            return Err(GraphError::DeadlineExceeded);
+        }
+        Ok(())
+    }
+
+    /// Wraps `iter` so that it yields an error and stops once the budget runs out.
+    pub fn guard<I: Iterator>(&self, iter: I) -> Budgeted<I> {
+        Budgeted {
+            iter,
+            budget: self.clone(),
+            done: false,
+        }
+    }
+}
+
+/// An iterator that checks a `Budget` before every item, see `Budget::guard`.
+pub struct Budgeted<I> {
+    iter: I,
+    budget: Budget,
+    done: bool,
+}
+
+impl<I: Iterator> Iterator for Budgeted<I> {
+    type Item = Result<I::Item, GraphError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if let Err(err) = self.budget.check() {
+            self.done = true;
+            return Some(Err(err));
+        }
+        self.iter.next().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod cancel_tests {
+    use super::*;
+
+    #[test]
+    fn guard_stops_after_the_deadline() {
+        let budget = Budget::default().with_deadline(Instant::now());
+        let items = budget.guard(0..3).collect::<Vec<_>>();
+        assert_eq!(items, vec![Err(GraphError::DeadlineExceeded)]);
+
+        let items = Budget::default().guard(0..3).collect::<Vec<_>>();
+        assert_eq!(items, vec![Ok(0), Ok(1), Ok(2)]);
+    }
+}
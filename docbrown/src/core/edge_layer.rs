@@ -6,7 +6,8 @@ use std::ops::Range;
 use crate::core::adj::Adj;
 use crate::core::props::Props;
 use crate::core::tadjset::AdjEdge;
-use crate::core::tgraph::EdgeRef;
+use crate::core::tgraph::errors::MutateGraphError;
+use crate::core::tgraph::{EdgeRef, MutateGraphResult};
 use crate::core::{Direction, Prop};
 
 use super::tadjset::TAdjSet;
@@ -47,7 +48,9 @@ impl EdgeLayer {
         src_pid: usize,
         dst_pid: usize,
         props: &Vec<(String, Prop)>,
-    ) {
+    ) -> MutateGraphResult {
+        let edge_id = self.outbound_edge_id(src_pid, dst_pid, false);
+        self.check_props(src, dst, edge_id, props)?;
         let required_len = std::cmp::max(src_pid, dst_pid) + 1;
         self.ensure_adj_lists_len(required_len);
         let src_edge_meta_id = self.link_outbound_edge(t, src_pid, dst_pid, false);
@@ -59,8 +62,8 @@ impl EdgeLayer {
             );
         }
 
-        self.props.upsert_temporal_props(t, src_edge_meta_id, props);
         self.next_edge_id += 1; // FIXME: we have this in three different places, prone to errors!
+        self.props.upsert_temporal_props(t, src_edge_meta_id, props)
     }
 
     #[allow(unused_variables)]
@@ -71,11 +74,13 @@ impl EdgeLayer {
         dst: u64,
         src_pid: usize,
         props: &Vec<(String, Prop)>,
-    ) {
+    ) -> MutateGraphResult {
+        let edge_id = self.outbound_edge_id(src_pid, dst.try_into().unwrap(), true);
+        self.check_props(src, dst, edge_id, props)?;
         self.ensure_adj_lists_len(src_pid + 1);
         let src_edge_meta_id = self.link_outbound_edge(t, src_pid, dst.try_into().unwrap(), true);
-        self.props.upsert_temporal_props(t, src_edge_meta_id, props);
         self.next_edge_id += 1;
+        self.props.upsert_temporal_props(t, src_edge_meta_id, props)
     }
 
     #[allow(unused_variables)]
@@ -86,16 +91,64 @@ impl EdgeLayer {
         dst: u64, // we are on the destination shard
         dst_pid: usize,
         props: &Vec<(String, Prop)>,
-    ) {
+    ) -> MutateGraphResult {
+        let edge_id = self.inbound_edge_id(src.try_into().unwrap(), dst_pid, true);
+        self.check_props(src, dst, edge_id, props)?;
         self.ensure_adj_lists_len(dst_pid + 1);
         let dst_edge_meta_id = self.link_inbound_edge(t, src.try_into().unwrap(), dst_pid, true);
-        self.props.upsert_temporal_props(t, dst_edge_meta_id, props);
         self.next_edge_id += 1;
+        self.props.upsert_temporal_props(t, dst_edge_meta_id, props)
     }
 }
 
 // INGESTION HELPERS:
 impl EdgeLayer {
+    /// Checks that `props` can be added to the edge from `src` to `dst`, whose id is `edge_id` if
+    /// it is already linked, so that an add that fails leaves the layer as it was.
+    fn check_props(
+        &self,
+        src: u64,
+        dst: u64,
+        edge_id: Option<usize>,
+        props: &[(String, Prop)],
+    ) -> MutateGraphResult {
+        self.props.check_prop_kinds(props, false).map_err(|e| {
+            MutateGraphError::IllegalEdgePropertyKind {
+                src_id: src,
+                dst_id: dst,
+                source: e,
+            }
+        })?;
+        match edge_id {
+            Some(id) => self.props.check_prop_types(id, props),
+            None => Ok(()),
+        }
+    }
+
+    fn outbound_edge_id(&self, src_pid: usize, dst: usize, remote_edge: bool) -> Option<usize> {
+        match self.adj_lists.get(src_pid)? {
+            Adj::Solo => None,
+            Adj::List {
+                out, remote_out, ..
+            } => {
+                let list = if remote_edge { remote_out } else { out };
+                list.find(dst).map(|e| e.edge_id())
+            }
+        }
+    }
+
+    fn inbound_edge_id(&self, src: usize, dst_pid: usize, remote_edge: bool) -> Option<usize> {
+        match self.adj_lists.get(dst_pid)? {
+            Adj::Solo => None,
+            Adj::List {
+                into, remote_into, ..
+            } => {
+                let list = if remote_edge { remote_into } else { into };
+                list.find(src).map(|e| e.edge_id())
+            }
+        }
+    }
+
     #[inline]
     fn ensure_adj_lists_len(&mut self, len: usize) {
         if self.adj_lists.len() < len {
@@ -355,6 +408,15 @@ impl EdgeLayer {
         }
     }
 
+    /// The id of the properties of the edge from the remote vertex `src` into `dst_pid`, if
+    /// there is one.
+    pub(crate) fn remote_in_edge_id(&self, src: u64, dst_pid: usize) -> Option<usize> {
+        match self.adj_lists.get(dst_pid).unwrap_or(&Adj::Solo) {
+            Adj::Solo => None,
+            Adj::List { remote_into, .. } => Some(remote_into.find(src as usize)?.edge_id()),
+        }
+    }
+
     pub(crate) fn remote_edge(&self, src: u64, dst: u64, src_pid: usize) -> Option<EdgeRef> {
         match self.adj_lists.get(src_pid).unwrap_or(&Adj::Solo) {
             Adj::Solo => None,
@@ -647,4 +709,31 @@ mod edge_layer_tests {
         // both local as well as remote edge id. Hence edge ids must always start with 1.
         assert_eq!(layer.next_edge_id, 1);
     }
+
+    #[test]
+    fn adding_props_of_another_type_leaves_the_layer_as_it_was() {
+        let mut layer = EdgeLayer::new(0);
+        let props = |p| vec![("amount".to_string(), p)];
+        layer
+            .add_edge_with_props(1, 10, 20, 0, 1, &props(Prop::I64(1)))
+            .unwrap();
+        layer
+            .add_edge_remote_out(1, 10, 30, 0, &props(Prop::I64(1)))
+            .unwrap();
+        layer
+            .add_edge_remote_into(1, 40, 20, 1, &props(Prop::I64(1)))
+            .unwrap();
+        let before = bincode::serialize(&layer).unwrap();
+
+        assert!(layer
+            .add_edge_with_props(2, 10, 20, 0, 1, &props(Prop::F64(1.0)))
+            .is_err());
+        assert!(layer
+            .add_edge_remote_out(2, 10, 30, 0, &props(Prop::F64(1.0)))
+            .is_err());
+        assert!(layer
+            .add_edge_remote_into(2, 40, 20, 1, &props(Prop::F64(1.0)))
+            .is_err());
+        assert_eq!(bincode::serialize(&layer).unwrap(), before);
+    }
 }
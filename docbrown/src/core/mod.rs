@@ -24,7 +24,10 @@
 //!    * `macOS`
 //!
 
+use chrono::{DateTime, FixedOffset};
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
 
 #[cfg(test)]
@@ -33,11 +36,13 @@ extern crate core;
 mod adj;
 pub mod agg;
 mod bitset;
+pub mod cancel;
 mod edge_layer;
 mod lazy_vec;
 pub mod lsm;
 mod misc;
 mod props;
+pub mod schema;
 mod sorted_vec_map;
 pub mod state;
 pub mod tadjset;
@@ -48,6 +53,7 @@ pub mod time;
 mod tprop;
 pub mod utils;
 pub mod vertex;
+pub mod vertex_names;
 
 type Time = i64;
 
@@ -70,6 +76,12 @@ pub enum Prop {
     F32(f32),
     F64(f64),
     Bool(bool),
+    /// A timestamp with a fixed offset from UTC.
+    DTime(DateTime<FixedOffset>),
+    List(Vec<Prop>),
+    // boxed to keep `Prop` as small as a `String`
+    Map(Box<HashMap<String, Prop>>),
+    Bytes(Vec<u8>),
 }
 
 impl fmt::Display for Prop {
@@ -83,6 +95,95 @@ impl fmt::Display for Prop {
             Prop::F32(value) => write!(f, "{}", value),
             Prop::F64(value) => write!(f, "{}", value),
             Prop::Bool(value) => write!(f, "{}", value),
+            Prop::DTime(value) => write!(f, "{}", value.to_rfc3339()),
+            Prop::List(values) => write!(f, "[{}]", values.iter().join(", ")),
+            Prop::Map(values) => {
+                // sort the keys so the same map is always displayed the same way
+                let entries = values
+                    .iter()
+                    .sorted_by(|(a, _), (b, _)| a.cmp(b))
+                    .map(|(k, v)| format!("{}: {}", k, v))
+                    .join(", ");
+                write!(f, "{{{}}}", entries)
+            }
+            Prop::Bytes(value) => write!(f, "{:?}", value),
         }
     }
 }
+
+impl Prop {
+    /// The type of this property value.
+    pub fn dtype(&self) -> PropType {
+        match self {
+            Prop::Str(_) => PropType::Str,
+            Prop::I32(_) => PropType::I32,
+            Prop::I64(_) => PropType::I64,
+            Prop::U32(_) => PropType::U32,
+            Prop::U64(_) => PropType::U64,
+            Prop::F32(_) => PropType::F32,
+            Prop::F64(_) => PropType::F64,
+            Prop::Bool(_) => PropType::Bool,
+            Prop::DTime(_) => PropType::DTime,
+            Prop::List(_) => PropType::List,
+            Prop::Map(_) => PropType::Map,
+            Prop::Bytes(_) => PropType::Bytes,
+        }
+    }
+
+    /// Converts this value to `dtype` if that can be done without losing information,
+    /// e.g. an `I32` into an `I64` or an `F32` into an `F64`. An `I64` is also converted into an
+    /// `I32`, `U32` or `U64` if it is in the range of that type, and an `F64` into an `F32` if
+    /// the `F32` has the same value, as that is how integers and floats given from Python arrive.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use docbrown::core::{Prop, PropType};
+    ///
+    /// assert_eq!(Prop::I32(7).coerce(PropType::I64), Some(Prop::I64(7)));
+    /// assert_eq!(Prop::I64(7).coerce(PropType::I32), Some(Prop::I32(7)));
+    /// assert_eq!(Prop::I64(-7).coerce(PropType::U64), None);
+    /// assert_eq!(Prop::F64(0.1).coerce(PropType::F32), None);
+    /// ```
+    pub fn coerce(&self, dtype: PropType) -> Option<Prop> {
+        match (self, dtype) {
+            (value, dtype) if value.dtype() == dtype => Some(value.clone()),
+            (Prop::I32(v), PropType::I64) => Some(Prop::I64(*v as i64)),
+            (Prop::I32(v), PropType::F64) => Some(Prop::F64(*v as f64)),
+            (Prop::U32(v), PropType::I64) => Some(Prop::I64(*v as i64)),
+            (Prop::U32(v), PropType::U64) => Some(Prop::U64(*v as u64)),
+            (Prop::U32(v), PropType::F64) => Some(Prop::F64(*v as f64)),
+            (Prop::F32(v), PropType::F64) => Some(Prop::F64(*v as f64)),
+            (Prop::I64(v), PropType::I32) => i32::try_from(*v).ok().map(Prop::I32),
+            (Prop::I64(v), PropType::U32) => u32::try_from(*v).ok().map(Prop::U32),
+            (Prop::I64(v), PropType::U64) => u64::try_from(*v).ok().map(Prop::U64),
+            (Prop::F64(v), PropType::F32) if *v as f32 as f64 == *v || v.is_nan() => {
+                Some(Prop::F32(*v as f32))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The type of a property value, one per variant of `Prop`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum PropType {
+    Str,
+    I32,
+    I64,
+    U32,
+    U64,
+    F32,
+    F64,
+    Bool,
+    DTime,
+    List,
+    Map,
+    Bytes,
+}
+
+impl fmt::Display for PropType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
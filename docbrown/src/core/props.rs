@@ -1,6 +1,8 @@
 use crate::core::lazy_vec::{IllegalSet, LazyVec};
+use crate::core::tgraph::errors::MutateGraphError;
+use crate::core::tgraph_shard::errors::GraphError;
 use crate::core::tprop::TProp;
-use crate::core::Prop;
+use crate::core::{Prop, PropType};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -23,6 +25,15 @@ impl IllegalMutate {
     }
 }
 
+/// A property name that is already used for static properties was given as a temporal one, or
+/// the other way round.
+#[derive(thiserror::Error, Debug, PartialEq)]
+#[error("property '{name}' is already used as a {existing} property")]
+pub struct IllegalPropKind {
+    pub name: String,
+    pub existing: &'static str,
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
 enum PropId {
     Static(usize),
@@ -166,32 +177,125 @@ impl Props {
         }
     }
 
+    /// Checks that none of `props` is already used as the other kind of property, so that
+    /// callers can reject them before changing anything.
+    pub(crate) fn check_prop_kinds(
+        &self,
+        props: &[(String, Prop)],
+        should_be_static: bool,
+    ) -> Result<(), IllegalPropKind> {
+        match props.iter().find(|(name, _)| {
+            let id = self.prop_ids.get(name);
+            id.map_or(false, |id| id.is_static() != should_be_static)
+        }) {
+            Some((name, _)) => Err(IllegalPropKind {
+                name: name.clone(),
+                existing: if should_be_static {
+                    "temporal"
+                } else {
+                    "static"
+                },
+            }),
+            None => Ok(()),
+        }
+    }
+
     fn translate_props(
         &mut self,
         props: &Vec<(String, Prop)>,
         should_be_static: bool,
     ) -> Vec<(usize, Prop)> {
-        // TODO: return Result
         props
             .iter()
             .map(|(name, prop)| {
                 (
-                    self.get_or_allocate_id(name, should_be_static).unwrap(),
+                    self.get_or_allocate_id(name, should_be_static)
+                        .expect("property kinds are checked before properties are added"),
                     prop.clone(),
                 )
             })
             .collect_vec()
     }
 
-    pub fn upsert_temporal_props(&mut self, t: i64, id: usize, props: &Vec<(String, Prop)>) {
+    /// Checks that `props` have the types of the values of the temporal properties of `id` with
+    /// the same names, so that callers can reject them before changing anything.
+    pub(crate) fn check_prop_types(
+        &self,
+        id: usize,
+        props: &[(String, Prop)],
+    ) -> Result<(), MutateGraphError> {
+        for (name, prop) in props {
+            match self.temporal_prop(id, name).and_then(|p| p.dtype()) {
+                Some(dtype) if dtype != prop.dtype() => {
+                    return Err(MutateGraphError::PropertyChangedType {
+                        name: name.clone(),
+                        first_type: dtype,
+                        second_type: prop.dtype(),
+                    })
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every value of the properties `declared` declares, of every entity, has the
+    /// declared type.
+    pub(crate) fn check_declared_types(
+        &self,
+        declared: &HashMap<String, PropType>,
+    ) -> Result<(), GraphError> {
+        for (name, &expected) in declared {
+            let actual = match self.prop_ids.get(name) {
+                None => None,
+                Some(PropId::Static(id)) => self
+                    .static_props
+                    .iter()
+                    .filter_map(|props| props.get(*id)?.as_ref().map(|p| p.dtype()))
+                    .find(|&dtype| dtype != expected),
+                Some(PropId::Temporal(id)) => self
+                    .temporal_props
+                    .iter()
+                    .filter_map(|props| props.get(*id)?.dtype())
+                    .find(|&dtype| dtype != expected),
+            };
+            if let Some(actual) = actual {
+                return Err(GraphError::PropertyTypeMismatch {
+                    name: name.clone(),
+                    expected,
+                    actual,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn upsert_temporal_props(
+        &mut self,
+        t: i64,
+        id: usize,
+        props: &Vec<(String, Prop)>,
+    ) -> Result<(), MutateGraphError> {
         if !props.is_empty() {
+            self.check_prop_types(id, props)?;
             let translated_props = self.translate_props(props, false);
             let vertex_slot: &mut LazyVec<TProp> =
                 Self::grow_and_get_slot(&mut self.temporal_props, id);
-            for (prop_id, prop) in translated_props {
-                vertex_slot.update_or_set(prop_id, |p| p.set(t, &prop), TProp::from(t, &prop));
+            for ((name, _), (prop_id, prop)) in props.iter().zip(translated_props) {
+                let mut result = Ok(());
+                vertex_slot.update_or_set(
+                    prop_id,
+                    |p| result = p.set(t, &prop),
+                    TProp::from(t, &prop),
+                );
+                result.map_err(|first_type| MutateGraphError::PropertyChangedType {
+                    name: name.clone(),
+                    first_type,
+                    second_type: prop.dtype(),
+                })?;
             }
         }
+        Ok(())
     }
 
     pub fn set_static_props(
@@ -240,7 +344,9 @@ mod props_tests {
     #[test]
     fn insert_new_vertex_prop() {
         let mut props = Props::default();
-        props.upsert_temporal_props(1, 0, &vec![("bla".to_string(), Prop::I32(10))]);
+        props
+            .upsert_temporal_props(1, 0, &vec![("bla".to_string(), Prop::I32(10))])
+            .unwrap();
 
         let prop_id = props.get_or_allocate_id("bla", false).unwrap();
         assert_eq!(
@@ -259,8 +365,12 @@ mod props_tests {
     #[test]
     fn update_existing_vertex_prop() {
         let mut props = Props::default();
-        props.upsert_temporal_props(1, 0, &vec![("bla".to_string(), Prop::I32(10))]);
-        props.upsert_temporal_props(2, 0, &vec![("bla".to_string(), Prop::I32(10))]);
+        props
+            .upsert_temporal_props(1, 0, &vec![("bla".to_string(), Prop::I32(10))])
+            .unwrap();
+        props
+            .upsert_temporal_props(2, 0, &vec![("bla".to_string(), Prop::I32(10))])
+            .unwrap();
 
         let prop_id = props.get_or_allocate_id("bla", false).unwrap();
         assert_eq!(
@@ -279,8 +389,12 @@ mod props_tests {
     #[test]
     fn new_update_with_the_same_time_to_a_vertex_prop_is_ignored() {
         let mut props = Props::default();
-        props.upsert_temporal_props(1, 0, &vec![("bla".to_string(), Prop::I32(10))]);
-        props.upsert_temporal_props(1, 0, &vec![("bla".to_string(), Prop::I32(20))]);
+        props
+            .upsert_temporal_props(1, 0, &vec![("bla".to_string(), Prop::I32(10))])
+            .unwrap();
+        props
+            .upsert_temporal_props(1, 0, &vec![("bla".to_string(), Prop::I32(20))])
+            .unwrap();
 
         let prop_id = props.get_or_allocate_id("bla", false).unwrap();
         assert_eq!(
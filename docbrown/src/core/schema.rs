@@ -0,0 +1,219 @@
+//! Declared property types for a graph.
+//!
+//! A `Schema` maps property names to the `PropType` their values must have, for vertices and
+//! separately for the edges of each layer. Once set on a `Graph`, values of a declared property
+//! are converted to the declared type when that is lossless (see `Prop::coerce`) and rejected
+//! with a `GraphError::PropertyTypeMismatch` otherwise. Properties that are not declared are
+//! stored as given.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use docbrown::core::schema::Schema;
+//! use docbrown::core::tgraph_shard::errors::GraphError;
+//! use docbrown::core::{Prop, PropType};
+//! use docbrown::db::graph::Graph;
+//!
+//! let g = Graph::new(1);
+//! g.set_schema(
+//!     Schema::new()
+//!         .vertex_prop("age", PropType::I64)
+//!         .edge_prop(Some("trades"), "amount", PropType::F64),
+//! )
+//! .unwrap();
+//!
+//! // an i32 fits into an i64 so it is widened
+//! g.add_vertex(0, "Alice", &vec![("age".to_string(), Prop::I32(30))]).unwrap();
+//!
+//! let err = g.add_vertex(0, "Bob", &vec![("age".to_string(), Prop::Str("30".to_string()))]);
+//! assert_eq!(
+//!     err,
+//!     Err(GraphError::PropertyTypeMismatch {
+//!         name: "age".to_string(),
+//!         expected: PropType::I64,
+//!         actual: PropType::Str,
+//!     })
+//! );
+//! ```
+
+use crate::core::tgraph_shard::errors::GraphError;
+use crate::core::{Prop, PropType};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The declared property types of a graph, the default schema declares nothing.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Schema {
+    vertex_props: HashMap<String, PropType>,
+    // keyed by layer name, `None` is the default layer
+    edge_props: HashMap<Option<String>, HashMap<String, PropType>>,
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares that the vertex property `name` holds values of type `dtype`.
+    pub fn vertex_prop(mut self, name: &str, dtype: PropType) -> Self {
+        self.vertex_props.insert(name.to_string(), dtype);
+        self
+    }
+
+    /// Declares that the property `name` of edges in `layer` holds values of type `dtype`.
+    pub fn edge_prop(mut self, layer: Option<&str>, name: &str, dtype: PropType) -> Self {
+        self.edge_props
+            .entry(layer.map(|l| l.to_string()))
+            .or_default()
+            .insert(name.to_string(), dtype);
+        self
+    }
+
+    /// The declared vertex properties.
+    pub fn vertex_props(&self) -> &HashMap<String, PropType> {
+        &self.vertex_props
+    }
+
+    /// The declared properties of edges in `layer`, if any were declared.
+    pub fn edge_props(&self, layer: Option<&str>) -> Option<&HashMap<String, PropType>> {
+        self.edge_props.get(&layer.map(|l| l.to_string()))
+    }
+
+    /// The layers that have declared edge properties.
+    pub fn layers(&self) -> impl Iterator<Item = Option<&str>> {
+        self.edge_props.keys().map(|l| l.as_deref())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertex_props.is_empty() && self.edge_props.values().all(|p| p.is_empty())
+    }
+
+    /// Checks `props` against the declared vertex properties, returning the coerced properties
+    /// or `None` if they can be added as they are.
+    pub(crate) fn coerce_vertex_props(
+        &self,
+        props: &[(String, Prop)],
+    ) -> Result<Option<Vec<(String, Prop)>>, GraphError> {
+        coerce_props(&self.vertex_props, props)
+    }
+
+    /// Checks `props` against the declared properties of edges in `layer`, returning the
+    /// coerced properties or `None` if they can be added as they are.
+    pub(crate) fn coerce_edge_props(
+        &self,
+        layer: Option<&str>,
+        props: &[(String, Prop)],
+    ) -> Result<Option<Vec<(String, Prop)>>, GraphError> {
+        match self.edge_props(layer) {
+            Some(declared) => coerce_props(declared, props),
+            None => Ok(None),
+        }
+    }
+}
+
+fn coerce_props(
+    declared: &HashMap<String, PropType>,
+    props: &[(String, Prop)],
+) -> Result<Option<Vec<(String, Prop)>>, GraphError> {
+    let mut coerced: Option<Vec<(String, Prop)>> = None;
+    for (i, (name, value)) in props.iter().enumerate() {
+        match declared.get(name) {
+            Some(&expected) if value.dtype() != expected => {
+                let value =
+                    value
+                        .coerce(expected)
+                        .ok_or_else(|| GraphError::PropertyTypeMismatch {
+                            name: name.clone(),
+                            expected,
+                            actual: value.dtype(),
+                        })?;
+                // only copy `props` once one of them actually has to change
+                coerced.get_or_insert_with(|| props.to_vec())[i].1 = value;
+            }
+            _ => {}
+        }
+    }
+    Ok(coerced)
+}
+
+#[cfg(test)]
+mod schema_tests {
+    use super::*;
+
+    #[test]
+    fn only_declared_props_are_coerced() {
+        let schema = Schema::new().vertex_prop("age", PropType::I64).edge_prop(
+            None,
+            "weight",
+            PropType::F64,
+        );
+
+        let props = vec![
+            ("age".to_string(), Prop::U32(3)),
+            ("weight".to_string(), Prop::F32(1.5)),
+        ];
+        assert_eq!(
+            schema.coerce_vertex_props(&props),
+            Ok(Some(vec![
+                ("age".to_string(), Prop::I64(3)),
+                ("weight".to_string(), Prop::F32(1.5)),
+            ]))
+        );
+        assert_eq!(
+            schema.coerce_edge_props(None, &props),
+            Ok(Some(vec![
+                ("age".to_string(), Prop::U32(3)),
+                ("weight".to_string(), Prop::F64(1.5)),
+            ]))
+        );
+        assert_eq!(schema.coerce_edge_props(Some("other"), &props), Ok(None));
+        assert_eq!(schema.coerce_vertex_props(&props[1..]), Ok(None));
+
+        assert_eq!(
+            schema.coerce_vertex_props(&[("age".to_string(), Prop::F64(3.0))]),
+            Err(GraphError::PropertyTypeMismatch {
+                name: "age".to_string(),
+                expected: PropType::I64,
+                actual: PropType::F64,
+            })
+        );
+    }
+
+    #[test]
+    fn values_in_range_are_narrowed() {
+        let schema = Schema::new()
+            .vertex_prop("small", PropType::I32)
+            .vertex_prop("count", PropType::U64)
+            .vertex_prop("ratio", PropType::F32);
+
+        let props = vec![
+            ("small".to_string(), Prop::I64(-3)),
+            ("count".to_string(), Prop::I64(3)),
+            ("ratio".to_string(), Prop::F64(0.5)),
+        ];
+        assert_eq!(
+            schema.coerce_vertex_props(&props),
+            Ok(Some(vec![
+                ("small".to_string(), Prop::I32(-3)),
+                ("count".to_string(), Prop::U64(3)),
+                ("ratio".to_string(), Prop::F32(0.5)),
+            ]))
+        );
+
+        for (name, value, expected) in [
+            ("small", Prop::I64(1 << 40), PropType::I32),
+            ("count", Prop::I64(-1), PropType::U64),
+            ("ratio", Prop::F64(0.1), PropType::F32),
+        ] {
+            let actual = value.dtype();
+            assert_eq!(
+                schema.coerce_vertex_props(&[(name.to_string(), value)]),
+                Err(GraphError::PropertyTypeMismatch {
+                    name: name.to_string(),
+                    expected,
+                    actual,
+                })
+            );
+        }
+    }
+}
@@ -1,9 +1,15 @@
 //! A data structure for storing stateful data for temporal graphs and their shards.
 
 use crate::core::agg::Accumulator;
+use crate::core::tgraph_shard::errors::GraphError;
 use crate::core::utils::get_shard_id_from_global_vid;
-use rustc_hash::FxHashMap;
-use std::{any::Any, fmt::Debug};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    any::{Any, TypeId},
+    collections::BTreeMap,
+    fmt::Debug,
+};
 
 #[derive(Debug, Copy)]
 pub struct AccId<A, IN, OUT, ACC: Accumulator<A, IN, OUT>> {
@@ -36,14 +42,22 @@ unsafe impl<A, IN, OUT, ACC: Accumulator<A, IN, OUT>> Send for AccId<A, IN, OUT,
 
 unsafe impl<A, IN, OUT, ACC: Accumulator<A, IN, OUT>> Sync for AccId<A, IN, OUT, ACC> {}
 
+/// Accumulator ids from here up are handed out by `AccumulatorRegistry`, programs that use one
+/// that was not handed out fail with `GraphError::ReservedAccumulatorId` so hand-picked ids can
+/// never collide with registered ones.
+pub const REGISTERED_IDS_START: u32 = 1 << 31;
+
 pub mod def {
-    use super::{AccId, StateType};
+    use super::{register_codec, AccId, CheckpointState, StateType};
     use crate::core::agg::{
+        hll::{DistinctCount, HyperLogLog},
+        quantile::{Median, Quantiles, TDigest},
         set::{BitSet, Set},
         topk::{TopK, TopKHeap},
-        AvgDef, MaxDef, MinDef, SumDef, ValDef,
+        Accumulator, ArgMaxDef, ArgMinDef, AvgDef, CountDef, FirstDef, LastDef, MaxDef,
+        MessageList, MinDef, StableSumDef, SumDef, ValDef,
     };
-    use num_traits::{Bounded, Zero};
+    use num_traits::{Bounded, Float, ToPrimitive, Zero};
     use roaring::{RoaringBitmap, RoaringTreemap};
     use rustc_hash::FxHashSet;
     use std::{
@@ -52,7 +66,12 @@ pub mod def {
         ops::{AddAssign, Div},
     };
 
-    pub fn min<A: StateType + Bounded + PartialOrd>(id: u32) -> AccId<A, A, A, MinDef<A>> {
+    // every constructor registers the codec of its state, so any accumulator made here can be
+    // checkpointed
+    fn acc_id<A: CheckpointState, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+        id: u32,
+    ) -> AccId<A, IN, OUT, ACC> {
+        register_codec::<A>();
         AccId {
             id,
             _a: std::marker::PhantomData,
@@ -62,100 +81,278 @@ pub mod def {
         }
     }
 
-    pub fn max<A: StateType + Bounded + PartialOrd>(id: u32) -> AccId<A, A, A, MaxDef<A>> {
-        AccId {
-            id,
-            _a: std::marker::PhantomData,
-            _acc: std::marker::PhantomData,
-            _in: std::marker::PhantomData,
-            _out: std::marker::PhantomData,
-        }
+    pub fn min<A: CheckpointState + Bounded + PartialOrd>(id: u32) -> AccId<A, A, A, MinDef<A>> {
+        acc_id(id)
     }
 
-    pub fn sum<A: StateType + Zero + AddAssign<A>>(id: u32) -> AccId<A, A, A, SumDef<A>> {
-        AccId {
-            id,
-            _a: std::marker::PhantomData,
-            _acc: std::marker::PhantomData,
-            _in: std::marker::PhantomData,
-            _out: std::marker::PhantomData,
-        }
+    pub fn max<A: CheckpointState + Bounded + PartialOrd>(id: u32) -> AccId<A, A, A, MaxDef<A>> {
+        acc_id(id)
     }
 
-    pub fn val<A: StateType + Zero>(id: u32) -> AccId<A, A, A, ValDef<A>> {
-        AccId {
-            id,
-            _a: std::marker::PhantomData,
-            _acc: std::marker::PhantomData,
-            _in: std::marker::PhantomData,
-            _out: std::marker::PhantomData,
-        }
+    pub fn sum<A: CheckpointState + Zero + AddAssign<A>>(id: u32) -> AccId<A, A, A, SumDef<A>> {
+        acc_id(id)
+    }
+
+    /// Sums floating point values with compensated summation, see `StableSumDef`.
+    pub fn stable_sum<A: CheckpointState + Float>(id: u32) -> AccId<(A, A), A, A, StableSumDef<A>> {
+        acc_id(id)
+    }
+
+    pub fn val<A: CheckpointState + Zero>(id: u32) -> AccId<A, A, A, ValDef<A>> {
+        acc_id(id)
     }
 
     pub fn avg<A>(id: u32) -> AccId<(A, usize), A, A, AvgDef<A>>
     where
-        A: StateType + Zero + AddAssign<A> + TryFrom<usize> + Div<A, Output = A>,
+        A: CheckpointState + Zero + AddAssign<A> + TryFrom<usize> + Div<A, Output = A>,
         <A as TryFrom<usize>>::Error: std::fmt::Debug,
     {
-        AccId {
-            id,
-            _a: std::marker::PhantomData,
-            _acc: std::marker::PhantomData,
-            _in: std::marker::PhantomData,
-            _out: std::marker::PhantomData,
-        }
+        acc_id(id)
     }
 
-    pub fn topk<A: StateType + Ord, const N: usize>(
+    pub fn topk<A: CheckpointState + Ord, const N: usize>(
         id: u32,
     ) -> AccId<TopKHeap<A>, A, Vec<A>, TopK<A, N>> {
-        AccId {
-            id,
-            _a: std::marker::PhantomData,
-            _acc: std::marker::PhantomData,
-            _in: std::marker::PhantomData,
-            _out: std::marker::PhantomData,
-        }
+        acc_id(id)
     }
 
-    pub fn hash_set<A: StateType + Hash + Eq>(
+    pub fn hash_set<A: CheckpointState + Hash + Eq>(
         id: u32,
     ) -> AccId<FxHashSet<A>, A, FxHashSet<A>, Set<A>> {
-        AccId {
-            id,
-            _a: std::marker::PhantomData,
-            _acc: std::marker::PhantomData,
-            _in: std::marker::PhantomData,
-            _out: std::marker::PhantomData,
-        }
+        acc_id(id)
     }
 
     pub fn bit_set_32(id: u32) -> AccId<RoaringBitmap, u32, RoaringBitmap, BitSet<u32>> {
-        AccId {
-            id,
-            _a: std::marker::PhantomData,
-            _acc: std::marker::PhantomData,
-            _in: std::marker::PhantomData,
-            _out: std::marker::PhantomData,
-        }
+        acc_id(id)
+    }
+
+    /// A mailbox that keeps every message, in no particular order.
+    pub fn messages<A: CheckpointState>(id: u32) -> AccId<Vec<A>, A, Vec<A>, MessageList<A>> {
+        acc_id(id)
     }
 
     pub fn bit_set_64(id: u32) -> AccId<RoaringTreemap, u64, RoaringTreemap, BitSet<u64>> {
-        AccId {
+        acc_id(id)
+    }
+
+    pub type ArgMinId<K, V> = AccId<Option<(K, V)>, (K, V), Option<(K, V)>, ArgMinDef<K, V>>;
+    pub type ArgMaxId<K, V> = AccId<Option<(K, V)>, (K, V), Option<(K, V)>, ArgMaxDef<K, V>>;
+    pub type FirstId<A> = AccId<Option<(i64, A)>, (i64, A), Option<A>, FirstDef<A>>;
+    pub type LastId<A> = AccId<Option<(i64, A)>, (i64, A), Option<A>, LastDef<A>>;
+
+    pub fn count<A: StateType>(id: u32) -> AccId<usize, A, usize, CountDef<A>> {
+        acc_id(id)
+    }
+
+    /// Keeps the `(key, payload)` input with the smallest key.
+    pub fn arg_min<K: CheckpointState + PartialOrd, V: CheckpointState + PartialOrd>(
+        id: u32,
+    ) -> ArgMinId<K, V> {
+        acc_id(id)
+    }
+
+    /// Keeps the `(key, payload)` input with the largest key.
+    pub fn arg_max<K: CheckpointState + PartialOrd, V: CheckpointState + PartialOrd>(
+        id: u32,
+    ) -> ArgMaxId<K, V> {
+        acc_id(id)
+    }
+
+    /// Keeps the value of the earliest `(time, value)` input.
+    pub fn first<A: CheckpointState + PartialOrd>(id: u32) -> FirstId<A> {
+        acc_id(id)
+    }
+
+    /// Keeps the value of the latest `(time, value)` input.
+    pub fn last<A: CheckpointState + PartialOrd>(id: u32) -> LastId<A> {
+        acc_id(id)
+    }
+
+    /// The approximate median of the inputs.
+    pub fn median<A: StateType + ToPrimitive>(
+        id: u32,
+    ) -> AccId<TDigest, A, Option<f64>, Median<A>> {
+        acc_id(id)
+    }
+
+    /// A t-digest of the inputs, read it with `TDigest::quantile`.
+    pub fn quantiles<A: StateType + ToPrimitive>(
+        id: u32,
+    ) -> AccId<TDigest, A, TDigest, Quantiles<A>> {
+        acc_id(id)
+    }
+
+    /// Estimates the number of distinct inputs with a HyperLogLog sketch of `2^P` bytes, e.g.
+    /// `distinct_count::<u64, 12>` is usually within 2% of the exact count.
+    pub fn distinct_count<A: StateType + std::hash::Hash, const P: usize>(
+        id: u32,
+    ) -> AccId<HyperLogLog, A, u64, DistinctCount<A, P>> {
+        acc_id(id)
+    }
+}
+
+/// Hands out accumulator ids by name, so programs sharing state agree on them without picking
+/// ids by hand. Ids are allocated counting down from `u32::MAX` and never go below
+/// `REGISTERED_IDS_START`, so they can't collide with hand-picked ids.
+///
+/// The registry also remembers the accumulator type every hand-picked id was first used with,
+/// see `check`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccumulatorRegistry {
+    accumulators: FxHashMap<String, RegisteredAccumulator>,
+    hand_picked: FxHashMap<u32, RegisteredAccumulator>,
+    next_id: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RegisteredAccumulator {
+    id: u32,
+    // type ids are not stable across builds, registries read from a checkpoint compare names
+    #[serde(skip)]
+    type_id: Option<TypeId>,
+    type_name: String,
+}
+
+impl RegisteredAccumulator {
+    fn new<ACC: 'static>(id: u32, type_id: TypeId) -> Self {
+        Self {
             id,
-            _a: std::marker::PhantomData,
-            _acc: std::marker::PhantomData,
-            _in: std::marker::PhantomData,
-            _out: std::marker::PhantomData,
+            type_id: Some(type_id),
+            type_name: std::any::type_name::<ACC>().to_string(),
+        }
+    }
+
+    /// Whether the accumulator has the type `type_id`, the type of `ACC`.
+    fn has_type<ACC: 'static>(&mut self, type_id: TypeId) -> bool {
+        match self.type_id {
+            Some(id) => id == type_id,
+            None if self.type_name == std::any::type_name::<ACC>() => {
+                self.type_id = Some(type_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn mismatch<ACC: 'static>(&self, name: String) -> GraphError {
+        GraphError::AccumulatorTypeMismatch {
+            name,
+            registered: self.type_name.clone(),
+            requested: std::any::type_name::<ACC>().to_string(),
         }
     }
 }
 
+impl Default for AccumulatorRegistry {
+    fn default() -> Self {
+        Self {
+            accumulators: FxHashMap::default(),
+            hand_picked: FxHashMap::default(),
+            next_id: u32::MAX,
+        }
+    }
+}
+
+impl AccumulatorRegistry {
+    /// Returns the accumulator registered under `name`, registering it if it is new.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the accumulator
+    /// * `def` - One of the constructors in `def`, e.g. `def::sum::<u64>`
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::AccumulatorTypeMismatch` if `name` is already registered with a
+    /// different accumulator type.
+    pub fn register<A, IN, OUT, ACC, F>(
+        &mut self,
+        name: &str,
+        def: F,
+    ) -> Result<AccId<A, IN, OUT, ACC>, GraphError>
+    where
+        A: StateType,
+        IN: 'static,
+        OUT: 'static,
+        ACC: Accumulator<A, IN, OUT> + 'static,
+        F: FnOnce(u32) -> AccId<A, IN, OUT, ACC>,
+    {
+        let type_id = TypeId::of::<AccId<A, IN, OUT, ACC>>();
+
+        match self.accumulators.get_mut(name) {
+            Some(acc) => {
+                if acc.has_type::<ACC>(type_id) {
+                    Ok(def(acc.id))
+                } else {
+                    Err(acc.mismatch::<ACC>(name.to_string()))
+                }
+            }
+            None => {
+                let id = self.next_id;
+                assert!(id >= REGISTERED_IDS_START, "ran out of accumulator ids");
+                self.next_id -= 1;
+                self.accumulators.insert(
+                    name.to_string(),
+                    RegisteredAccumulator::new::<ACC>(id, type_id),
+                );
+                Ok(def(id))
+            }
+        }
+    }
+
+    /// Checks that `acc` has the type its id was registered with or, for hand-picked ids, the
+    /// type the id was first used with.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::AccumulatorTypeMismatch` if the id is used with another type, or
+    /// `GraphError::ReservedAccumulatorId` if the id is in the registered range but was not
+    /// handed out by this registry.
+    pub fn check<A, IN, OUT, ACC>(&mut self, acc: &AccId<A, IN, OUT, ACC>) -> Result<(), GraphError>
+    where
+        A: StateType,
+        IN: 'static,
+        OUT: 'static,
+        ACC: Accumulator<A, IN, OUT> + 'static,
+    {
+        let type_id = TypeId::of::<AccId<A, IN, OUT, ACC>>();
+        let id = acc.id();
+        if id >= REGISTERED_IDS_START {
+            let (name, registered) = self
+                .accumulators
+                .iter_mut()
+                .find(|(_, registered)| registered.id == id)
+                .ok_or(GraphError::ReservedAccumulatorId { id })?;
+            if !registered.has_type::<ACC>(type_id) {
+                return Err(registered.mismatch::<ACC>(name.clone()));
+            }
+            return Ok(());
+        }
+        let registered = self
+            .hand_picked
+            .entry(id)
+            .or_insert_with(|| RegisteredAccumulator::new::<ACC>(id, type_id));
+        if registered.has_type::<ACC>(type_id) {
+            Ok(())
+        } else {
+            Err(registered.mismatch::<ACC>(id.to_string()))
+        }
+    }
+
+    /// Returns the id of the accumulator registered under `name`.
+    pub fn id(&self, name: &str) -> Option<u32> {
+        self.accumulators.get(name).map(|acc| acc.id)
+    }
+}
+
 pub trait DynArray: Debug + Send + Sync {
     fn as_any(&self) -> &dyn Any;
     fn as_mut_any(&mut self) -> &mut dyn Any;
     fn clone_array(&self) -> Box<dyn DynArray>;
     fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
     fn copy_from(&mut self, other: &dyn DynArray);
     // used for tricks
     fn empty(&self) -> Box<dyn DynArray>;
@@ -164,6 +361,7 @@ pub trait DynArray: Debug + Send + Sync {
     fn reset(&mut self, ss: usize);
     fn iter_keys(&self) -> Box<dyn Iterator<Item = u64> + '_>;
     fn iter_keys_changed(&self, ss: usize) -> Box<dyn Iterator<Item = u64> + '_>;
+    fn type_name(&self) -> &'static str;
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -198,7 +396,10 @@ where
     }
 
     fn empty(&self) -> Box<dyn DynArray> {
-        panic!("not implemented");
+        Box::new(MapArray {
+            map: FxHashMap::default(),
+            zero: self.zero.clone(),
+        })
     }
 
     fn copy_over(&mut self, ss: usize) {
@@ -225,6 +426,10 @@ where
         }))
     }
 
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
+
     fn reset(&mut self, ss: usize) {
         for val in self.map.values_mut() {
             let i = (ss + 1) % 2;
@@ -233,10 +438,109 @@ where
     }
 }
 
+/// The state of an accumulator in a checkpoint, see `ShuffleComputeState::encode`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct EncodedArray {
+    // type ids are not stable across builds, decoding compares names
+    type_name: String,
+    map: FxHashMap<u64, [Vec<u8>; 2]>,
+    zero: Vec<u8>,
+}
+
+fn encode_array<T: CheckpointState>(
+    array: &dyn DynArray,
+) -> Option<Result<EncodedArray, GraphError>> {
+    let array = array.as_any().downcast_ref::<MapArray<T>>()?;
+    let encode = || {
+        let map = array
+            .map
+            .iter()
+            .map(|(k, [v0, v1])| Ok((*k, [bincode::serialize(v0)?, bincode::serialize(v1)?])))
+            .collect::<Result<_, bincode::Error>>()?;
+        Ok(EncodedArray {
+            type_name: std::any::type_name::<T>().to_string(),
+            map,
+            zero: bincode::serialize(&array.zero)?,
+        })
+    };
+    Some(encode())
+}
+
+fn decode_array<T: CheckpointState>(
+    encoded: &EncodedArray,
+) -> Result<Box<dyn DynArray>, GraphError> {
+    let map = encoded
+        .map
+        .iter()
+        .map(|(k, [v0, v1])| Ok((*k, [bincode::deserialize(v0)?, bincode::deserialize(v1)?])))
+        .collect::<Result<_, bincode::Error>>()?;
+    Ok(Box::new(MapArray::<T> {
+        map,
+        zero: bincode::deserialize(&encoded.zero)?,
+    }))
+}
+
+#[derive(Debug, Clone, Copy)]
+struct StateCodec {
+    // `None` if the state has a different type
+    encode: fn(&dyn DynArray) -> Option<Result<EncodedArray, GraphError>>,
+    decode: fn(&EncodedArray) -> Result<Box<dyn DynArray>, GraphError>,
+}
+
+// the codecs of every state type an accumulator was made with, by type name
+static STATE_CODECS: parking_lot::RwLock<BTreeMap<&'static str, StateCodec>> =
+    parking_lot::const_rwlock(BTreeMap::new());
+
+/// Registers the codec of the state type `T`, called by the `def` constructors.
+fn register_codec<T: CheckpointState>() {
+    let type_name = std::any::type_name::<T>();
+    if !STATE_CODECS.read().contains_key(type_name) {
+        STATE_CODECS.write().insert(
+            type_name,
+            StateCodec {
+                encode: encode_array::<T>,
+                decode: decode_array::<T>,
+            },
+        );
+    }
+}
+
+impl ComputeStateMap {
+    fn encode(&self, id: u32) -> Result<EncodedArray, GraphError> {
+        let array = self.current();
+        let unregistered = || GraphError::UnregisteredStateCodec {
+            id,
+            state_type: array.type_name().to_string(),
+        };
+        let codec = STATE_CODECS
+            .read()
+            .get(array.type_name())
+            .copied()
+            .ok_or_else(unregistered)?;
+        (codec.encode)(array).unwrap_or_else(|| Err(unregistered()))
+    }
+
+    /// Decodes a state written by `encode`, `None` if no accumulator with its state type was
+    /// made yet.
+    fn decode(encoded: &EncodedArray) -> Option<Result<Self, GraphError>> {
+        let codec = STATE_CODECS
+            .read()
+            .get(encoded.type_name.as_str())
+            .copied()?;
+        Some((codec.decode)(encoded).map(ComputeStateMap))
+    }
+}
+
 pub trait StateType: PartialEq + Clone + Debug + Send + Sync + 'static {}
 
 impl<T: PartialEq + Clone + Debug + Send + Sync + 'static> StateType for T {}
 
+/// A state type that can be written to a checkpoint. The `def` constructors only take
+/// accumulators with such states, and register how to encode them.
+pub trait CheckpointState: StateType + Serialize + DeserializeOwned {}
+
+impl<T: StateType + Serialize + DeserializeOwned> CheckpointState for T {}
+
 pub trait ComputeState: Debug + Clone {
     fn clone_current_into_other(&mut self, ss: usize);
 
@@ -473,6 +777,8 @@ const GLOBAL_STATE_KEY: usize = 0;
 #[derive(Debug, Clone)]
 pub struct ShardComputeState<CS: ComputeState + Send> {
     states: FxHashMap<u32, CS>,
+    // checkpointed states whose type no accumulator was made with yet, see `decode_pending`
+    pending: FxHashMap<u32, EncodedArray>,
 }
 
 impl<CS: ComputeState + Send + Clone> ShardComputeState<CS> {
@@ -482,7 +788,7 @@ impl<CS: ComputeState + Send + Clone> ShardComputeState<CS> {
         }
     }
 
-    fn reset_states(&mut self, ss: usize, states: &Vec<u32>) {
+    fn reset_states(&mut self, ss: usize, states: &[u32]) {
         for (id, state) in self.states.iter_mut() {
             if states.contains(id) {
                 state.reset_resetable_states(ss);
@@ -597,6 +903,7 @@ impl<CS: ComputeState + Send + Clone> ShardComputeState<CS> {
     fn new() -> Self {
         ShardComputeState {
             states: FxHashMap::default(),
+            pending: FxHashMap::default(),
         }
     }
 
@@ -640,6 +947,145 @@ impl<CS: ComputeState + Send> ShardComputeState<CS> {
 pub struct ShuffleComputeState<CS: ComputeState + Send> {
     pub global: ShardComputeState<CS>,
     pub parts: Vec<ShardComputeState<CS>>,
+    // edge state lives in the partition of the source vertex, one state per source and layer
+    // keyed by the destination, a shard only keeps the edges of its own vertices
+    pub edges: Vec<FxHashMap<(u64, usize), ShardComputeState<CS>>>,
+    // the (source, layer) edge states each accumulator updated since its last merge, the only
+    // ones `merge_mut` and `set_from_other` ship
+    changed_edges: FxHashMap<u32, FxHashSet<(u64, usize)>>,
+    // number of accumulator updates since the last `take_updates`
+    updates: usize,
+}
+
+impl ShardComputeState<ComputeStateMap> {
+    fn encode(&self) -> Result<EncodedShardState, GraphError> {
+        self.states
+            .iter()
+            .map(|(id, state)| Ok((*id, state.encode(*id)?)))
+            .chain(
+                self.pending
+                    .iter()
+                    .map(|(id, state)| Ok((*id, state.clone()))),
+            )
+            .collect()
+    }
+
+    fn decode(encoded: &EncodedShardState) -> Result<Self, GraphError> {
+        let mut state = Self::new();
+        for (id, encoded) in encoded {
+            match ComputeStateMap::decode(encoded) {
+                Some(decoded) => {
+                    state.states.insert(*id, decoded?);
+                }
+                None => {
+                    state.pending.insert(*id, encoded.clone());
+                }
+            }
+        }
+        Ok(state)
+    }
+
+    fn decode_pending(&mut self, id: u32) -> Result<(), GraphError> {
+        if let Some(encoded) = self.pending.remove(&id) {
+            let state = ComputeStateMap::decode(&encoded).ok_or_else(|| {
+                GraphError::UnregisteredStateCodec {
+                    id,
+                    state_type: encoded.type_name.clone(),
+                }
+            })??;
+            self.states.insert(id, state);
+        }
+        Ok(())
+    }
+}
+
+type EncodedShardState = FxHashMap<u32, EncodedArray>;
+
+/// The state of a `ShuffleComputeState` in a checkpoint, see `ShuffleComputeState::encode`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncodedState {
+    global: EncodedShardState,
+    parts: Vec<EncodedShardState>,
+    edges: Vec<FxHashMap<(u64, usize), EncodedShardState>>,
+}
+
+impl ShuffleComputeState<ComputeStateMap> {
+    /// Encodes the state of the accumulators for a checkpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::UnregisteredStateCodec` if a state has a type without a codec,
+    /// which can't happen for accumulators made with `def`.
+    pub fn encode(&self) -> Result<EncodedState, GraphError> {
+        Ok(EncodedState {
+            global: self.global.encode()?,
+            parts: self
+                .parts
+                .iter()
+                .map(|part| part.encode())
+                .collect::<Result<_, _>>()?,
+            edges: self
+                .edges
+                .iter()
+                .map(|part| {
+                    part.iter()
+                        .map(|(key, state)| Ok((*key, state.encode()?)))
+                        .collect::<Result<_, GraphError>>()
+                })
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Decodes the checkpointed state of accumulator `id` if it was left encoded by
+    /// `EncodedState::decode`, has to be called before the accumulator is used.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::UnregisteredStateCodec` if still no accumulator was made with the
+    /// state type, e.g. because `id` is used with another accumulator than in the checkpoint.
+    pub fn decode_pending(&mut self, id: u32) -> Result<(), GraphError> {
+        self.global.decode_pending(id)?;
+        for part in &mut self.parts {
+            part.decode_pending(id)?;
+        }
+        for part in &mut self.edges {
+            for state in part.values_mut() {
+                state.decode_pending(id)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl EncodedState {
+    /// Decodes the state written by `ShuffleComputeState::encode`. States whose type no
+    /// accumulator was made with yet, e.g. in a new process, stay encoded until their
+    /// accumulator is used, see `ShuffleComputeState::decode_pending`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::Io` if a state can't be decoded.
+    pub fn decode(&self) -> Result<ShuffleComputeState<ComputeStateMap>, GraphError> {
+        Ok(ShuffleComputeState {
+            global: ShardComputeState::decode(&self.global)?,
+            parts: self
+                .parts
+                .iter()
+                .map(ShardComputeState::decode)
+                .collect::<Result<_, _>>()?,
+            edges: self
+                .edges
+                .iter()
+                .map(|part| {
+                    part.iter()
+                        .map(|(key, state)| Ok((*key, ShardComputeState::decode(state)?)))
+                        .collect::<Result<_, GraphError>>()
+                })
+                .collect::<Result<_, _>>()?,
+            changed_edges: FxHashMap::default(),
+            updates: 0,
+        })
+    }
 }
 
 // every partition has a struct as such
@@ -679,13 +1125,39 @@ impl<CS: ComputeState + Send + Sync> ShuffleComputeState<CS> {
             .iter_mut()
             .zip(other.parts.iter())
             .for_each(|(s, o)| s.merge(o, agg_ref, ss));
+
+        // only the edges `other` updated are merged, the ones only `other` updated replace the
+        // copy in `self` instead of adding to it
+        let changed = match other.changed_edges.get(&agg_ref.id) {
+            Some(changed) => changed,
+            None => return,
+        };
+        let self_changed = self.changed_edges.entry(agg_ref.id).or_default();
+        for key in changed {
+            let part = get_shard_id_from_global_vid(key.0, self.edges.len());
+            if let Some(o) = other.edges[part].get(key) {
+                let s = self.edges[part]
+                    .entry(*key)
+                    .or_insert_with(ShardComputeState::new);
+                if self_changed.contains(key) {
+                    s.merge(o, agg_ref, ss);
+                } else {
+                    s.set_from_other(o, agg_ref, ss);
+                }
+            }
+        }
+        self_changed.extend(changed);
     }
 
+    /// Sets the state of `agg_ref` to the merged state in `other`. Of the edge state only the
+    /// changed edges that `shard`, the shard this state belongs to, can see are copied, see
+    /// `retain_edges`.
     pub fn set_from_other<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
         &mut self,
         other: &Self,
         agg_ref: &AccId<A, IN, OUT, ACC>,
         ss: usize,
+        shard: usize,
     ) where
         A: StateType,
     {
@@ -696,6 +1168,58 @@ impl<CS: ComputeState + Send + Sync> ShuffleComputeState<CS> {
             .iter_mut()
             .zip(other.parts.iter())
             .for_each(|(s, o)| s.set_from_other(o, agg_ref, ss));
+
+        self.changed_edges.remove(&agg_ref.id);
+        for key in other.changed_edges.get(&agg_ref.id).into_iter().flatten() {
+            let part = get_shard_id_from_global_vid(key.0, self.edges.len());
+            if let Some(o) = other.edges[part].get(key) {
+                if Self::sees_edge(o, key.0, agg_ref.id, shard, self.edges.len()) {
+                    self.edges[part]
+                        .entry(*key)
+                        .or_insert_with(ShardComputeState::new)
+                        .set_from_other(o, agg_ref, ss);
+                }
+            }
+        }
+    }
+
+    /// Drops the state of `agg_ref` on the edges `shard` can't see, which `merge_mut` brought
+    /// in from the other shards, and marks the edges as shipped. Called on the merged state
+    /// after `set_from_other` copied it to the other shards.
+    pub fn retain_edges<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+        &mut self,
+        agg_ref: &AccId<A, IN, OUT, ACC>,
+        shard: usize,
+    ) where
+        A: StateType,
+    {
+        let n_shards = self.edges.len();
+        for key in self.changed_edges.remove(&agg_ref.id).into_iter().flatten() {
+            let part = get_shard_id_from_global_vid(key.0, n_shards);
+            if let Some(state) = self.edges[part].get_mut(&key) {
+                if !Self::sees_edge(state, key.0, agg_ref.id, shard, n_shards) {
+                    state.states.remove(&agg_ref.id);
+                    if state.states.is_empty() && state.pending.is_empty() {
+                        self.edges[part].remove(&key);
+                    }
+                }
+            }
+        }
+    }
+
+    // the vertices of a shard see the edges they are the source or destination of
+    fn sees_edge(
+        state: &ShardComputeState<CS>,
+        src: u64,
+        id: u32,
+        shard: usize,
+        n_shards: usize,
+    ) -> bool {
+        get_shard_id_from_global_vid(src, n_shards) == shard
+            || state.states.get(&id).map_or(false, |cs| {
+                cs.iter_keys()
+                    .any(|dst| get_shard_id_from_global_vid(dst, n_shards) == shard)
+            })
     }
 
     pub fn merge_mut_global<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
@@ -711,37 +1235,50 @@ impl<CS: ComputeState + Send + Sync> ShuffleComputeState<CS> {
 
     pub fn copy_over_next_ss(&mut self, ss: usize) {
         self.parts.iter_mut().for_each(|p| p.copy_over_next_ss(ss));
+        self.edges
+            .iter_mut()
+            .flat_map(|p| p.values_mut())
+            .for_each(|p| p.copy_over_next_ss(ss));
     }
 
-    pub fn reset_states(&mut self, ss: usize, states: &Vec<u32>) {
+    pub fn reset_states(&mut self, ss: usize, states: &[u32]) {
         self.global.reset_states(ss, states);
         self.parts
             .iter_mut()
             .for_each(|p| p.reset_states(ss, states));
+        self.edges
+            .iter_mut()
+            .flat_map(|p| p.values_mut())
+            .for_each(|p| p.reset_states(ss, states));
     }
 
     pub fn new(n_parts: usize) -> Self {
         Self {
-            parts: (0..n_parts)
-                .into_iter()
-                .map(|_| ShardComputeState::new())
-                .collect(),
+            parts: (0..n_parts).map(|_| ShardComputeState::new()).collect(),
+            edges: (0..n_parts).map(|_| FxHashMap::default()).collect(),
+            changed_edges: FxHashMap::default(),
             global: ShardComputeState::new(),
+            updates: 0,
         }
     }
 
+    /// Returns the number of accumulator updates since the last call and resets it.
+    pub fn take_updates(&mut self) -> usize {
+        std::mem::take(&mut self.updates)
+    }
+
     pub fn keys(&self, part_num: usize) -> impl Iterator<Item = u64> + '_ {
         self.parts[part_num]
             .states
-            .iter()
-            .flat_map(|(_, cs)| cs.iter_keys())
+            .values()
+            .flat_map(|cs| cs.iter_keys())
     }
 
     pub fn changed_keys(&self, part_num: usize, ss: usize) -> impl Iterator<Item = u64> + '_ {
         self.parts[part_num]
             .states
-            .iter()
-            .flat_map(move |(_, cs)| cs.iter_keys_changed(ss))
+            .values()
+            .flat_map(move |cs| cs.iter_keys_changed(ss))
     }
 
     pub fn accumulate_into<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
@@ -753,10 +1290,37 @@ impl<CS: ComputeState + Send + Sync> ShuffleComputeState<CS> {
     ) where
         A: StateType,
     {
+        self.updates += 1;
         let part = get_shard_id_from_global_vid(into as u64, self.parts.len());
         self.parts[part].accumulate_into(ss, into, a, agg_ref)
     }
 
+    /// Accumulates `a` into the state of the edge from `src` to `dst` in `layer`. Edge updates
+    /// don't change the keys of the vertex state, so they don't activate vertices for the next
+    /// step.
+    pub fn accumulate_into_edge<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+        &mut self,
+        ss: usize,
+        src: u64,
+        dst: u64,
+        layer: usize,
+        a: IN,
+        agg_ref: &AccId<A, IN, OUT, ACC>,
+    ) where
+        A: StateType,
+    {
+        self.updates += 1;
+        self.changed_edges
+            .entry(agg_ref.id)
+            .or_default()
+            .insert((src, layer));
+        let part = get_shard_id_from_global_vid(src, self.edges.len());
+        self.edges[part]
+            .entry((src, layer))
+            .or_insert_with(ShardComputeState::new)
+            .accumulate_into(ss, dst as usize, a, agg_ref)
+    }
+
     pub fn accumulate_global<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
         &mut self,
         ss: usize,
@@ -765,6 +1329,7 @@ impl<CS: ComputeState + Send + Sync> ShuffleComputeState<CS> {
     ) where
         A: StateType,
     {
+        self.updates += 1;
         self.global
             .accumulate_into(ss, GLOBAL_STATE_KEY, a, agg_ref)
     }
@@ -796,6 +1361,24 @@ impl<CS: ComputeState + Send + Sync> ShuffleComputeState<CS> {
         self.parts[part].read_ref::<A, IN, OUT, ACC>(into, agg_ref.id, ss)
     }
 
+    pub fn read_edge<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+        &self,
+        ss: usize,
+        src: u64,
+        dst: u64,
+        layer: usize,
+        agg_ref: &AccId<A, IN, OUT, ACC>,
+    ) -> Option<OUT>
+    where
+        A: StateType,
+        OUT: Debug,
+    {
+        let part = get_shard_id_from_global_vid(src, self.edges.len());
+        self.edges[part]
+            .get(&(src, layer))?
+            .read::<A, IN, OUT, ACC>(dst as usize, agg_ref.id, ss)
+    }
+
     pub fn read_global<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
         &self,
         ss: usize,
@@ -850,8 +1433,63 @@ mod state_test {
     // merge the monoids in parallel
 
     use super::*;
+    use crate::core::agg::MessageList;
     use rand::Rng;
 
+    #[test]
+    fn registry_checks_accumulator_types() {
+        let mut registry = AccumulatorRegistry::default();
+
+        let score = registry.register("score", def::sum::<f64>).unwrap();
+        let count = registry.register("count", def::sum::<u64>).unwrap();
+        assert_ne!(score.id(), count.id());
+        assert!(score.id() >= REGISTERED_IDS_START && count.id() >= REGISTERED_IDS_START);
+        assert_eq!(registry.id("score"), Some(score.id()));
+
+        // registering again with the same type returns the same accumulator
+        let again = registry.register("score", def::sum::<f64>).unwrap();
+        assert_eq!(again.id(), score.id());
+
+        assert_eq!(
+            registry.register("score", def::max::<f64>).unwrap_err(),
+            GraphError::AccumulatorTypeMismatch {
+                name: "score".to_string(),
+                registered: "docbrown::core::agg::SumDef<f64>".to_string(),
+                requested: "docbrown::core::agg::MaxDef<f64>".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn registry_checks_hand_picked_ids() {
+        let mut registry = AccumulatorRegistry::default();
+
+        registry.check(&def::sum::<u64>(0)).unwrap();
+        registry.check(&def::sum::<u64>(0)).unwrap();
+        registry.check(&def::sum::<f64>(1)).unwrap();
+        assert_eq!(
+            registry.check(&def::max::<u64>(0)),
+            Err(GraphError::AccumulatorTypeMismatch {
+                name: "0".to_string(),
+                registered: "docbrown::core::agg::SumDef<u64>".to_string(),
+                requested: "docbrown::core::agg::MaxDef<u64>".to_string(),
+            })
+        );
+
+        let score = registry.register("score", def::sum::<f64>).unwrap();
+        registry.check(&score).unwrap();
+        assert!(matches!(
+            registry.check(&def::min::<f64>(score.id())),
+            Err(GraphError::AccumulatorTypeMismatch { name, .. }) if name == "score"
+        ));
+        assert_eq!(
+            registry.check(&def::sum::<u64>(REGISTERED_IDS_START)),
+            Err(GraphError::ReservedAccumulatorId {
+                id: REGISTERED_IDS_START
+            })
+        );
+    }
+
     #[test]
     fn min_aggregates_for_3_keys() {
         let min = def::min(0);
@@ -1016,6 +1654,145 @@ mod state_test {
         );
     }
 
+    #[test]
+    fn edge_state_merges_across_parts() {
+        let sum = def::sum::<u64>(0);
+
+        let mut part1_state: ShuffleComputeState<ComputeStateMap> = ShuffleComputeState::new(2);
+        let mut part2_state: ShuffleComputeState<ComputeStateMap> = ShuffleComputeState::new(2);
+
+        part1_state.accumulate_into_edge(0, 1, 2, 0, 3, &sum);
+        part2_state.accumulate_into_edge(0, 1, 2, 0, 4, &sum);
+        part2_state.accumulate_into_edge(0, 2, 1, 0, 5, &sum);
+        part2_state.accumulate_into_edge(0, 1, 2, 1, 6, &sum);
+
+        part1_state.merge_mut(&part2_state, &sum, 0);
+
+        assert_eq!(part1_state.read_edge(0, 1, 2, 0, &sum), Some(7));
+        assert_eq!(part1_state.read_edge(0, 2, 1, 0, &sum), Some(5));
+        assert_eq!(part1_state.read_edge(0, 1, 3, 0, &sum), None);
+        // edges in different layers keep their own state
+        assert_eq!(part1_state.read_edge(0, 1, 2, 1, &sum), Some(6));
+        // edge updates leave the vertex state alone
+        assert_eq!(part1_state.read(0, 1, &sum), None);
+        assert_eq!(part1_state.keys(1).count(), 0);
+    }
+
+    #[test]
+    fn shards_only_get_the_changed_edges_they_see() {
+        let sum = def::sum::<u64>(0);
+
+        let mut shards: Vec<ShuffleComputeState<ComputeStateMap>> =
+            (0..3).map(|_| ShuffleComputeState::new(3)).collect();
+        // 0 -> 1 is seen by shards 0 and 1, 2 -> 2 only by shard 2
+        shards[0].accumulate_into_edge(0, 0, 1, 0, 3, &sum);
+        shards[1].accumulate_into_edge(0, 0, 1, 0, 4, &sum);
+        shards[2].accumulate_into_edge(0, 2, 2, 0, 5, &sum);
+
+        let mut merged = shards[0].clone();
+        merged.merge_mut(&shards[1], &sum, 0);
+        merged.merge_mut(&shards[2], &sum, 0);
+        for (shard, state) in shards.iter_mut().enumerate().skip(1) {
+            state.set_from_other(&merged, &sum, 0, shard);
+        }
+        merged.retain_edges(&sum, 0);
+        shards[0] = merged;
+
+        assert_eq!(shards[0].read_edge(0, 0, 1, 0, &sum), Some(7));
+        assert_eq!(shards[1].read_edge(0, 0, 1, 0, &sum), Some(7));
+        assert_eq!(shards[2].read_edge(0, 0, 1, 0, &sum), None);
+        assert_eq!(shards[0].read_edge(0, 2, 2, 0, &sum), None);
+        assert_eq!(shards[2].read_edge(0, 2, 2, 0, &sum), Some(5));
+        assert!(shards[0].edges[2].is_empty());
+
+        // edges nobody updated since the last merge are left as they are
+        shards[2].accumulate_into_edge(1, 2, 2, 0, 1, &sum);
+        let mut merged = shards[0].clone();
+        merged.merge_mut(&shards[1], &sum, 1);
+        merged.merge_mut(&shards[2], &sum, 1);
+        assert_eq!(merged.read_edge(0, 0, 1, 0, &sum), Some(7));
+        assert_eq!(merged.read_edge(1, 2, 2, 0, &sum), Some(1));
+    }
+
+    #[test]
+    fn state_roundtrips_through_bincode() {
+        let sum = def::sum::<u64>(0);
+        let min = def::min::<u64>(1);
+
+        let mut state: ShuffleComputeState<ComputeStateMap> = ShuffleComputeState::new(2);
+        state.accumulate_into(0, 1, 3, &sum);
+        state.accumulate_into(0, 2, 4, &min);
+        state.accumulate_into_edge(0, 1, 2, 0, 5, &sum);
+
+        let mut registry = AccumulatorRegistry::default();
+        registry.register("sum", def::sum::<u64>).unwrap();
+
+        let bytes = bincode::serialize(&(state.encode().unwrap(), &registry)).unwrap();
+        let (encoded, mut registry): (EncodedState, AccumulatorRegistry) =
+            bincode::deserialize(&bytes).unwrap();
+        let mut state = encoded.decode().unwrap();
+
+        assert_eq!(state.keys(1).count(), 1);
+        state.copy_over_next_ss(0);
+        assert_eq!(state.read(1, 1, &sum), Some(3));
+        assert_eq!(state.read(1, 2, &min), Some(4));
+        assert_eq!(state.read_edge(1, 1, 2, 0, &sum), Some(5));
+
+        assert!(registry.register("sum", def::sum::<u64>).is_ok());
+        assert!(registry.register("sum", def::min::<u64>).is_err());
+    }
+
+    #[test]
+    fn states_of_types_without_a_codec_stay_encoded() {
+        let sum = def::sum::<u64>(0);
+        let mut state: ShuffleComputeState<ComputeStateMap> = ShuffleComputeState::new(2);
+        state.accumulate_into(0, 1, 3, &sum);
+
+        // what a new process sees before any accumulator with the state type was made
+        let mut encoded = state.encode().unwrap();
+        encoded.parts[1].get_mut(&0).unwrap().type_name = "Unknown".to_string();
+        let mut state = encoded.decode().unwrap();
+        assert_eq!(state.read(0, 1, &sum), None);
+        assert_eq!(state.encode().unwrap().parts, encoded.parts);
+        assert_eq!(
+            state.decode_pending(0),
+            Err(GraphError::UnregisteredStateCodec {
+                id: 0,
+                state_type: "Unknown".to_string(),
+            })
+        );
+
+        encoded.parts[1].get_mut(&0).unwrap().type_name = "u64".to_string();
+        let mut state = encoded.decode().unwrap();
+        state.decode_pending(0).unwrap();
+        assert_eq!(state.read(0, 1, &sum), Some(3));
+    }
+
+    #[test]
+    fn states_without_a_codec_are_not_checkpointed() {
+        #[derive(Debug, Clone, PartialEq)]
+        struct Opaque(u64);
+
+        // only the `def` constructors register codecs
+        let messages: AccId<Vec<Opaque>, Opaque, Vec<Opaque>, MessageList<Opaque>> = AccId {
+            id: 0,
+            _a: std::marker::PhantomData,
+            _acc: std::marker::PhantomData,
+            _in: std::marker::PhantomData,
+            _out: std::marker::PhantomData,
+        };
+        let mut state: ShuffleComputeState<ComputeStateMap> = ShuffleComputeState::new(1);
+        state.accumulate_into(0, 1, Opaque(3), &messages);
+        assert_eq!(state.read(0, 1, &messages), Some(vec![Opaque(3)]));
+        assert_eq!(
+            state.encode().unwrap_err(),
+            GraphError::UnregisteredStateCodec {
+                id: 0,
+                state_type: std::any::type_name::<Vec<Opaque>>().to_string(),
+            }
+        );
+    }
+
     #[test]
     fn min_sum_aggregates_for_3_keys_2_parts() {
         let sum = def::sum(0);
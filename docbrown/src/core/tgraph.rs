@@ -11,17 +11,19 @@ use serde::{Deserialize, Serialize};
 
 use crate::core::edge_layer::EdgeLayer;
 use crate::core::props::Props;
+use crate::core::tgraph_shard::errors::GraphError;
 use crate::core::tprop::TProp;
 use crate::core::vertex::InputVertex;
 use crate::core::{bitset::BitSet, Direction};
-use crate::core::{Prop, Time};
+use crate::core::{Prop, PropType, Time};
 
 use self::errors::MutateGraphError;
 
 use super::utils;
 
 pub(crate) mod errors {
-    use crate::core::props::IllegalMutate;
+    use crate::core::props::{IllegalMutate, IllegalPropKind};
+    use crate::core::PropType;
 
     #[derive(thiserror::Error, Debug, PartialEq)]
     pub enum MutateGraphError {
@@ -40,10 +42,22 @@ pub(crate) mod errors {
             dst_id: u64,
             source: IllegalMutate,
         },
-        #[error("cannot update property as is '{first_type}' and '{second_type}' given'")]
+        #[error("cannot add property to vertex '{vertex_id}'")]
+        IllegalVertexPropertyKind {
+            vertex_id: u64,
+            source: IllegalPropKind,
+        },
+        #[error("cannot add property to edge '{src_id}' -> '{dst_id}'")]
+        IllegalEdgePropertyKind {
+            src_id: u64,
+            dst_id: u64,
+            source: IllegalPropKind,
+        },
+        #[error("cannot update property '{name}' as is '{first_type}' and '{second_type}' given")]
         PropertyChangedType {
-            first_type: &'static str,
-            second_type: &'static str,
+            name: String,
+            first_type: PropType,
+            second_type: PropType,
         },
     }
 }
@@ -227,6 +241,15 @@ impl TemporalGraph {
         v: T,
         props: &Vec<(String, Prop)>,
     ) -> MutateGraphResult {
+        self.vertex_props
+            .check_prop_kinds(props, false)
+            .map_err(|e| MutateGraphError::IllegalVertexPropertyKind {
+                vertex_id: v.id(),
+                source: e,
+            })?;
+        if let Some(&pid) = self.logical_to_physical.get(&v.id()) {
+            self.vertex_props.check_prop_types(pid, props)?;
+        }
         //Updating time - only needs to be here as every other adding function calls this one
         if self.earliest_time > t {
             self.earliest_time = t
@@ -273,7 +296,7 @@ impl TemporalGraph {
                 source: e,
             })?
         }
-        Ok(self.vertex_props.upsert_temporal_props(t, index, props))
+        self.vertex_props.upsert_temporal_props(t, index, props)
     }
 
     pub(crate) fn add_vertex_properties(
@@ -285,6 +308,12 @@ impl TemporalGraph {
             .logical_to_physical
             .get(&v)
             .ok_or(MutateGraphError::VertexNotFoundError { vertex_id: v })?);
+        self.vertex_props
+            .check_prop_kinds(data, true)
+            .map_err(|e| MutateGraphError::IllegalVertexPropertyKind {
+                vertex_id: v,
+                source: e,
+            })?;
         let result = self.vertex_props.set_static_props(index, data);
         result.map_err(|e| MutateGraphError::IllegalVertexPropertyChange {
             vertex_id: v,
@@ -295,6 +324,57 @@ impl TemporalGraph {
     // TODO: remove this??? it's only used for tests, we can use the other one instead
     pub fn add_edge<T: InputVertex>(&mut self, t: i64, src: T, dst: T, layer: usize) {
         self.add_edge_with_props(t, src, dst, &vec![], layer)
+            .expect("an edge without properties can always be added")
+    }
+
+    /// Checks that the vertex properties and the edge properties of each of `layers` already in
+    /// the graph have the declared types.
+    pub(crate) fn check_declared_types(
+        &self,
+        vertex_props: &HashMap<String, PropType>,
+        layers: &[(usize, &HashMap<String, PropType>)],
+    ) -> Result<(), GraphError> {
+        self.vertex_props.check_declared_types(vertex_props)?;
+        for (layer, declared) in layers {
+            if let Some(layer) = self.layers.get(*layer) {
+                layer.props.check_declared_types(declared)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that `props` can be added to the edge from `src` to `dst` in `layer`, or to the half
+    /// of it on this shard: that none of them is already used as the other kind of property and,
+    /// for temporal properties, that they have the types of the values the edge already holds.
+    pub(crate) fn check_edge_prop_kinds(
+        &self,
+        src: u64,
+        dst: u64,
+        props: &[(String, Prop)],
+        should_be_static: bool,
+        layer: usize,
+    ) -> MutateGraphResult {
+        let edge_props = &self.layers[layer].props;
+        edge_props
+            .check_prop_kinds(props, should_be_static)
+            .map_err(|e| MutateGraphError::IllegalEdgePropertyKind {
+                src_id: src,
+                dst_id: dst,
+                source: e,
+            })?;
+        if should_be_static {
+            return Ok(());
+        }
+        let edge_id = match self.logical_to_physical.get(&dst) {
+            Some(&dst_pid) if !self.has_vertex(src) => {
+                self.layers[layer].remote_in_edge_id(src, dst_pid)
+            }
+            _ => self.edge(src, dst, layer).map(|e| e.edge_id),
+        };
+        match edge_id {
+            Some(id) => edge_props.check_prop_types(id, props),
+            None => Ok(()),
+        }
     }
 
     pub(crate) fn add_edge_with_props<T: InputVertex>(
@@ -304,9 +384,10 @@ impl TemporalGraph {
         dst: T,
         props: &Vec<(String, Prop)>,
         layer: usize,
-    ) {
+    ) -> MutateGraphResult {
         let src_id = src.id();
         let dst_id = dst.id();
+        self.check_edge_prop_kinds(src_id, dst_id, props, false, layer)?;
         // mark the times of the vertices at t
         self.add_vertex(t, src)
             .map_err(|err| println!("{:?}", err))
@@ -328,9 +409,10 @@ impl TemporalGraph {
         dst: T,
         props: &Vec<(String, Prop)>,
         layer: usize,
-    ) {
+    ) -> MutateGraphResult {
         let src_id = src.id();
         let dst_id = dst.id();
+        self.check_edge_prop_kinds(src_id, dst_id, props, false, layer)?;
 
         self.add_vertex(t, src)
             .map_err(|err| println!("{:?}", err))
@@ -346,9 +428,10 @@ impl TemporalGraph {
         dst: T, // we are on the destination shard
         props: &Vec<(String, Prop)>,
         layer: usize,
-    ) {
+    ) -> MutateGraphResult {
         let src_id = src.id();
         let dst_id = dst.id();
+        self.check_edge_prop_kinds(src_id, dst_id, props, false, layer)?;
         self.add_vertex(t, dst)
             .map_err(|err| println!("{:?}", err))
             .ok();
@@ -366,6 +449,7 @@ impl TemporalGraph {
         let edge = self
             .edge(src, dst, layer)
             .ok_or_else(|| MutateGraphError::MissingEdge(src, dst))?;
+        self.check_edge_prop_kinds(src, dst, data, true, edge.layer_id)?;
         let result = self.layers[edge.layer_id]
             .props
             .set_static_props(edge.edge_id, data);
@@ -1456,7 +1540,8 @@ mod graph_test {
         g.add_vertex(1, 11).unwrap();
         g.add_vertex(2, 22).unwrap();
 
-        g.add_edge_with_props(4, 11, 22, &vec![("weight".into(), Prop::U32(12))], 0);
+        g.add_edge_with_props(4, 11, 22, &vec![("weight".into(), Prop::U32(12))], 0)
+            .unwrap();
 
         let edge_weights = g
             .vertex_edges(11, Direction::OUT, None)
@@ -1489,7 +1574,8 @@ mod graph_test {
                 ("label".into(), Prop::Str("blerg".into())),
             ],
             0,
-        );
+        )
+        .unwrap();
 
         let edge_weights = g
             .vertex_edges(11, Direction::OUT, None)
@@ -1518,9 +1604,12 @@ mod graph_test {
         g.add_vertex(1, 11).unwrap();
         g.add_vertex(2, 22).unwrap();
 
-        g.add_edge_with_props(4, 11, 22, &vec![("amount".into(), Prop::U32(12))], 0);
-        g.add_edge_with_props(7, 11, 22, &vec![("amount".into(), Prop::U32(24))], 0);
-        g.add_edge_with_props(19, 11, 22, &vec![("amount".into(), Prop::U32(48))], 0);
+        g.add_edge_with_props(4, 11, 22, &vec![("amount".into(), Prop::U32(12))], 0)
+            .unwrap();
+        g.add_edge_with_props(7, 11, 22, &vec![("amount".into(), Prop::U32(24))], 0)
+            .unwrap();
+        g.add_edge_with_props(19, 11, 22, &vec![("amount".into(), Prop::U32(48))], 0)
+            .unwrap();
 
         let edge_weights = g
             .vertex_edges_window(11, &(4..8), Direction::OUT, None)
@@ -1565,7 +1654,8 @@ mod graph_test {
                 ("label".into(), Prop::Str("blerg".into())),
             ],
             0,
-        );
+        )
+        .unwrap();
 
         g.add_edge_with_props(
             3,
@@ -1576,7 +1666,8 @@ mod graph_test {
                 ("label".into(), Prop::Str("blerg".into())),
             ],
             0,
-        );
+        )
+        .unwrap();
 
         g.add_edge_with_props(
             4,
@@ -1584,7 +1675,8 @@ mod graph_test {
             22,
             &vec![("label".into(), Prop::Str("blerg_again".into()))],
             0,
-        );
+        )
+        .unwrap();
 
         g.add_edge_with_props(
             5,
@@ -1595,7 +1687,8 @@ mod graph_test {
                 ("amount".into(), Prop::F64(12.34)),
             ],
             0,
-        );
+        )
+        .unwrap();
 
         let edge_weights = g
             .vertex_edges_window(11, &(3..5), Direction::OUT, None)
@@ -1626,7 +1719,8 @@ mod graph_test {
         for (t, src, dst) in edges {
             g.add_vertex(t, src).unwrap();
             g.add_vertex(t, dst).unwrap();
-            g.add_edge_with_props(t, src, dst, &vec![("amount".into(), Prop::U64(12))], 0);
+            g.add_edge_with_props(t, src, dst, &vec![("amount".into(), Prop::U64(12))], 0)
+                .unwrap();
         }
     }
 
@@ -1639,9 +1733,12 @@ mod graph_test {
         g.add_vertex(3, 33).unwrap();
         g.add_vertex(4, 44).unwrap();
 
-        g.add_edge_with_props(4, 11, 22, &vec![("weight".into(), Prop::F32(1122.0))], 0);
-        g.add_edge_with_props(4, 11, 33, &vec![("weight".into(), Prop::F32(1133.0))], 0);
-        g.add_edge_with_props(4, 44, 11, &vec![("weight".into(), Prop::F32(4411.0))], 0);
+        g.add_edge_with_props(4, 11, 22, &vec![("weight".into(), Prop::F32(1122.0))], 0)
+            .unwrap();
+        g.add_edge_with_props(4, 11, 33, &vec![("weight".into(), Prop::F32(1133.0))], 0)
+            .unwrap();
+        g.add_edge_with_props(4, 44, 11, &vec![("weight".into(), Prop::F32(4411.0))], 0)
+            .unwrap();
 
         let edge_weights_out_11 = g
             .vertex_edges(11, Direction::OUT, None)
@@ -1688,7 +1785,8 @@ mod graph_test {
                 ("label".into(), Prop::Str("blerg".into())),
             ],
             0,
-        );
+        )
+        .unwrap();
 
         g.add_edge_with_props(
             3,
@@ -1699,7 +1797,8 @@ mod graph_test {
                 ("label".into(), Prop::Str("blerg".into())),
             ],
             0,
-        );
+        )
+        .unwrap();
 
         g.add_edge_with_props(
             4,
@@ -1707,7 +1806,8 @@ mod graph_test {
             44,
             &vec![("label".into(), Prop::Str("blerg".into()))],
             0,
-        );
+        )
+        .unwrap();
 
         g.add_edge_with_props(
             5,
@@ -1718,7 +1818,8 @@ mod graph_test {
                 ("amount".into(), Prop::F64(12.34)),
             ],
             0,
-        );
+        )
+        .unwrap();
 
         // betwen t:2 and t:4 (excluded) only 11, 22 and 33 are visible, 11 is visible because it has an edge at time 2
         let vs = g.vertices_window(2..4).map(|v| v.g_id).collect::<Vec<_>>();
@@ -1850,7 +1951,8 @@ mod graph_test {
         ];
 
         for (t, src, dst, w) in triplets {
-            g.add_edge_with_props(t, src, dst, &vec![("weight".to_string(), Prop::U32(w))], 0);
+            g.add_edge_with_props(t, src, dst, &vec![("weight".to_string(), Prop::U32(w))], 0)
+                .unwrap();
         }
 
         for i in 1..4 {
@@ -1929,7 +2031,7 @@ mod graph_test {
                     if let Some((src, dst, t)) = parse_record(&rec) {
                         g.add_vertex(t, src.clone()).unwrap();
                         g.add_vertex(t, src.clone()).unwrap();
-                        g.add_edge_with_props(t, src, dst, &vec![], 0);
+                        g.add_edge_with_props(t, src, dst, &vec![], 0).unwrap();
                     }
                 }
             }
@@ -2138,7 +2240,8 @@ mod graph_test {
         ];
 
         for (t, src, dst, w) in triplets {
-            g.add_edge_with_props(t, src, dst, &vec![("weight".to_string(), Prop::U32(w))], 0);
+            g.add_edge_with_props(t, src, dst, &vec![("weight".to_string(), Prop::U32(w))], 0)
+                .unwrap();
         }
 
         let pid = *(g.logical_to_physical.get(&1).unwrap());
@@ -2197,28 +2300,16 @@ mod graph_test {
                 .unwrap();
 
             if src_shard == dst_shard {
-                shards[src_shard].add_edge_with_props(
-                    t.try_into().unwrap(),
-                    src,
-                    dst,
-                    &some_props,
-                    0,
-                );
+                shards[src_shard]
+                    .add_edge_with_props(t.try_into().unwrap(), src, dst, &some_props, 0)
+                    .unwrap();
             } else {
-                shards[src_shard].add_edge_remote_out(
-                    t.try_into().unwrap(),
-                    src,
-                    dst,
-                    &some_props,
-                    0,
-                );
-                shards[dst_shard].add_edge_remote_into(
-                    t.try_into().unwrap(),
-                    src,
-                    dst,
-                    &some_props,
-                    0,
-                );
+                shards[src_shard]
+                    .add_edge_remote_out(t.try_into().unwrap(), src, dst, &some_props, 0)
+                    .unwrap();
+                shards[dst_shard]
+                    .add_edge_remote_into(t.try_into().unwrap(), src, dst, &some_props, 0)
+                    .unwrap();
             }
         }
     }
@@ -2226,8 +2317,10 @@ mod graph_test {
     #[test]
     fn adding_remote_edge_does_not_break_local_indices() {
         let mut g1 = TemporalGraph::default();
-        g1.add_edge_remote_out(11, 1, 1, &vec![("bla".to_string(), Prop::U32(1))], 0);
-        g1.add_edge_with_props(11, 0, 2, &vec![("bla".to_string(), Prop::U32(1))], 0);
+        g1.add_edge_remote_out(11, 1, 1, &vec![("bla".to_string(), Prop::U32(1))], 0)
+            .unwrap();
+        g1.add_edge_with_props(11, 0, 2, &vec![("bla".to_string(), Prop::U32(1))], 0)
+            .unwrap();
     }
 
     #[test]
@@ -2235,7 +2328,8 @@ mod graph_test {
         let mut g1 = TemporalGraph::default();
         g1.add_vertex(1, 11).unwrap();
 
-        g1.add_edge_remote_out(2, 11, 22, &vec![("bla".to_string(), Prop::U32(1))], 0);
+        g1.add_edge_remote_out(2, 11, 22, &vec![("bla".to_string(), Prop::U32(1))], 0)
+            .unwrap();
 
         let actual = g1
             .vertex_edges_window(11, &(1..3), Direction::OUT, None)
@@ -2255,15 +2349,18 @@ mod graph_test {
         g.add_vertex(3, 3).unwrap();
         g.add_vertex(4, 1).unwrap();
 
-        g.add_edge_with_props(1, 2, 3, &vec![("bla".to_string(), Prop::U32(1))], 0);
-        g.add_edge_with_props(3, 4, 4, &vec![("bla1".to_string(), Prop::U64(1))], 0);
+        g.add_edge_with_props(1, 2, 3, &vec![("bla".to_string(), Prop::U32(1))], 0)
+            .unwrap();
+        g.add_edge_with_props(3, 4, 4, &vec![("bla1".to_string(), Prop::U64(1))], 0)
+            .unwrap();
         g.add_edge_with_props(
             4,
             1,
             5,
             &vec![("bla2".to_string(), Prop::Str("blergo blargo".to_string()))],
             0,
-        );
+        )
+        .unwrap();
 
         let mut buffer: Vec<u8> = Vec::new();
 
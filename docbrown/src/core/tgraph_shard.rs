@@ -20,7 +20,7 @@ use genawaiter::yield_;
 
 use crate::core::tgraph::{EdgeRef, TemporalGraph, VertexRef};
 use crate::core::vertex::InputVertex;
-use crate::core::{Direction, Prop, Time};
+use crate::core::{utils, Direction, Prop, PropType, Time};
 
 use self::errors::GraphError;
 use self::lock::OptionLock;
@@ -81,6 +81,7 @@ mod lock {
 pub mod errors {
     use crate::core::tgraph::errors::MutateGraphError;
     use crate::core::time::error::ParseTimeError;
+    use crate::core::PropType;
 
     #[derive(thiserror::Error, Debug, PartialEq)]
     pub enum GraphError {
@@ -95,6 +96,72 @@ pub mod errors {
             #[from]
             source: ParseTimeError,
         },
+        #[error(
+            "Accumulator {name} is registered as {registered} but was requested as {requested}"
+        )]
+        AccumulatorTypeMismatch {
+            name: String,
+            registered: String,
+            requested: String,
+        },
+        #[error("Accumulator id {id} is reserved for accumulators registered by name")]
+        ReservedAccumulatorId { id: u32 },
+        #[error("Accumulator {id} has state of type {state_type}, which has no codec")]
+        UnregisteredStateCodec { id: u32, state_type: String },
+        #[error(
+            "The checkpoint was written for a graph with {expected} but the graph has {found}"
+        )]
+        CheckpointGraphMismatch { expected: String, found: String },
+        #[error("The computation was cancelled")]
+        Cancelled,
+        #[error("The computation ran past its deadline")]
+        DeadlineExceeded,
+        #[error("Property {name} is declared as {expected} but was given a {actual}")]
+        PropertyTypeMismatch {
+            name: String,
+            expected: PropType,
+            actual: PropType,
+        },
+        #[error("The graph has no layer named {name}")]
+        LayerNotFound { name: String },
+        #[error("IO error: {message}")]
+        Io {
+            kind: std::io::ErrorKind,
+            message: String,
+        },
+        #[error("The graph has no write-ahead log, enable it with Graph::enable_wal")]
+        WalDisabled,
+        #[error("The graph was saved in format version {found} but only versions up to {supported} can be read")]
+        UnsupportedFormatVersion { found: u32, supported: u32 },
+        #[error("Shard {shard} does not match the checksum in the manifest")]
+        CorruptedShard { shard: usize },
+        #[error("Vertex {name} has the same id {id} as vertex {existing}")]
+        VertexNameCollision {
+            name: String,
+            existing: String,
+            id: u64,
+        },
+    }
+
+    impl From<Box<bincode::ErrorKind>> for GraphError {
+        fn from(source: Box<bincode::ErrorKind>) -> Self {
+            match *source {
+                bincode::ErrorKind::Io(e) => e.into(),
+                e => GraphError::Io {
+                    kind: std::io::ErrorKind::InvalidData,
+                    message: e.to_string(),
+                },
+            }
+        }
+    }
+
+    impl From<std::io::Error> for GraphError {
+        fn from(source: std::io::Error) -> Self {
+            GraphError::Io {
+                kind: source.kind(),
+                message: source.to_string(),
+            }
+        }
     }
 }
 
@@ -133,18 +200,21 @@ impl TGraphShard<TemporalGraph> {
 
     pub fn load_from_file<P: AsRef<Path>>(
         path: P,
-    ) -> Result<TGraphShard<TemporalGraph>, Box<bincode::ErrorKind>> {
-        // use BufReader for better performance
-        let f = std::fs::File::open(path).unwrap();
-        let mut reader = std::io::BufReader::new(f);
-        bincode::deserialize_from(&mut reader)
+    ) -> Result<TGraphShard<TemporalGraph>, GraphError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<TGraphShard<TemporalGraph>, GraphError> {
+        Ok(bincode::deserialize(bytes)?)
     }
 
-    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<bincode::ErrorKind>> {
-        // use BufWriter for better performance
-        let f = std::fs::File::create(path).unwrap();
-        let mut writer = std::io::BufWriter::new(f);
-        bincode::serialize_into(&mut writer, self)
+    /// Saves the shard to `path`, returning the crc32 checksum of the written file.
+    ///
+    /// The file at `path` is only replaced once the shard is completely written.
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<u32, GraphError> {
+        let bytes = bincode::serialize(self)?;
+        utils::write_atomically(path.as_ref(), &bytes)?;
+        Ok(crc32fast::hash(&bytes))
     }
 
     #[inline(always)]
@@ -157,6 +227,26 @@ impl TGraphShard<TemporalGraph> {
         f(shard)
     }
 
+    /// Write locks all of `shards`, in the same order as `add_edge_across`, and calls `f` with
+    /// the shards in their original order.
+    pub(crate) fn write_all<A, F>(shards: &[Self], f: F) -> Result<A, GraphError>
+    where
+        F: FnOnce(&mut [&mut TemporalGraph]) -> A,
+    {
+        let mut order = (0..shards.len()).collect::<Vec<_>>();
+        order.sort_by_key(|i| Arc::as_ptr(&shards[*i].rc));
+        let mut bindings = shards.iter().map(|_| None).collect::<Vec<_>>();
+        for i in order {
+            bindings[i] = Some(shards[i].rc.write());
+        }
+        let mut tgs = bindings
+            .iter_mut()
+            .flatten()
+            .map(|binding| binding.as_mut().ok_or(GraphError::IllegalGraphAccess))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(f(&mut tgs))
+    }
+
     #[inline(always)]
     fn read_shard<A, F>(&self, f: F) -> A
     where
@@ -177,6 +267,14 @@ impl TGraphShard<TemporalGraph> {
         self.write_shard(|tg| Ok(tg.allocate_layer(id)))
     }
 
+    pub(crate) fn check_declared_types(
+        &self,
+        vertex_props: &HashMap<String, PropType>,
+        layers: &[(usize, &HashMap<String, PropType>)],
+    ) -> Result<(), GraphError> {
+        self.read_shard(|tg| tg.check_declared_types(vertex_props, layers))
+    }
+
     pub fn earliest_time(&self) -> i64 {
         self.read_shard(|tg| tg.earliest_time)
     }
@@ -248,7 +346,10 @@ impl TGraphShard<TemporalGraph> {
         props: &Vec<(String, Prop)>,
         layer: usize,
     ) -> Result<(), GraphError> {
-        self.write_shard(|tg| Ok(tg.add_edge_with_props(t, src, dst, props, layer)))
+        self.write_shard(|tg| {
+            let res = tg.add_edge_with_props(t, src, dst, props, layer);
+            res.map_err(|e| GraphError::FailedToMutateGraph { source: e })
+        })
     }
 
     pub fn add_edge_remote_out<T: InputVertex>(
@@ -259,7 +360,10 @@ impl TGraphShard<TemporalGraph> {
         props: &Vec<(String, Prop)>,
         layer: usize,
     ) -> Result<(), GraphError> {
-        self.write_shard(|tg| Ok(tg.add_edge_remote_out(t, src, dst, props, layer)))
+        self.write_shard(|tg| {
+            let res = tg.add_edge_remote_out(t, src, dst, props, layer);
+            res.map_err(|e| GraphError::FailedToMutateGraph { source: e })
+        })
     }
 
     pub fn add_edge_remote_into<T: InputVertex>(
@@ -270,7 +374,45 @@ impl TGraphShard<TemporalGraph> {
         props: &Vec<(String, Prop)>,
         layer: usize,
     ) -> Result<(), GraphError> {
-        self.write_shard(|tg| Ok(tg.add_edge_remote_into(t, src, dst, props, layer)))
+        self.write_shard(|tg| {
+            let res = tg.add_edge_remote_into(t, src, dst, props, layer);
+            res.map_err(|e| GraphError::FailedToMutateGraph { source: e })
+        })
+    }
+
+    /// Adds an edge between `src`, which lives on `src_shard`, and `dst`, which lives on
+    /// `dst_shard`.
+    ///
+    /// Both write locks are held while the two halves of the edge are added, so readers see
+    /// either none or both of them, and the properties are checked on both shards first so that
+    /// an error leaves both shards unchanged. The locks are always taken in the same order to
+    /// avoid deadlocks between concurrent insertions in opposite directions.
+    pub fn add_edge_across<T: InputVertex>(
+        src_shard: &Self,
+        dst_shard: &Self,
+        t: i64,
+        src: T,
+        dst: T,
+        props: &Vec<(String, Prop)>,
+        layer: usize,
+    ) -> Result<(), GraphError> {
+        let (mut src_binding, mut dst_binding) =
+            if Arc::as_ptr(&src_shard.rc) < Arc::as_ptr(&dst_shard.rc) {
+                let src_binding = src_shard.rc.write();
+                (src_binding, dst_shard.rc.write())
+            } else {
+                let dst_binding = dst_shard.rc.write();
+                (src_shard.rc.write(), dst_binding)
+            };
+        let src_tg = src_binding.as_mut().ok_or(GraphError::IllegalGraphAccess)?;
+        let dst_tg = dst_binding.as_mut().ok_or(GraphError::IllegalGraphAccess)?;
+
+        let res = src_tg
+            .check_edge_prop_kinds(src.id(), dst.id(), props, false, layer)
+            .and_then(|_| dst_tg.check_edge_prop_kinds(src.id(), dst.id(), props, false, layer))
+            .and_then(|_| src_tg.add_edge_remote_out(t, src.clone(), dst.clone(), props, layer))
+            .and_then(|_| dst_tg.add_edge_remote_into(t, src, dst, props, layer));
+        res.map_err(|e| GraphError::FailedToMutateGraph { source: e })
     }
 
     pub fn add_edge_properties(
@@ -1,11 +1,34 @@
+//! Timestamps and intervals.
+//!
+//! Every graph counts time in one `TimeUnit`, milliseconds since the Unix epoch unless it was
+//! created with `Graph::new_with_time_unit`. Datetime strings given as times and intervals such
+//! as `"10 microseconds"` given to `rolling` and `expanding` are converted to that unit.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use docbrown::core::time::TimeUnit;
+//! use docbrown::db::graph::Graph;
+//! use docbrown::db::view_api::*;
+//!
+//! let g = Graph::new_with_time_unit(1, TimeUnit::Nanoseconds);
+//! g.add_vertex("2023-04-01T10:00:00.000000100Z", 1, &vec![]).unwrap();
+//! g.add_vertex("2023-04-01T10:00:00.000000599Z", 1, &vec![]).unwrap();
+//! assert_eq!(g.latest_time().unwrap() - g.earliest_time().unwrap(), 499);
+//!
+//! let windows = g.rolling("100 nanoseconds", None).unwrap();
+//! assert_eq!(windows.count(), 5);
+//! ```
+
 use chrono::{DateTime, Duration, NaiveDateTime};
 use itertools::{Either, Itertools};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 
 use crate::core::time::error::*;
-use std::ops::{Add, Sub};
 
 pub mod error {
+    use crate::core::time::TimeUnit;
     use chrono::ParseError;
     use std::num::ParseIntError;
 
@@ -24,46 +47,106 @@ pub mod error {
         InvalidUnit(String),
         #[error(transparent)]
         ParseError(#[from] ParseError),
+        #[error("the graph has integer times, which datetimes and intervals with a unit cannot be converted to")]
+        NoTimeUnit,
+        #[error("the time or interval cannot be represented as a whole number of {0:?} in an i64")]
+        NotRepresentable(TimeUnit),
+    }
+}
+
+/// The unit the times of a graph are counted in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeUnit {
+    /// Milliseconds since the Unix epoch
+    #[default]
+    Milliseconds,
+    /// Microseconds since the Unix epoch
+    Microseconds,
+    /// Nanoseconds since the Unix epoch
+    Nanoseconds,
+    /// Integers without a unit, such as sequence numbers. Times can only be given as integers
+    /// and intervals only as a number of steps.
+    Integer,
+}
+
+impl TimeUnit {
+    /// The time of `datetime` in this unit, rounded down.
+    fn time_of(self, datetime: NaiveDateTime) -> Result<i64, ParseTimeError> {
+        match self {
+            TimeUnit::Milliseconds => Ok(datetime.timestamp_millis()),
+            TimeUnit::Microseconds => Ok(datetime.timestamp_micros()),
+            TimeUnit::Nanoseconds => datetime
+                .timestamp()
+                .checked_mul(1_000_000_000)
+                .and_then(|ns| ns.checked_add(datetime.timestamp_subsec_nanos() as i64))
+                .ok_or(ParseTimeError::NotRepresentable(self)),
+            TimeUnit::Integer => Err(ParseTimeError::NoTimeUnit),
+        }
+    }
+
+    /// The length of `duration` in this unit, which it must be a whole number of.
+    fn length_of(self, duration: Duration) -> Result<i64, ParseTimeError> {
+        let count = match self {
+            TimeUnit::Milliseconds => Some(duration.num_milliseconds())
+                .filter(|&ms| Duration::milliseconds(ms) == duration),
+            TimeUnit::Microseconds => duration
+                .num_microseconds()
+                .filter(|&us| Duration::microseconds(us) == duration),
+            TimeUnit::Nanoseconds => duration.num_nanoseconds(),
+            TimeUnit::Integer => return Err(ParseTimeError::NoTimeUnit),
+        };
+        count.ok_or(ParseTimeError::NotRepresentable(self))
     }
 }
 
 pub trait IntoTime {
-    fn into_time(&self) -> Result<i64, ParseTimeError>;
+    /// Converts to a time in milliseconds since the Unix epoch.
+    fn into_time(&self) -> Result<i64, ParseTimeError> {
+        self.time_in(TimeUnit::Milliseconds)
+    }
+
+    /// Converts to a time in `unit`.
+    fn time_in(&self, unit: TimeUnit) -> Result<i64, ParseTimeError>;
 }
 
 impl IntoTime for i64 {
-    fn into_time(&self) -> Result<i64, ParseTimeError> {
+    fn time_in(&self, _unit: TimeUnit) -> Result<i64, ParseTimeError> {
         Ok(*self)
     }
 }
 
 impl IntoTime for &str {
     /// Tries to parse the timestamp as RFC3339 and then as ISO 8601 with local format and all
-    /// fields mandatory except for milliseconds and allows replacing the T with a space
-    fn into_time(&self) -> Result<i64, ParseTimeError> {
+    /// fields mandatory except for fractions of a second and allows replacing the T with a
+    /// space. Integer times are parsed as integers.
+    fn time_in(&self, unit: TimeUnit) -> Result<i64, ParseTimeError> {
+        if unit == TimeUnit::Integer {
+            return Ok(self.parse::<i64>()?);
+        }
+
         let rfc_result = DateTime::parse_from_rfc3339(self);
         if let Ok(datetime) = rfc_result {
-            return Ok(datetime.timestamp_millis());
+            return unit.time_of(datetime.naive_utc());
         }
 
-        let result = NaiveDateTime::parse_from_str(self, "%Y-%m-%dT%H:%M:%S%.3f");
+        let result = NaiveDateTime::parse_from_str(self, "%Y-%m-%dT%H:%M:%S%.f");
         if let Ok(datetime) = result {
-            return Ok(datetime.timestamp_millis());
+            return unit.time_of(datetime);
         }
 
         let result = NaiveDateTime::parse_from_str(self, "%Y-%m-%dT%H:%M:%S%");
         if let Ok(datetime) = result {
-            return Ok(datetime.timestamp_millis());
+            return unit.time_of(datetime);
         }
 
-        let result = NaiveDateTime::parse_from_str(self, "%Y-%m-%d %H:%M:%S%.3f");
+        let result = NaiveDateTime::parse_from_str(self, "%Y-%m-%d %H:%M:%S%.f");
         if let Ok(datetime) = result {
-            return Ok(datetime.timestamp_millis());
+            return unit.time_of(datetime);
         }
 
         let result = NaiveDateTime::parse_from_str(self, "%Y-%m-%d %H:%M:%S%");
         if let Ok(datetime) = result {
-            return Ok(datetime.timestamp_millis());
+            return unit.time_of(datetime);
         }
 
         Err(rfc_result.unwrap_err().into())
@@ -71,12 +154,12 @@ impl IntoTime for &str {
 }
 
 pub(crate) trait IntoTimeWithFormat {
-    fn parse_time(&self, fmt: &str) -> Result<i64, ParseTimeError>;
+    fn parse_time(&self, fmt: &str, unit: TimeUnit) -> Result<i64, ParseTimeError>;
 }
 
 impl IntoTimeWithFormat for &str {
-    fn parse_time(&self, fmt: &str) -> Result<i64, ParseTimeError> {
-        Ok(NaiveDateTime::parse_from_str(self, fmt)?.timestamp_millis())
+    fn parse_time(&self, fmt: &str, unit: TimeUnit) -> Result<i64, ParseTimeError> {
+        unit.time_of(NaiveDateTime::parse_from_str(self, fmt)?)
     }
 }
 
@@ -148,11 +231,12 @@ impl TryFrom<u64> for Interval {
 }
 
 impl Interval {
-    /// Return an option because there might be no exact translation to millis for some intervals
-    pub(crate) fn to_millis(&self) -> Option<u64> {
+    /// The length of the interval in `unit`, intervals given as a number are that many steps
+    /// whatever the unit.
+    pub(crate) fn to_unit(self, unit: TimeUnit) -> Result<i64, ParseTimeError> {
         match self.size {
-            IntervalSize::Discrete(millis) => Some(millis),
-            IntervalSize::Temporal(duration) => Some(duration.num_milliseconds() as u64),
+            IntervalSize::Discrete(number) => Ok(number as i64),
+            IntervalSize::Temporal(duration) => unit.length_of(duration),
         }
     }
 
@@ -165,65 +249,62 @@ impl Interval {
             "minute" | "minutes" => Duration::minutes(number),
             "second" | "seconds" => Duration::seconds(number),
             "millisecond" | "milliseconds" => Duration::milliseconds(number),
+            "microsecond" | "microseconds" => Duration::microseconds(number),
+            "nanosecond" | "nanoseconds" => Duration::nanoseconds(number),
             unit => return Err(ParseTimeError::InvalidUnit(unit.to_string())),
         };
         Ok(duration)
     }
 }
 
-impl Sub<Interval> for i64 {
-    type Output = i64;
-    fn sub(self, rhs: Interval) -> Self::Output {
-        match rhs.size {
-            IntervalSize::Discrete(number) => self - (number as i64),
-            IntervalSize::Temporal(duration) => self - duration.num_milliseconds(),
-        }
-    }
-}
-
-impl Add<Interval> for i64 {
-    type Output = i64;
-    fn add(self, rhs: Interval) -> Self::Output {
-        match rhs.size {
-            IntervalSize::Discrete(number) => self + (number as i64),
-            IntervalSize::Temporal(duration) => self + duration.num_milliseconds(),
-        }
-    }
-}
-
 #[cfg(test)]
 mod time_tests {
-    use crate::core::time::{Interval, ParseTimeError};
+    use crate::core::time::{Interval, IntoTime, ParseTimeError, TimeUnit};
     #[test]
     fn interval_parsing() {
-        let second: u64 = 1000;
+        let second: i64 = 1000;
         let minute = 60 * second;
         let hour = 60 * minute;
         let day = 24 * hour;
         let week = 7 * day;
 
         let interval: Interval = "1 day".try_into().unwrap();
-        assert_eq!(interval.to_millis().unwrap(), day);
+        assert_eq!(interval.to_unit(TimeUnit::Milliseconds).unwrap(), day);
 
         let interval: Interval = "1 week".try_into().unwrap();
-        assert_eq!(interval.to_millis().unwrap(), week);
+        assert_eq!(interval.to_unit(TimeUnit::Milliseconds).unwrap(), week);
 
         let interval: Interval = "4 weeks and 1 day".try_into().unwrap();
-        assert_eq!(interval.to_millis().unwrap(), 4 * week + day);
+        assert_eq!(
+            interval.to_unit(TimeUnit::Milliseconds).unwrap(),
+            4 * week + day
+        );
 
         let interval: Interval = "2 days & 1 millisecond".try_into().unwrap();
-        assert_eq!(interval.to_millis().unwrap(), 2 * day + 1);
+        assert_eq!(
+            interval.to_unit(TimeUnit::Milliseconds).unwrap(),
+            2 * day + 1
+        );
 
         let interval: Interval = "2 days, 1 hour, and 2 minutes".try_into().unwrap();
-        assert_eq!(interval.to_millis().unwrap(), 2 * day + hour + 2 * minute);
+        assert_eq!(
+            interval.to_unit(TimeUnit::Milliseconds).unwrap(),
+            2 * day + hour + 2 * minute
+        );
 
         let interval: Interval = "1 weeks ,   1 minute".try_into().unwrap();
-        assert_eq!(interval.to_millis().unwrap(), week + minute);
+        assert_eq!(
+            interval.to_unit(TimeUnit::Milliseconds).unwrap(),
+            week + minute
+        );
 
         let interval: Interval = "23 seconds  and 34 millisecond and 1 minute"
             .try_into()
             .unwrap();
-        assert_eq!(interval.to_millis().unwrap(), 23 * second + 34 + minute);
+        assert_eq!(
+            interval.to_unit(TimeUnit::Milliseconds).unwrap(),
+            23 * second + 34 + minute
+        );
     }
 
     #[test]
@@ -247,4 +328,48 @@ mod time_tests {
             _ => panic!(),
         }
     }
+
+    #[test]
+    fn intervals_in_units() {
+        let interval: Interval = "1 second and 1 microsecond".try_into().unwrap();
+        assert_eq!(interval.to_unit(TimeUnit::Microseconds), Ok(1_000_001));
+        assert_eq!(interval.to_unit(TimeUnit::Nanoseconds), Ok(1_000_001_000));
+        assert_eq!(
+            interval.to_unit(TimeUnit::Milliseconds),
+            Err(ParseTimeError::NotRepresentable(TimeUnit::Milliseconds))
+        );
+        assert_eq!(
+            interval.to_unit(TimeUnit::Integer),
+            Err(ParseTimeError::NoTimeUnit)
+        );
+
+        let interval: Interval = "3 nanoseconds".try_into().unwrap();
+        assert_eq!(interval.to_unit(TimeUnit::Nanoseconds), Ok(3));
+
+        let interval: Interval = 5.try_into().unwrap();
+        assert_eq!(interval.to_unit(TimeUnit::Integer), Ok(5));
+        assert_eq!(interval.to_unit(TimeUnit::Nanoseconds), Ok(5));
+    }
+
+    #[test]
+    fn times_in_units() {
+        let time = "1970-01-01T00:00:01.000001001Z";
+        assert_eq!(time.into_time(), Ok(1_000));
+        assert_eq!(time.time_in(TimeUnit::Microseconds), Ok(1_000_001));
+        assert_eq!(time.time_in(TimeUnit::Nanoseconds), Ok(1_000_001_001));
+        assert_eq!(
+            "1970-01-01 00:00:00.000002".time_in(TimeUnit::Microseconds),
+            Ok(2)
+        );
+        assert_eq!(
+            "3000-01-01T00:00:00Z".time_in(TimeUnit::Nanoseconds),
+            Err(ParseTimeError::NotRepresentable(TimeUnit::Nanoseconds))
+        );
+
+        assert_eq!("42".time_in(TimeUnit::Integer), Ok(42));
+        assert!(matches!(
+            time.time_in(TimeUnit::Integer),
+            Err(ParseTimeError::ParseInt { .. })
+        ));
+    }
 }
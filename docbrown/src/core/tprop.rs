@@ -1,6 +1,8 @@
 use crate::core::tcell::TCell;
-use crate::core::Prop;
+use crate::core::{Prop, PropType};
+use chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::ops::Range;
 
 // TODO TProp struct could be replaced with Option<TCell<Prop>>, with the only issue (or advantage) that then the type can change?
@@ -17,6 +19,10 @@ pub(crate) enum TProp {
     F32(TCell<f32>),
     F64(TCell<f64>),
     Bool(TCell<bool>),
+    DTime(TCell<DateTime<FixedOffset>>),
+    List(TCell<Vec<Prop>>),
+    Map(TCell<HashMap<String, Prop>>),
+    Bytes(TCell<Vec<u8>>),
 }
 
 impl TProp {
@@ -30,55 +36,52 @@ impl TProp {
             Prop::F32(value) => TProp::F32(TCell::new(t, *value)),
             Prop::F64(value) => TProp::F64(TCell::new(t, *value)),
             Prop::Bool(value) => TProp::Bool(TCell::new(t, *value)),
+            Prop::DTime(value) => TProp::DTime(TCell::new(t, *value)),
+            Prop::List(value) => TProp::List(TCell::new(t, value.clone())),
+            Prop::Map(value) => TProp::Map(TCell::new(t, value.as_ref().clone())),
+            Prop::Bytes(value) => TProp::Bytes(TCell::new(t, value.clone())),
         }
     }
 
-    pub(crate) fn set(&mut self, t: i64, prop: &Prop) {
+    /// The type of the values of the property, `None` if it has none yet.
+    pub(crate) fn dtype(&self) -> Option<PropType> {
         match self {
-            TProp::Empty => {
-                *self = TProp::from(t, prop);
-            }
-            TProp::Str(cell) => {
-                if let Prop::Str(a) = prop {
-                    cell.set(t, a.to_string());
-                }
-            }
-            TProp::I32(cell) => {
-                if let Prop::I32(a) = prop {
-                    cell.set(t, *a);
-                }
-            }
-            TProp::I64(cell) => {
-                if let Prop::I64(a) = prop {
-                    cell.set(t, *a);
-                }
-            }
-            TProp::U32(cell) => {
-                if let Prop::U32(a) = prop {
-                    cell.set(t, *a);
-                }
-            }
-            TProp::U64(cell) => {
-                if let Prop::U64(a) = prop {
-                    cell.set(t, *a);
-                }
-            }
-            TProp::F32(cell) => {
-                if let Prop::F32(a) = prop {
-                    cell.set(t, *a);
-                }
-            }
-            TProp::F64(cell) => {
-                if let Prop::F64(a) = prop {
-                    cell.set(t, *a);
-                }
-            }
-            TProp::Bool(cell) => {
-                if let Prop::Bool(a) = prop {
-                    cell.set(t, *a);
-                }
-            }
+            TProp::Empty => None,
+            TProp::Str(_) => Some(PropType::Str),
+            TProp::I32(_) => Some(PropType::I32),
+            TProp::I64(_) => Some(PropType::I64),
+            TProp::U32(_) => Some(PropType::U32),
+            TProp::U64(_) => Some(PropType::U64),
+            TProp::F32(_) => Some(PropType::F32),
+            TProp::F64(_) => Some(PropType::F64),
+            TProp::Bool(_) => Some(PropType::Bool),
+            TProp::DTime(_) => Some(PropType::DTime),
+            TProp::List(_) => Some(PropType::List),
+            TProp::Map(_) => Some(PropType::Map),
+            TProp::Bytes(_) => Some(PropType::Bytes),
+        }
+    }
+
+    /// Sets the value of the property at `t` to `prop`, returning the type of the values of the
+    /// property as the error if `prop` has a different type.
+    pub(crate) fn set(&mut self, t: i64, prop: &Prop) -> Result<(), PropType> {
+        match (self, prop) {
+            (this @ TProp::Empty, _) => *this = TProp::from(t, prop),
+            (TProp::Str(cell), Prop::Str(a)) => cell.set(t, a.to_string()),
+            (TProp::I32(cell), Prop::I32(a)) => cell.set(t, *a),
+            (TProp::I64(cell), Prop::I64(a)) => cell.set(t, *a),
+            (TProp::U32(cell), Prop::U32(a)) => cell.set(t, *a),
+            (TProp::U64(cell), Prop::U64(a)) => cell.set(t, *a),
+            (TProp::F32(cell), Prop::F32(a)) => cell.set(t, *a),
+            (TProp::F64(cell), Prop::F64(a)) => cell.set(t, *a),
+            (TProp::Bool(cell), Prop::Bool(a)) => cell.set(t, *a),
+            (TProp::DTime(cell), Prop::DTime(a)) => cell.set(t, *a),
+            (TProp::List(cell), Prop::List(a)) => cell.set(t, a.clone()),
+            (TProp::Map(cell), Prop::Map(a)) => cell.set(t, a.as_ref().clone()),
+            (TProp::Bytes(cell), Prop::Bytes(a)) => cell.set(t, a.clone()),
+            (this, _) => return Err(this.dtype().expect("empty properties take any value")),
         }
+        Ok(())
     }
 
     pub(crate) fn iter(&self) -> Box<dyn Iterator<Item = (&i64, Prop)> + '_> {
@@ -95,6 +98,21 @@ impl TProp {
             TProp::F32(cell) => Box::new(cell.iter_t().map(|(t, value)| (t, Prop::F32(*value)))),
             TProp::F64(cell) => Box::new(cell.iter_t().map(|(t, value)| (t, Prop::F64(*value)))),
             TProp::Bool(cell) => Box::new(cell.iter_t().map(|(t, value)| (t, Prop::Bool(*value)))),
+            TProp::DTime(cell) => {
+                Box::new(cell.iter_t().map(|(t, value)| (t, Prop::DTime(*value))))
+            }
+            TProp::List(cell) => Box::new(
+                cell.iter_t()
+                    .map(|(t, value)| (t, Prop::List(value.clone()))),
+            ),
+            TProp::Map(cell) => Box::new(
+                cell.iter_t()
+                    .map(|(t, value)| (t, Prop::Map(Box::new(value.clone())))),
+            ),
+            TProp::Bytes(cell) => Box::new(
+                cell.iter_t()
+                    .map(|(t, value)| (t, Prop::Bytes(value.clone()))),
+            ),
         }
     }
 
@@ -133,6 +151,22 @@ impl TProp {
                 cell.iter_window_t(r)
                     .map(|(t, value)| (t, Prop::Bool(*value))),
             ),
+            TProp::DTime(cell) => Box::new(
+                cell.iter_window_t(r)
+                    .map(|(t, value)| (t, Prop::DTime(*value))),
+            ),
+            TProp::List(cell) => Box::new(
+                cell.iter_window_t(r)
+                    .map(|(t, value)| (t, Prop::List(value.clone()))),
+            ),
+            TProp::Map(cell) => Box::new(
+                cell.iter_window_t(r)
+                    .map(|(t, value)| (t, Prop::Map(Box::new(value.clone())))),
+            ),
+            TProp::Bytes(cell) => Box::new(
+                cell.iter_window_t(r)
+                    .map(|(t, value)| (t, Prop::Bytes(value.clone()))),
+            ),
         }
     }
 }
@@ -144,15 +178,24 @@ mod tprop_tests {
     #[test]
     fn set_new_value_for_tprop_initialized_as_empty() {
         let mut tprop = TProp::Empty;
-        tprop.set(1, &Prop::I32(10));
+        tprop.set(1, &Prop::I32(10)).unwrap();
 
         assert_eq!(tprop.iter().collect::<Vec<_>>(), vec![(&1, Prop::I32(10))]);
     }
 
+    #[test]
+    fn values_of_another_type_are_rejected() {
+        let mut tprop = TProp::from(1, &Prop::I64(10));
+        assert_eq!(tprop.set(2, &Prop::I32(11)), Err(PropType::I64));
+        assert_eq!(tprop.set(2, &Prop::Str("11".into())), Err(PropType::I64));
+
+        assert_eq!(tprop.iter().collect::<Vec<_>>(), vec![(&1, Prop::I64(10))]);
+    }
+
     #[test]
     fn every_new_update_to_the_same_prop_is_recorded_as_history() {
         let mut tprop = TProp::from(1, &Prop::Str("Pometry".into()));
-        tprop.set(2, &Prop::Str("Pometry Inc.".into()));
+        tprop.set(2, &Prop::Str("Pometry Inc.".into())).unwrap();
 
         assert_eq!(
             tprop.iter().collect::<Vec<_>>(),
@@ -166,7 +209,7 @@ mod tprop_tests {
     #[test]
     fn new_update_with_the_same_time_to_a_prop_is_ignored() {
         let mut tprop = TProp::from(1, &Prop::Str("Pometry".into()));
-        tprop.set(1, &Prop::Str("Pometry Inc.".into()));
+        tprop.set(1, &Prop::Str("Pometry Inc.".into())).unwrap();
 
         assert_eq!(
             tprop.iter().collect::<Vec<_>>(),
@@ -181,7 +224,7 @@ mod tprop_tests {
         assert_eq!(tprop.iter().collect::<Vec<_>>(), vec![]);
 
         let mut tprop = TProp::from(1, &Prop::Str("Pometry".into()));
-        tprop.set(2, &Prop::Str("Pometry Inc.".into()));
+        tprop.set(2, &Prop::Str("Pometry Inc.".into())).unwrap();
 
         assert_eq!(
             tprop.iter().collect::<Vec<_>>(),
@@ -192,7 +235,7 @@ mod tprop_tests {
         );
 
         let mut tprop = TProp::from(1, &Prop::I32(2022));
-        tprop.set(2, &Prop::I32(2023));
+        tprop.set(2, &Prop::I32(2023)).unwrap();
 
         assert_eq!(
             tprop.iter().collect::<Vec<_>>(),
@@ -200,7 +243,7 @@ mod tprop_tests {
         );
 
         let mut tprop = TProp::from(1, &Prop::I64(2022));
-        tprop.set(2, &Prop::I64(2023));
+        tprop.set(2, &Prop::I64(2023)).unwrap();
 
         assert_eq!(
             tprop.iter().collect::<Vec<_>>(),
@@ -208,7 +251,7 @@ mod tprop_tests {
         );
 
         let mut tprop = TProp::from(1, &Prop::F32(10.0));
-        tprop.set(2, &Prop::F32(11.0));
+        tprop.set(2, &Prop::F32(11.0)).unwrap();
 
         assert_eq!(
             tprop.iter().collect::<Vec<_>>(),
@@ -216,7 +259,7 @@ mod tprop_tests {
         );
 
         let mut tprop = TProp::from(1, &Prop::F64(10.0));
-        tprop.set(2, &Prop::F64(11.0));
+        tprop.set(2, &Prop::F64(11.0)).unwrap();
 
         assert_eq!(
             tprop.iter().collect::<Vec<_>>(),
@@ -224,7 +267,7 @@ mod tprop_tests {
         );
 
         let mut tprop = TProp::from(1, &Prop::U32(1));
-        tprop.set(2, &Prop::U32(2));
+        tprop.set(2, &Prop::U32(2)).unwrap();
 
         assert_eq!(
             tprop.iter().collect::<Vec<_>>(),
@@ -232,7 +275,7 @@ mod tprop_tests {
         );
 
         let mut tprop = TProp::from(1, &Prop::U64(1));
-        tprop.set(2, &Prop::U64(2));
+        tprop.set(2, &Prop::U64(2)).unwrap();
 
         assert_eq!(
             tprop.iter().collect::<Vec<_>>(),
@@ -240,7 +283,7 @@ mod tprop_tests {
         );
 
         let mut tprop = TProp::from(1, &Prop::Bool(true));
-        tprop.set(2, &Prop::Bool(true));
+        tprop.set(2, &Prop::Bool(true)).unwrap();
 
         assert_eq!(
             tprop.iter().collect::<Vec<_>>(),
@@ -258,8 +301,8 @@ mod tprop_tests {
         );
 
         let mut tprop = TProp::from(3, &Prop::Str("Pometry".into()));
-        tprop.set(1, &Prop::Str("Pometry Inc.".into()));
-        tprop.set(2, &Prop::Str("Raphtory".into()));
+        tprop.set(1, &Prop::Str("Pometry Inc.".into())).unwrap();
+        tprop.set(2, &Prop::Str("Raphtory".into())).unwrap();
 
         assert_eq!(
             tprop.iter_window(2..3).collect::<Vec<_>>(),
@@ -306,7 +349,7 @@ mod tprop_tests {
         assert_eq!(tprop.iter_window(i64::MIN..1).collect::<Vec<_>>(), vec![]);
 
         let mut tprop = TProp::from(1, &Prop::I32(2022));
-        tprop.set(2, &Prop::I32(2023));
+        tprop.set(2, &Prop::I32(2023)).unwrap();
 
         assert_eq!(
             tprop.iter_window(i64::MIN..i64::MAX).collect::<Vec<_>>(),
@@ -314,7 +357,7 @@ mod tprop_tests {
         );
 
         let mut tprop = TProp::from(1, &Prop::I64(2022));
-        tprop.set(2, &Prop::I64(2023));
+        tprop.set(2, &Prop::I64(2023)).unwrap();
 
         assert_eq!(
             tprop.iter_window(i64::MIN..i64::MAX).collect::<Vec<_>>(),
@@ -322,7 +365,7 @@ mod tprop_tests {
         );
 
         let mut tprop = TProp::from(1, &Prop::F32(10.0));
-        tprop.set(2, &Prop::F32(11.0));
+        tprop.set(2, &Prop::F32(11.0)).unwrap();
 
         assert_eq!(
             tprop.iter_window(i64::MIN..i64::MAX).collect::<Vec<_>>(),
@@ -330,7 +373,7 @@ mod tprop_tests {
         );
 
         let mut tprop = TProp::from(1, &Prop::F64(10.0));
-        tprop.set(2, &Prop::F64(11.0));
+        tprop.set(2, &Prop::F64(11.0)).unwrap();
 
         assert_eq!(
             tprop.iter_window(i64::MIN..i64::MAX).collect::<Vec<_>>(),
@@ -338,7 +381,7 @@ mod tprop_tests {
         );
 
         let mut tprop = TProp::from(1, &Prop::U32(1));
-        tprop.set(2, &Prop::U32(2));
+        tprop.set(2, &Prop::U32(2)).unwrap();
 
         assert_eq!(
             tprop.iter_window(i64::MIN..i64::MAX).collect::<Vec<_>>(),
@@ -346,7 +389,7 @@ mod tprop_tests {
         );
 
         let mut tprop = TProp::from(1, &Prop::U64(1));
-        tprop.set(2, &Prop::U64(2));
+        tprop.set(2, &Prop::U64(2)).unwrap();
 
         assert_eq!(
             tprop.iter_window(i64::MIN..i64::MAX).collect::<Vec<_>>(),
@@ -354,7 +397,7 @@ mod tprop_tests {
         );
 
         let mut tprop = TProp::from(1, &Prop::Bool(true));
-        tprop.set(2, &Prop::Bool(true));
+        tprop.set(2, &Prop::Bool(true)).unwrap();
 
         assert_eq!(
             tprop.iter_window(i64::MIN..i64::MAX).collect::<Vec<_>>(),
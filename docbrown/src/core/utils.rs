@@ -1,6 +1,9 @@
 //! Utility functions used throughout the modules.
 
+use std::fs::File;
 use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::path::Path;
 
 use twox_hash::XxHash64;
 
@@ -13,3 +16,14 @@ pub fn calculate_hash<T: Hash>(t: &T) -> u64 {
 pub fn get_shard_id_from_global_vid(v_id: u64, n_shards: usize) -> usize {
     (v_id % n_shards as u64) as usize
 }
+
+/// Writes `bytes` to `path` by writing them to a temporary file that is renamed to `path` once it
+/// is on disk, so that `path` holds either its previous content or all of `bytes`.
+pub(crate) fn write_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    std::fs::rename(tmp, path)
+}
@@ -4,7 +4,9 @@
 //! This trait allows you to use a variety of types as input vertices, including
 //! `u64`, `&str`, and `String`.
 
+use crate::core::tgraph::VertexRef;
 use crate::core::{utils, Prop};
+use serde::{Deserialize, Serialize};
 
 pub trait InputVertex: Clone {
     fn id(&self) -> u64;
@@ -40,3 +42,65 @@ impl InputVertex for String {
         Some(Prop::Str(self.to_string()))
     }
 }
+
+/// A vertex given to one of the functions that look vertices up in a graph, such as
+/// `GraphViewOps::vertex`. Vertices given by name are looked up in the names of the graph, see
+/// `docbrown::core::vertex_names`.
+pub trait VertexLookup {
+    /// The vertex, or the vertex whose id is the hash of the name for vertices given by name.
+    fn vertex_ref(&self) -> VertexRef;
+
+    /// The name of the vertex if it is given by name.
+    fn lookup_name(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl VertexLookup for u64 {
+    fn vertex_ref(&self) -> VertexRef {
+        VertexRef::new_remote(*self)
+    }
+}
+
+impl VertexLookup for VertexRef {
+    fn vertex_ref(&self) -> VertexRef {
+        *self
+    }
+}
+
+impl VertexLookup for &str {
+    fn vertex_ref(&self) -> VertexRef {
+        VertexRef::new_remote(self.id())
+    }
+
+    fn lookup_name(&self) -> Option<&str> {
+        Some(self)
+    }
+}
+
+impl VertexLookup for String {
+    fn vertex_ref(&self) -> VertexRef {
+        VertexRef::new_remote(self.id())
+    }
+
+    fn lookup_name(&self) -> Option<&str> {
+        Some(self)
+    }
+}
+
+/// A vertex given to one of the `add_*` functions of a `Graph`, with the id it has in the graph.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct ResolvedVertex {
+    pub(crate) id: u64,
+    pub(crate) name: Option<Prop>,
+}
+
+impl InputVertex for ResolvedVertex {
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn name_prop(&self) -> Option<Prop> {
+        self.name.clone()
+    }
+}
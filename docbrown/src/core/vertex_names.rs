@@ -0,0 +1,230 @@
+//! The table of the names of the vertices of a graph.
+//!
+//! Vertices given by name, as a `&str` or `String`, get the id of their name. By default the id
+//! of a name is its hash and the graph keeps no table of names, so two names that hash to the
+//! same id become the same vertex. A graph created with `VertexIds::Checked` or
+//! `VertexIds::Dense` keeps a table of the names it was given instead: with `Checked` a new name
+//! gets its hash as id, unless that is the id of another name or of a vertex that was given by
+//! id, in which case adding it fails with a `GraphError::VertexNameCollision`. With `Dense` a new
+//! name gets the smallest id that is neither. With either, adding a vertex by id fails in the
+//! same way if the id is the id of a name, rather than adding to the vertex of that name.
+//!
+//! Vertices are looked up by name in the same way, by `GraphViewOps::vertex` and the other
+//! functions that take a `VertexLookup`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use docbrown::core::vertex_names::VertexIds;
+//! use docbrown::db::graph::Graph;
+//! use docbrown::db::view_api::*;
+//!
+//! let g = Graph::new_with_vertex_ids(2, VertexIds::Dense);
+//! g.add_edge(1, "Alice", "Bob", &vec![], None).unwrap();
+//!
+//! let alice = g.vertex("Alice").unwrap();
+//! assert_eq!(alice.id(), 0);
+//! assert_eq!(g.vertex_by_name("Bob").unwrap().id(), 1);
+//! assert!(g.has_edge("Alice", "Bob", None));
+//! assert_eq!(alice.out_degree(), 1);
+//! ```
+
+use crate::core::tgraph_shard::errors::GraphError;
+use crate::core::utils;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+/// How the vertices of a graph that are given by name get their ids.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VertexIds {
+    /// The id of a name is its hash, so it is the same in every graph. No table of names is
+    /// kept, so names with the same hash are not told apart.
+    #[default]
+    Hashed,
+    /// The id of a name is its hash, and names are kept in a table so that a name with the id
+    /// of another name or of a vertex given by id is rejected.
+    Checked,
+    /// Names get the sequential ids `0, 1, 2, ...` in the order they are first added, skipping
+    /// the ids of vertices given by id.
+    Dense,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct VertexNames {
+    ids: VertexIds,
+    by_name: FxHashMap<String, u64>,
+    by_id: FxHashMap<u64, String>,
+    // the smallest id that may be free, for dense ids
+    next_id: u64,
+}
+
+impl VertexNames {
+    pub(crate) fn new(ids: VertexIds) -> Self {
+        Self {
+            ids,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the id of `name`, which is its hash if no table of names is kept.
+    pub(crate) fn get(&self, name: &str) -> Option<u64> {
+        match self.ids {
+            VertexIds::Hashed => Some(utils::calculate_hash(&name)),
+            _ => self.by_name.get(name).copied(),
+        }
+    }
+
+    /// Returns the id of `name`, giving it one if it has none yet. `is_vertex` tells whether an
+    /// id is the id of a vertex already in the graph.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::VertexNameCollision` if the hash of `name` is the id of another
+    /// name or of a vertex that was given by id.
+    pub(crate) fn get_or_insert<F: Fn(u64) -> bool>(
+        &mut self,
+        name: &str,
+        is_vertex: F,
+    ) -> Result<u64, GraphError> {
+        if let Some(id) = self.get(name) {
+            return Ok(id);
+        }
+        let id = match self.ids {
+            VertexIds::Hashed | VertexIds::Checked => {
+                let id = utils::calculate_hash(&name);
+                if !self.by_id.contains_key(&id) && is_vertex(id) {
+                    return Err(GraphError::VertexNameCollision {
+                        name: name.to_string(),
+                        existing: id.to_string(),
+                        id,
+                    });
+                }
+                id
+            }
+            VertexIds::Dense => {
+                while self.by_id.contains_key(&self.next_id) || is_vertex(self.next_id) {
+                    self.next_id += 1;
+                }
+                self.next_id
+            }
+        };
+        self.insert(name, id)
+    }
+
+    /// Checks that `id`, the id of a vertex given by id, is not the id of a name.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::VertexNameCollision` if it is.
+    pub(crate) fn check_id(&self, id: u64) -> Result<(), GraphError> {
+        match self.by_id.get(&id) {
+            Some(existing) => Err(GraphError::VertexNameCollision {
+                name: id.to_string(),
+                existing: existing.clone(),
+                id,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Gives `name` the id `id`, unless it has one already, and returns its id.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::VertexNameCollision` if `id` is the id of another name.
+    pub(crate) fn insert(&mut self, name: &str, id: u64) -> Result<u64, GraphError> {
+        if let Some(id) = self.get(name) {
+            return Ok(id);
+        }
+        if let Some(existing) = self.by_id.get(&id) {
+            return Err(GraphError::VertexNameCollision {
+                name: name.to_string(),
+                existing: existing.clone(),
+                id,
+            });
+        }
+        self.by_name.insert(name.to_string(), id);
+        self.by_id.insert(id, name.to_string());
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod vertex_names_tests {
+    use super::*;
+
+    #[test]
+    fn hashed_ids_keep_no_names() {
+        let mut names = VertexNames::new(VertexIds::Hashed);
+        let alice = utils::calculate_hash(&"Alice");
+        assert_eq!(names.get_or_insert("Alice", |_| true), Ok(alice));
+        assert_eq!(
+            names.insert("Mallory", alice),
+            Ok(utils::calculate_hash(&"Mallory"))
+        );
+        assert_eq!(names, VertexNames::new(VertexIds::Hashed));
+    }
+
+    #[test]
+    fn names_with_the_id_of_another_name_are_rejected() {
+        let mut names = VertexNames::new(VertexIds::Checked);
+        let alice = names.get_or_insert("Alice", |_| false).unwrap();
+        assert_eq!(alice, utils::calculate_hash(&"Alice"));
+        assert_eq!(names.get_or_insert("Alice", |_| true), Ok(alice));
+        assert_eq!(
+            names.insert("Mallory", alice),
+            Err(GraphError::VertexNameCollision {
+                name: "Mallory".to_string(),
+                existing: "Alice".to_string(),
+                id: alice,
+            })
+        );
+        assert_eq!(names.get("Mallory"), None);
+    }
+
+    #[test]
+    fn names_with_the_id_of_a_vertex_given_by_id_are_rejected() {
+        let mut names = VertexNames::new(VertexIds::Checked);
+        let alice = utils::calculate_hash(&"Alice");
+        assert_eq!(
+            names.get_or_insert("Alice", |id| id == alice),
+            Err(GraphError::VertexNameCollision {
+                name: "Alice".to_string(),
+                existing: alice.to_string(),
+                id: alice,
+            })
+        );
+        assert_eq!(names.get("Alice"), None);
+    }
+
+    #[test]
+    fn ids_of_names_are_rejected_as_vertex_ids() {
+        for ids in [VertexIds::Checked, VertexIds::Dense] {
+            let mut names = VertexNames::new(ids);
+            let alice = names.get_or_insert("Alice", |_| false).unwrap();
+            assert_eq!(
+                names.check_id(alice),
+                Err(GraphError::VertexNameCollision {
+                    name: alice.to_string(),
+                    existing: "Alice".to_string(),
+                    id: alice,
+                })
+            );
+            assert_eq!(names.check_id(alice + 1), Ok(()));
+        }
+
+        let mut names = VertexNames::new(VertexIds::Hashed);
+        let alice = names.get_or_insert("Alice", |_| false).unwrap();
+        assert_eq!(names.check_id(alice), Ok(()));
+    }
+
+    #[test]
+    fn dense_ids_skip_the_ids_that_are_taken() {
+        let mut names = VertexNames::new(VertexIds::Dense);
+        assert_eq!(names.get_or_insert("Alice", |_| false), Ok(0));
+        assert_eq!(names.insert("Bob", 1), Ok(1));
+        assert_eq!(names.get_or_insert("Carol", |id| id == 2), Ok(3));
+        assert_eq!(names.get_or_insert("Alice", |_| false), Ok(0));
+        assert_eq!(names.get("Bob"), Some(1));
+    }
+}
@@ -6,6 +6,7 @@
 //!
 
 use crate::core::tgraph::{EdgeRef, VertexRef};
+use crate::core::time::TimeUnit;
 use crate::core::Direction;
 use crate::core::Prop;
 use crate::db::vertex::VertexView;
@@ -261,6 +262,10 @@ impl<G: GraphViewOps> TimeOps for EdgeView<G> {
         }
     }
 
+    fn time_unit(&self) -> TimeUnit {
+        self.graph.time_unit()
+    }
+
     fn window(&self, t_start: i64, t_end: i64) -> Self::WindowedViewType {
         Self {
             graph: self.graph.clone(),
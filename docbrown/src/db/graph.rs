@@ -17,31 +17,40 @@
 //! ```
 //!
 
-use crate::core::tgraph::TemporalGraph;
+use crate::core::schema::Schema;
+use crate::core::tgraph::errors::MutateGraphError;
+use crate::core::tgraph::{MutateGraphResult, TemporalGraph};
 use crate::core::tgraph_shard::TGraphShard;
-use crate::core::time::{IntoTime, IntoTimeWithFormat};
+use crate::core::time::{IntoTime, IntoTimeWithFormat, TimeUnit};
 use crate::core::{
     tgraph::{EdgeRef, VertexRef},
     tgraph_shard::errors::GraphError,
     utils,
-    vertex::InputVertex,
+    vertex::{InputVertex, ResolvedVertex},
+    vertex_names::{VertexIds, VertexNames},
     Direction, Prop,
 };
 
 use crate::db::graph_immutable::ImmutableGraph;
+use crate::db::manifest::{self, Manifest};
+use crate::db::vertex::VertexView;
 use crate::db::view_api::internal::GraphViewInternalOps;
+use crate::db::wal::{Wal, WalEntry, WAL_FILE};
 use itertools::Itertools;
 use rayon::prelude::*;
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 use std::cmp::{max, min};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     ops::Range,
-    path::{Path, PathBuf},
+    path::Path,
     sync::Arc,
 };
 
+/// The result of adding each record of a batch, by position in the batch.
+type BatchResults = Vec<(usize, Result<(), GraphError>)>;
+
 /// A temporal graph composed of multiple shards.
 ///
 /// This is the public facing struct used to create a temporal graph, add vertices and edges,
@@ -55,6 +64,15 @@ pub struct Graph {
     pub(crate) shards: Vec<TGraphShard<TemporalGraph>>,
     /// Translates layer names to layer ids
     pub(crate) layer_ids: Arc<parking_lot::RwLock<FxHashMap<String, usize>>>,
+    /// The declared property types that new properties are checked against
+    pub(crate) schema: Arc<parking_lot::RwLock<Schema>>,
+    /// The ids of the vertices that were given by name
+    pub(crate) vertex_names: Arc<parking_lot::RwLock<VertexNames>>,
+    /// The unit that times are counted in
+    pub(crate) time_unit: TimeUnit,
+    /// The write-ahead log that changes are recorded in, if enabled
+    #[serde(skip)]
+    pub(crate) wal: Arc<parking_lot::RwLock<Option<Wal>>>,
 }
 
 impl GraphViewInternalOps for Graph {
@@ -73,6 +91,10 @@ impl GraphViewInternalOps for Graph {
         self.latest_time_global().map(|t| t + 1) // so it is exclusive
     }
 
+    fn view_time_unit(&self) -> TimeUnit {
+        self.time_unit
+    }
+
     fn earliest_time_global(&self) -> Option<i64> {
         let min_from_shards = self.shards.iter().map(|shard| shard.earliest_time()).min();
         min_from_shards.filter(|&min| min != i64::MAX)
@@ -174,6 +196,10 @@ impl GraphViewInternalOps for Graph {
             .degree_window(v.g_id, t_start..t_end, d, layer)
     }
 
+    fn vertex_id_by_name(&self, name: &str) -> Option<u64> {
+        self.vertex_names.read().get(name)
+    }
+
     fn vertex_ref(&self, v: u64) -> Option<VertexRef> {
         self.get_shard_from_id(v).vertex(v)
     }
@@ -567,6 +593,9 @@ impl Graph {
             nr_shards: self.nr_shards,
             shards: self.shards.iter().map(|s| s.freeze()).collect_vec(),
             layer_ids: Arc::new(self.layer_ids.read().clone()),
+            schema: Arc::new(self.schema.read().clone()),
+            vertex_names: Arc::new(self.vertex_names.read().clone()),
+            time_unit: self.time_unit,
         }
     }
 
@@ -583,6 +612,45 @@ impl Graph {
         utils::get_shard_id_from_global_vid(g_id, self.nr_shards)
     }
 
+    /// Returns `v` with the id it has in the graph, giving its name an id if it has none yet.
+    fn resolve<V: InputVertex>(&self, v: &V) -> Result<ResolvedVertex, GraphError> {
+        let name = v.name_prop();
+        let id = match &name {
+            Some(Prop::Str(name)) => {
+                let id = self.vertex_names.read().get(name);
+                match id {
+                    Some(id) => id,
+                    None => self
+                        .vertex_names
+                        .write()
+                        .get_or_insert(name, |id| self.get_shard_from_id(id).has_vertex(id))?,
+                }
+            }
+            _ => {
+                let id = v.id();
+                self.vertex_names.read().check_id(id)?;
+                id
+            }
+        };
+        Ok(ResolvedVertex { id, name })
+    }
+
+    /// Returns the id `v` has in the graph without giving its name one.
+    fn vertex_id<V: InputVertex>(&self, v: &V) -> u64 {
+        match v.name_prop() {
+            Some(Prop::Str(name)) => self.vertex_names.read().get(&name).unwrap_or(v.id()),
+            _ => v.id(),
+        }
+    }
+
+    /// Gives the name of `v` the id of `v`, for vertices that were resolved before.
+    pub(crate) fn register_name(&self, v: &ResolvedVertex) -> Result<(), GraphError> {
+        if let Some(Prop::Str(name)) = &v.name {
+            self.vertex_names.write().insert(name, v.id)?;
+        }
+        Ok(())
+    }
+
     /// Get the shard from a global vertex id
     ///
     /// # Arguments
@@ -639,15 +707,69 @@ impl Graph {
     /// let g = Graph::new(4);
     /// ```
     pub fn new(nr_shards: usize) -> Self {
+        Self::new_with_vertex_ids(nr_shards, VertexIds::Hashed)
+    }
+
+    /// Create a new graph with the specified number of shards, whose vertices that are given by
+    /// name get their ids as set by `ids`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use docbrown::core::vertex_names::VertexIds;
+    /// use docbrown::db::graph::Graph;
+    /// use docbrown::db::view_api::*;
+    ///
+    /// let g = Graph::new_with_vertex_ids(4, VertexIds::Dense);
+    /// g.add_vertex(0, "Alice", &vec![]).unwrap();
+    /// assert_eq!(g.vertex_by_name("Alice").unwrap().id(), 0);
+    /// ```
+    pub fn new_with_vertex_ids(nr_shards: usize, ids: VertexIds) -> Self {
+        Self::new_with(nr_shards, ids, TimeUnit::default())
+    }
+
+    /// Create a new graph with the specified number of shards, whose times are counted in
+    /// `unit`.
+    ///
+    /// Datetime strings given as times and intervals given to `rolling` and `expanding` are
+    /// converted to `unit`, see `docbrown::core::time`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use docbrown::core::time::TimeUnit;
+    /// use docbrown::db::graph::Graph;
+    /// use docbrown::db::view_api::*;
+    ///
+    /// let g = Graph::new_with_time_unit(4, TimeUnit::Microseconds);
+    /// g.add_vertex("1970-01-01 00:00:00.000002", "Alice", &vec![]).unwrap();
+    /// assert_eq!(g.earliest_time(), Some(2));
+    /// ```
+    pub fn new_with_time_unit(nr_shards: usize, unit: TimeUnit) -> Self {
+        Self::new_with(nr_shards, VertexIds::Hashed, unit)
+    }
+
+    /// Create a new graph with the specified number of shards, whose vertices that are given by
+    /// name get their ids as set by `ids` and whose times are counted in `time_unit`, see
+    /// `new_with_vertex_ids` and `new_with_time_unit`.
+    pub fn new_with(nr_shards: usize, ids: VertexIds, time_unit: TimeUnit) -> Self {
         Graph {
             nr_shards,
             shards: (0..nr_shards).map(|_| TGraphShard::default()).collect(),
             layer_ids: Default::default(),
+            schema: Default::default(),
+            vertex_names: Arc::new(parking_lot::RwLock::new(VertexNames::new(ids))),
+            time_unit,
+            wal: Default::default(),
         }
     }
 
     /// Load a graph from a directory
     ///
+    /// If the graph in the directory has a write-ahead log (see `enable_wal`), the changes in it
+    /// are replayed on top of the saved graph and the loaded graph keeps recording its changes
+    /// in the same log.
+    ///
     /// # Arguments
     ///
     /// * `path` - The path to the directory
@@ -662,40 +784,255 @@ impl Graph {
     /// use docbrown::db::graph::Graph;
     /// // let g = Graph::load_from_file("path/to/graph");
     /// ```
-    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<bincode::ErrorKind>> {
-        // use BufReader for better performance
-
-        //TODO turn to logging?
-        //println!("loading from {:?}", path.as_ref());
-        let mut p = PathBuf::from(path.as_ref());
-        p.push("graphdb_nr_shards");
-
-        let f = std::fs::File::open(p).unwrap();
-        let mut reader = std::io::BufReader::new(f);
-        let (nr_shards, layer_ids) = bincode::deserialize_from(&mut reader)?;
-
-        let mut shard_paths = vec![];
-        for i in 0..nr_shards {
-            let mut p = PathBuf::from(path.as_ref());
-            p.push(format!("shard_{}", i));
-            shard_paths.push((i, p));
-        }
-        let mut shards = shard_paths
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, GraphError> {
+        let path = path.as_ref();
+        let Manifest {
+            nr_shards,
+            layer_ids,
+            schema,
+            vertex_names,
+            time_unit,
+            generation,
+            shard_checksums,
+        } = Manifest::read(path)?;
+
+        let mut shards = shard_checksums
             .par_iter()
-            .map(|(i, path)| {
-                let shard = TGraphShard::load_from_file(path)?;
-                Ok((*i, shard))
+            .enumerate()
+            .map(|(i, checksum)| {
+                let bytes = std::fs::read(path.join(manifest::shard_file(i, generation)))?;
+                if crc32fast::hash(&bytes) != *checksum {
+                    return Err(GraphError::CorruptedShard { shard: i });
+                }
+                Ok((i, TGraphShard::from_bytes(&bytes)?))
             })
-            .collect::<Result<Vec<_>, Box<bincode::ErrorKind>>>()?;
+            .collect::<Result<Vec<_>, GraphError>>()?;
 
         shards.sort_by_cached_key(|(i, _)| *i);
 
         let shards = shards.into_iter().map(|(_, shard)| shard).collect();
-        Ok(Graph {
+        let g = Graph {
             nr_shards,
             shards,
-            layer_ids,
-        }) //TODO I need to put in the actual values here
+            layer_ids: Arc::new(parking_lot::RwLock::new(layer_ids)),
+            schema: Arc::new(parking_lot::RwLock::new(schema)),
+            vertex_names: Arc::new(parking_lot::RwLock::new(vertex_names)),
+            time_unit,
+            wal: Default::default(),
+        };
+
+        if path.join(WAL_FILE).exists() {
+            let (wal, entries) = Wal::open(path)?;
+            for entry in entries {
+                // a change that failed when it was made fails again and is left out as it was then
+                let _ = entry.apply(&g);
+            }
+            *g.wal.write() = Some(wal);
+        }
+        Ok(g)
+    }
+
+    /// Load a graph from a directory into `nr_shards` shards, whatever the number of shards it
+    /// was saved with.
+    ///
+    /// The loaded graph keeps recording its changes in the write-ahead log of the directory, if
+    /// it has one, and the next `checkpoint` saves it with the new number of shards.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use docbrown::db::graph::Graph;
+    /// use docbrown::db::view_api::*;
+    /// use tempdir::TempDir;
+    ///
+    /// let dir = TempDir::new("graph").unwrap();
+    /// let g = Graph::new(2);
+    /// g.add_edge(1, "Alice", "Bob", &vec![], None).unwrap();
+    /// g.save_to_file(dir.path()).unwrap();
+    ///
+    /// let g = Graph::load_from_file_with_shards(dir.path(), 8).unwrap();
+    /// assert!(g.has_edge("Alice", "Bob", None));
+    /// ```
+    pub fn load_from_file_with_shards<P: AsRef<Path>>(
+        path: P,
+        nr_shards: usize,
+    ) -> Result<Self, GraphError> {
+        let g = Self::load_from_file(path)?;
+        if g.nr_shards == nr_shards {
+            return Ok(g);
+        }
+        let resharded = g.reshard(nr_shards)?;
+        *resharded.wal.write() = g.wal.write().take();
+        Ok(resharded)
+    }
+
+    /// Returns a copy of the graph with its vertices spread over `nr_shards` shards.
+    ///
+    /// Vertices are placed on the shard for their id as in `Graph::new(nr_shards)`, together
+    /// with their history, properties and edges in every layer. Layer ids, vertex ids, the
+    /// schema and the time unit stay the same. The copy has no write-ahead log.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use docbrown::db::graph::Graph;
+    /// use docbrown::db::view_api::*;
+    ///
+    /// let g = Graph::new(1);
+    /// g.add_edge(1, "Alice", "Bob", &vec![], Some("follows")).unwrap();
+    ///
+    /// let g = g.reshard(4).unwrap();
+    /// assert!(g.has_edge("Alice", "Bob", Some("follows")));
+    /// ```
+    pub fn reshard(&self, nr_shards: usize) -> Result<Graph, GraphError> {
+        let g = Graph::new_with_time_unit(nr_shards, self.time_unit);
+        let layer_ids = self.layer_ids.read().clone();
+        let mut layer_names = vec![None; layer_ids.len() + 1];
+        for (name, &id) in layer_ids.iter() {
+            layer_names[id] = Some(name.as_str());
+            for shard in &g.shards {
+                shard.allocate_layer(id)?;
+            }
+        }
+        *g.layer_ids.write() = layer_ids.clone();
+
+        self.shards.par_iter().try_for_each(|shard| {
+            for v in shard.vertex_ids() {
+                for (t, props) in
+                    Self::props_by_time(shard.vertex_timestamps(v), shard.temporal_vertex_props(v))
+                {
+                    g.add_vertex(t, v, &props)?;
+                }
+                let static_props = shard
+                    .static_vertex_prop_names(v)
+                    .into_iter()
+                    .filter_map(|name| Some((name.clone(), shard.static_vertex_prop(v, name)?)))
+                    .collect_vec();
+                if !static_props.is_empty() {
+                    g.add_vertex_properties(v, &static_props)?;
+                }
+
+                let out_edges = shard.vertex_edges(v, Direction::OUT, None).collect_vec();
+                for e in out_edges {
+                    let layer = layer_names[e.layer_id];
+                    for (t, props) in Self::props_by_time(
+                        shard.edge_timestamps(
+                            e.src_g_id,
+                            e.dst_g_id,
+                            e.layer_id,
+                            None,
+                            self.nr_shards,
+                        ),
+                        shard.temporal_edge_props(e.edge_id, e.layer_id),
+                    ) {
+                        g.add_edge(t, e.src_g_id, e.dst_g_id, &props, layer)?;
+                    }
+                    let static_props = shard
+                        .static_edge_prop_names(e.edge_id, e.layer_id)
+                        .into_iter()
+                        .filter_map(|name| {
+                            let prop = shard.static_edge_prop(e.edge_id, e.layer_id, name.clone());
+                            Some((name, prop?))
+                        })
+                        .collect_vec();
+                    if !static_props.is_empty() {
+                        g.add_edge_properties(e.src_g_id, e.dst_g_id, &static_props, layer)?;
+                    }
+                }
+            }
+            Ok::<_, GraphError>(())
+        })?;
+        // copied last so the vertices and values are replayed as they are stored, not checked
+        // against the names and coerced
+        *g.vertex_names.write() = self.vertex_names.read().clone();
+        *g.schema.write() = self.schema.read().clone();
+        Ok(g)
+    }
+
+    /// Groups the history of temporal properties by time, with an entry for every time in
+    /// `timestamps` even if no property was set then.
+    fn props_by_time(
+        timestamps: Vec<i64>,
+        props: HashMap<String, Vec<(i64, Prop)>>,
+    ) -> BTreeMap<i64, Vec<(String, Prop)>> {
+        let mut by_time: BTreeMap<_, Vec<_>> =
+            timestamps.into_iter().map(|t| (t, vec![])).collect();
+        for (name, history) in props {
+            for (t, prop) in history {
+                by_time.entry(t).or_default().push((name.clone(), prop));
+            }
+        }
+        by_time
+    }
+
+    /// Saves the graph to the directory `path` and from then on records every change made to it
+    /// in a write-ahead log in the same directory, so that `load_from_file(path)` recovers every
+    /// change that was acknowledged before a crash.
+    ///
+    /// Each change is written to the log, and synced to disk, before it is applied to the graph,
+    /// and changes are logged in the order they are applied in. A change that cannot be logged is
+    /// not applied. Use `checkpoint` to save the graph again and empty the log.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use docbrown::db::graph::Graph;
+    /// use docbrown::db::view_api::*;
+    /// use tempdir::TempDir;
+    ///
+    /// let dir = TempDir::new("graph").unwrap();
+    /// let g = Graph::new(2);
+    /// g.enable_wal(dir.path()).unwrap();
+    /// g.add_edge(1, "Alice", "Bob", &vec![], None).unwrap();
+    /// // no explicit save, e.g. because the process crashed
+    /// drop(g);
+    ///
+    /// let g = Graph::load_from_file(dir.path()).unwrap();
+    /// assert!(g.has_edge("Alice", "Bob", None));
+    /// g.checkpoint().unwrap();
+    /// ```
+    pub fn enable_wal<P: AsRef<Path>>(&self, path: P) -> Result<(), GraphError> {
+        let mut wal = self.wal.write();
+        self.save_to_file(path.as_ref())?;
+        *wal = Some(Wal::create(path.as_ref())?);
+        Ok(())
+    }
+
+    /// Saves the graph to the directory of its write-ahead log and empties the log.
+    ///
+    /// Changes made while the checkpoint is taken wait for it to finish. The snapshot is written
+    /// as in `save_to_file`, which leaves the previous snapshot in place until the new one is
+    /// complete, and the log is only emptied after that. If the process dies before the new
+    /// snapshot is complete, the log is replayed on top of the previous one. If it dies after,
+    /// the log is replayed on top of the new snapshot, which already has its changes and is left
+    /// the same by them.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::WalDisabled` if the graph has no write-ahead log.
+    pub fn checkpoint(&self) -> Result<(), GraphError> {
+        let wal = self.wal.write();
+        let wal = wal.as_ref().ok_or(GraphError::WalDisabled)?;
+        self.save_to_file(wal.dir())?;
+        wal.truncate()?;
+        Ok(())
+    }
+
+    /// Records `entries` in the write-ahead log, if the graph has one, and then makes the change
+    /// they record with `apply`.
+    ///
+    /// While the graph has a log, changes are logged and made one at a time, so that replaying
+    /// the log makes them in the order they were made in. A change that is logged and then fails
+    /// fails in the same way when the log is replayed.
+    fn logged<A, I, F>(wal: &Option<Wal>, entries: I, apply: F) -> Result<A, GraphError>
+    where
+        I: IntoIterator<Item = WalEntry>,
+        F: FnOnce() -> Result<A, GraphError>,
+    {
+        match wal {
+            Some(wal) => wal.append_then(&entries.into_iter().collect_vec(), apply)?,
+            None => apply(),
+        }
     }
 
     /// Save a graph to a directory
@@ -717,30 +1054,47 @@ impl Graph {
     /// g.add_vertex(1, 1, &vec![]);
     /// // g.save_to_file("path_str");
     /// ```
-    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<bincode::ErrorKind>> {
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), GraphError> {
+        let path = path.as_ref();
         // write each shard to a different file
 
         // crate directory path if it doesn't exist
-        std::fs::create_dir_all(path.as_ref())?;
-
-        let mut shard_paths = vec![];
-        for i in 0..self.nr_shards {
-            let mut p = PathBuf::from(path.as_ref());
-            p.push(format!("shard_{}", i));
-            //TODO turn to logging?
-            //println!("saving shard {} to {:?}", i, p);
-            shard_paths.push((i, p));
-        }
-        shard_paths
+        std::fs::create_dir_all(path)?;
+
+        // the files of the previous save are left as they are until the new manifest replaces
+        // the one listing them
+        let generation = match Manifest::read(path) {
+            Ok(previous) => previous.generation + 1,
+            Err(_) => 0,
+        };
+        let shard_checksums = self
+            .shards
             .par_iter()
-            .try_for_each(|(i, path)| self.shards[*i].save_to_file(path))?;
+            .enumerate()
+            .map(|(i, shard)| shard.save_to_file(path.join(manifest::shard_file(i, generation))))
+            .collect::<Result<Vec<_>, GraphError>>()?;
 
-        let mut p = PathBuf::from(path.as_ref());
-        p.push("graphdb_nr_shards");
-
-        let f = std::fs::File::create(p)?;
-        let writer = std::io::BufWriter::new(f);
-        bincode::serialize_into(writer, &(self.nr_shards, self.layer_ids.clone()))?;
+        Manifest {
+            nr_shards: self.nr_shards,
+            layer_ids: self.layer_ids.read().clone(),
+            schema: self.schema.read().clone(),
+            vertex_names: self.vertex_names.read().clone(),
+            time_unit: self.time_unit,
+            generation,
+            shard_checksums,
+        }
+        .write(path)?;
+
+        // remove the shards of previous saves
+        let current = (0..self.nr_shards)
+            .map(|i| manifest::shard_file(i, generation))
+            .collect::<HashSet<_>>();
+        for entry in std::fs::read_dir(path)? {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+            if name.starts_with("shard_") && !current.contains(&name) {
+                std::fs::remove_file(path.join(name))?;
+            }
+        }
         Ok(())
     }
 
@@ -771,8 +1125,21 @@ impl Graph {
         v: V,
         props: &Vec<(String, Prop)>,
     ) -> Result<(), GraphError> {
+        let t = t.time_in(self.time_unit)?;
+        let wal = self.wal.read();
+        let schema = self.schema.read();
+        let coerced = schema.coerce_vertex_props(props)?;
+        let props = coerced.as_ref().unwrap_or(props);
+        let v = self.resolve(&v)?;
+        let entry = wal.as_ref().map(|_| WalEntry::AddVertex {
+            t,
+            v: v.clone(),
+            props: props.clone(),
+        });
         let shard_id = utils::get_shard_id_from_global_vid(v.id(), self.nr_shards);
-        self.shards[shard_id].add_vertex(t.into_time()?, v, props)
+        Self::logged(&wal, entry, || {
+            self.shards[shard_id].add_vertex(t, v, props)
+        })
     }
 
     pub fn add_vertex_with_custom_time_format<V: InputVertex>(
@@ -782,7 +1149,7 @@ impl Graph {
         v: V,
         props: &Vec<(String, Prop)>,
     ) -> Result<(), GraphError> {
-        let time: i64 = t.parse_time(fmt)?;
+        let time: i64 = t.parse_time(fmt, self.time_unit)?;
         self.add_vertex(time, v, props)
     }
 
@@ -808,8 +1175,19 @@ impl Graph {
         v: V,
         data: &Vec<(String, Prop)>,
     ) -> Result<(), GraphError> {
-        let shard_id = utils::get_shard_id_from_global_vid(v.id(), self.nr_shards);
-        self.shards[shard_id].add_vertex_properties(v.id(), data)
+        let wal = self.wal.read();
+        let schema = self.schema.read();
+        let coerced = schema.coerce_vertex_props(data)?;
+        let data = coerced.as_ref().unwrap_or(data);
+        let v = self.vertex_id(&v);
+        let entry = wal.as_ref().map(|_| WalEntry::AddVertexProperties {
+            v,
+            props: data.clone(),
+        });
+        let shard_id = utils::get_shard_id_from_global_vid(v, self.nr_shards);
+        Self::logged(&wal, entry, || {
+            self.shards[shard_id].add_vertex_properties(v, data)
+        })
     }
 
     // TODO: Vertex.name which gets ._id property else numba as string
@@ -840,27 +1218,41 @@ impl Graph {
         props: &Vec<(String, Prop)>,
         layer: Option<&str>,
     ) -> Result<(), GraphError> {
-        let time = t.into_time()?;
+        let time = t.time_in(self.time_unit)?;
+        let wal = self.wal.read();
+        let schema = self.schema.read();
+        let coerced = schema.coerce_edge_props(layer, props)?;
+        let props = coerced.as_ref().unwrap_or(props);
+        let src = self.resolve(&src)?;
+        let dst = self.resolve(&dst)?;
         let src_shard_id = utils::get_shard_id_from_global_vid(src.id(), self.nr_shards);
         let dst_shard_id = utils::get_shard_id_from_global_vid(dst.id(), self.nr_shards);
 
-        let layer_id = self.get_or_allocate_layer(layer);
-
-        if src_shard_id == dst_shard_id {
-            self.shards[src_shard_id].add_edge(time, src, dst, props, layer_id)
-        } else {
-            // FIXME these are sort of connected, we need to hold both locks for
-            // the src partition and dst partition to add a remote edge between both
-            self.shards[src_shard_id].add_edge_remote_out(
-                time,
-                src.clone(),
-                dst.clone(),
-                props,
-                layer_id,
-            )?;
-            self.shards[dst_shard_id].add_edge_remote_into(time, src, dst, props, layer_id)?;
-            Ok(())
-        }
+        let entry = wal.as_ref().map(|_| WalEntry::AddEdge {
+            t: time,
+            src: src.clone(),
+            dst: dst.clone(),
+            props: props.clone(),
+            layer: layer.map(|l| l.to_string()),
+        });
+        Self::logged(&wal, entry, || {
+            // layers are allocated in the order they are logged in so that replays give them
+            // the same ids
+            let layer_id = self.get_or_allocate_layer(layer);
+            if src_shard_id == dst_shard_id {
+                self.shards[src_shard_id].add_edge(time, src, dst, props, layer_id)
+            } else {
+                TGraphShard::add_edge_across(
+                    &self.shards[src_shard_id],
+                    &self.shards[dst_shard_id],
+                    time,
+                    src,
+                    dst,
+                    props,
+                    layer_id,
+                )
+            }
+        })
     }
 
     pub fn add_edge_with_custom_time_format<V: InputVertex>(
@@ -872,7 +1264,7 @@ impl Graph {
         props: &Vec<(String, Prop)>,
         layer: Option<&str>,
     ) -> Result<(), GraphError> {
-        let time: i64 = t.parse_time(fmt)?;
+        let time: i64 = t.parse_time(fmt, self.time_unit)?;
         self.add_edge(time, src, dst, props, layer)
     }
 
@@ -883,6 +1275,7 @@ impl Graph {
     /// * `src` - An instance of `T` that implements the `InputVertex` trait representing the source vertex.
     /// * `dst` - An instance of `T` that implements the `InputVertex` trait representing the destination vertex.
     /// * `props` - A vector of tuples containing the property name and value pairs to add to the edge.
+    /// * `layer` - The layer of the edge, fails with `GraphError::LayerNotFound` if the graph has no such layer.
     ///
     /// # Example
     ///
@@ -903,11 +1296,348 @@ impl Graph {
         props: &Vec<(String, Prop)>,
         layer: Option<&str>,
     ) -> Result<(), GraphError> {
-        let layer_id = self.get_layer(layer).unwrap(); // FIXME: bubble up instead
-
+        let layer_id = self
+            .get_layer(layer)
+            .ok_or_else(|| GraphError::LayerNotFound {
+                name: layer.unwrap_or_default().to_string(),
+            })?;
+        let wal = self.wal.read();
+        let schema = self.schema.read();
+        let coerced = schema.coerce_edge_props(layer, props)?;
+        let props = coerced.as_ref().unwrap_or(props);
+        let (src, dst) = (self.vertex_id(&src), self.vertex_id(&dst));
+
+        let entry = wal.as_ref().map(|_| WalEntry::AddEdgeProperties {
+            src,
+            dst,
+            props: props.clone(),
+            layer: layer.map(|l| l.to_string()),
+        });
         // TODO: we don't add properties to dst shard, but may need to depending on the plans
-        self.get_shard_from_id(src.id())
-            .add_edge_properties(src.id(), dst.id(), props, layer_id)
+        Self::logged(&wal, entry, || {
+            self.get_shard_from_id(src)
+                .add_edge_properties(src, dst, props, layer_id)
+        })
+    }
+
+    /// Adds many vertices at once, see `add_vertex`.
+    ///
+    /// The vertices are grouped by shard and each shard adds its vertices, in the order they were
+    /// given, under a single write lock and in parallel with the other shards. This is a lot
+    /// faster than calling `add_vertex` for every vertex when loading large amounts of data.
+    ///
+    /// # Returns
+    ///
+    /// The result of adding each vertex, in the order the vertices were given.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use docbrown::core::Prop;
+    /// use docbrown::db::graph::Graph;
+    /// use docbrown::db::view_api::*;
+    ///
+    /// let graph = Graph::new(2);
+    /// let results = graph.add_vertices_batch(vec![
+    ///     (1, "Alice", vec![]),
+    ///     (2, "Bob", vec![("age".to_string(), Prop::I64(41))]),
+    /// ]);
+    /// assert!(results.iter().all(|r| r.is_ok()));
+    /// assert_eq!(graph.num_vertices(), 2);
+    /// ```
+    pub fn add_vertices_batch<V, T, I>(&self, vertices: I) -> Vec<Result<(), GraphError>>
+    where
+        V: InputVertex + Send,
+        T: IntoTime,
+        I: IntoIterator<Item = (T, V, Vec<(String, Prop)>)>,
+    {
+        let wal = self.wal.read();
+        let schema = self.schema.read();
+        let mut results = vec![];
+        let mut by_shard = self.shards.iter().map(|_| vec![]).collect_vec();
+        for (i, (t, v, props)) in vertices.into_iter().enumerate() {
+            let record = t
+                .time_in(self.time_unit)
+                .map_err(GraphError::from)
+                .and_then(|t| {
+                    let coerced = schema.coerce_vertex_props(&props)?;
+                    Ok((t, self.resolve(&v)?, coerced.unwrap_or(props)))
+                });
+            match record {
+                Ok((t, v, props)) => {
+                    by_shard[self.shard_id(v.id())].push((i, t, v, props));
+                    results.push(Ok(()));
+                }
+                Err(e) => results.push(Err(e)),
+            }
+        }
+
+        let entries = match wal.as_ref() {
+            Some(_) => by_shard
+                .iter()
+                .flatten()
+                .map(|(_, t, v, props)| WalEntry::AddVertex {
+                    t: *t,
+                    v: v.clone(),
+                    props: props.clone(),
+                })
+                .collect(),
+            None => vec![],
+        };
+        let added = Self::logged(&wal, entries, || {
+            TGraphShard::write_all(&self.shards, |tgs| {
+                tgs.par_iter_mut()
+                    .zip(by_shard)
+                    .flat_map_iter(|(tg, records)| {
+                        records
+                            .into_iter()
+                            .map(|(i, t, v, props)| {
+                                let res = tg.add_vertex_with_props(t, v, &props);
+                                (
+                                    i,
+                                    res.map_err(|e| GraphError::FailedToMutateGraph { source: e }),
+                                )
+                            })
+                            .collect_vec()
+                    })
+                    .collect()
+            })
+        });
+        Self::finish_batch(results, added)
+    }
+
+    /// Adds many edges to `layer` at once, see `add_edge`.
+    ///
+    /// The edges are grouped by the shards of their source and destination vertices and each
+    /// shard adds its edges, in the order they were given, under a single write lock and in
+    /// parallel with the other shards. All shards stay locked until the whole batch is added,
+    /// and an edge that fails on one of its shards is not added to the other one.
+    ///
+    /// # Returns
+    ///
+    /// The result of adding each edge, in the order the edges were given.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use docbrown::core::Prop;
+    /// use docbrown::db::graph::Graph;
+    /// use docbrown::db::view_api::*;
+    ///
+    /// let graph = Graph::new(2);
+    /// let results = graph.add_edges_batch(
+    ///     vec![
+    ///         (1, "Alice", "Bob", vec![]),
+    ///         (2, "Bob", "Carol", vec![("amount".to_string(), Prop::F64(9.5))]),
+    ///     ],
+    ///     None,
+    /// );
+    /// assert!(results.iter().all(|r| r.is_ok()));
+    /// assert_eq!(graph.num_edges(), 2);
+    /// ```
+    pub fn add_edges_batch<V, T, I>(
+        &self,
+        edges: I,
+        layer: Option<&str>,
+    ) -> Vec<Result<(), GraphError>>
+    where
+        V: InputVertex + Send + Sync,
+        T: IntoTime,
+        I: IntoIterator<Item = (T, V, V, Vec<(String, Prop)>)>,
+    {
+        let wal = self.wal.read();
+        let schema = self.schema.read();
+        let mut results = vec![];
+        let mut records = vec![];
+        // indices into `records` of the edges each shard adds (half of)
+        let mut by_shard = self.shards.iter().map(|_| vec![]).collect_vec();
+        for (i, (t, src, dst, props)) in edges.into_iter().enumerate() {
+            let record = t
+                .time_in(self.time_unit)
+                .map_err(GraphError::from)
+                .and_then(|t| {
+                    let coerced = schema.coerce_edge_props(layer, &props)?;
+                    let (src, dst) = (self.resolve(&src)?, self.resolve(&dst)?);
+                    Ok((t, src, dst, coerced.unwrap_or(props)))
+                });
+            match record {
+                Ok((t, src, dst, props)) => {
+                    let src_shard_id = self.shard_id(src.id());
+                    let dst_shard_id = self.shard_id(dst.id());
+                    by_shard[src_shard_id].push(records.len());
+                    if dst_shard_id != src_shard_id {
+                        by_shard[dst_shard_id].push(records.len());
+                    }
+                    records.push((i, t, src, dst, props));
+                    results.push(Ok(()));
+                }
+                Err(e) => results.push(Err(e)),
+            }
+        }
+
+        let entries = match wal.as_ref() {
+            Some(_) => records
+                .iter()
+                .map(|(_, t, src, dst, props)| WalEntry::AddEdge {
+                    t: *t,
+                    src: src.clone(),
+                    dst: dst.clone(),
+                    props: props.clone(),
+                    layer: layer.map(|l| l.to_string()),
+                })
+                .collect(),
+            None => vec![],
+        };
+        let added = Self::logged(&wal, entries, || {
+            let layer_id = self.get_or_allocate_layer(layer);
+            TGraphShard::write_all(&self.shards, |tgs| {
+                // check both halves of every edge before adding any of them
+                let failed: Vec<(usize, MutateGraphError)> = tgs
+                    .par_iter()
+                    .zip(&by_shard)
+                    .flat_map_iter(|(tg, indices)| {
+                        indices
+                            .iter()
+                            .filter_map(|&r| {
+                                let (_, _, src, dst, props) = &records[r];
+                                let res = tg.check_edge_prop_kinds(
+                                    src.id(),
+                                    dst.id(),
+                                    props,
+                                    false,
+                                    layer_id,
+                                );
+                                res.err().map(|e| (r, e))
+                            })
+                            .collect_vec()
+                    })
+                    .collect();
+                let mut is_failed = vec![false; records.len()];
+                for (r, _) in &failed {
+                    is_failed[*r] = true;
+                }
+
+                let added: Vec<(usize, MutateGraphResult)> = tgs
+                    .par_iter_mut()
+                    .enumerate()
+                    .zip(&by_shard)
+                    .flat_map_iter(|((shard_id, tg), indices)| {
+                        indices
+                            .iter()
+                            .filter(|&&r| !is_failed[r])
+                            .map(|&r| {
+                                let (_, t, src, dst, props) = &records[r];
+                                let (src, dst) = (src.clone(), dst.clone());
+                                let res = if self.shard_id(src.id()) != shard_id {
+                                    tg.add_edge_remote_into(*t, src, dst, props, layer_id)
+                                } else if self.shard_id(dst.id()) != shard_id {
+                                    tg.add_edge_remote_out(*t, src, dst, props, layer_id)
+                                } else {
+                                    tg.add_edge_with_props(*t, src, dst, props, layer_id)
+                                };
+                                (r, res)
+                            })
+                            .collect_vec()
+                    })
+                    .collect();
+
+                failed
+                    .into_iter()
+                    .map(|(r, e)| (r, Err(e)))
+                    .chain(added)
+                    .map(|(r, res)| {
+                        let res = res.map_err(|e| GraphError::FailedToMutateGraph { source: e });
+                        (records[r].0, res)
+                    })
+                    .collect()
+            })
+        });
+        Self::finish_batch(results, added)
+    }
+
+    /// Merges the results of applying a batch to the shards into the results of preparing it,
+    /// keeping the first error of each record.
+    fn finish_batch(
+        mut results: Vec<Result<(), GraphError>>,
+        added: Result<BatchResults, GraphError>,
+    ) -> Vec<Result<(), GraphError>> {
+        match added {
+            Ok(added) => {
+                for (i, res) in added {
+                    if results[i].is_ok() {
+                        results[i] = res;
+                    }
+                }
+            }
+            Err(GraphError::Io { kind, message }) => {
+                // the batch could not be logged, so nothing was added
+                for res in results.iter_mut().filter(|res| res.is_ok()) {
+                    *res = Err(GraphError::Io {
+                        kind,
+                        message: message.clone(),
+                    });
+                }
+            }
+            Err(_) => {
+                // the shards could not be locked, so nothing was added
+                for res in results.iter_mut().filter(|res| res.is_ok()) {
+                    *res = Err(GraphError::IllegalGraphAccess);
+                }
+            }
+        }
+        results
+    }
+
+    /// Sets the declared property types that properties added from now on are checked against.
+    ///
+    /// Fails with a `GraphError::PropertyTypeMismatch`, leaving the schema as it was, if a
+    /// property already in the graph holds values of another type than the one declared for it.
+    ///
+    /// See `docbrown::core::schema` for how values are checked and coerced.
+    pub fn set_schema(&self, schema: Schema) -> Result<(), GraphError> {
+        let wal = self.wal.read();
+        // held while checking so that no value of another type is added in the meantime, adding
+        // values holds the read lock until they are stored
+        let mut current = self.schema.write();
+        let layers = schema
+            .layers()
+            .filter_map(|layer| Some((self.get_layer(layer)?, schema.edge_props(layer)?)))
+            .collect_vec();
+        for shard in &self.shards {
+            shard.check_declared_types(schema.vertex_props(), &layers)?;
+        }
+        let entry = wal.as_ref().map(|_| WalEntry::SetSchema(schema.clone()));
+        Self::logged(&wal, entry, || {
+            *current = schema;
+            Ok(())
+        })
+    }
+
+    /// The declared property types of the graph.
+    pub fn schema(&self) -> Schema {
+        self.schema.read().clone()
+    }
+
+    /// Returns the vertex that was added with the name `name`, the same as `vertex` does when
+    /// given a name.
+    ///
+    /// See `docbrown::core::vertex_names` for how names get their ids.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use docbrown::db::graph::Graph;
+    /// use docbrown::db::view_api::*;
+    ///
+    /// let g = Graph::new(2);
+    /// g.add_vertex(0, "Alice", &vec![]).unwrap();
+    /// assert_eq!(g.vertex_by_name("Alice").unwrap().name(), "Alice");
+    /// assert!(g.vertex_by_name("Bob").is_none());
+    /// ```
+    pub fn vertex_by_name(&self, name: &str) -> Option<VertexView<Graph>> {
+        let id = self.vertex_names.read().get(name)?;
+        let v = self.vertex_ref(id)?;
+        Some(VertexView::new(self.clone(), v))
     }
 
     fn get_or_allocate_layer(&self, key: Option<&str>) -> usize {
@@ -924,16 +1654,21 @@ impl Graph {
 }
 
 #[cfg(test)]
-mod db_tests {
+pub(crate) mod db_tests {
     use super::*;
+    use crate::core::tgraph::errors::MutateGraphError;
+    use crate::core::time::error::ParseTimeError;
     use crate::core::utils;
+    use crate::core::PropType;
     use crate::db::edge::EdgeView;
     use crate::db::path::PathFromVertex;
     use crate::db::view_api::*;
     use crate::graphgen::random_attachment::random_attachment;
     use csv::StringRecord;
     use itertools::Itertools;
+    use std::collections::HashSet;
     use std::fs;
+    use std::path::PathBuf;
     use std::sync::Arc;
     use tempdir::TempDir;
     use uuid::Uuid;
@@ -994,6 +1729,45 @@ mod db_tests {
         assert_eq!(g.num_edges(), unique_edge_count);
     }
 
+    #[quickcheck]
+    fn concurrent_cross_shard_edges_are_all_or_nothing(edges: Vec<(u8, u8, bool)>) {
+        let g = Graph::new(4);
+        // "w" becomes a static edge property on the shard of vertex 0, so adding it as a
+        // temporal property fails for every edge with an endpoint on that shard
+        g.add_edge(0, 0, 0, &vec![], None).unwrap();
+        g.add_edge_properties(0, 0, &vec![("w".to_string(), Prop::U64(1))], None)
+            .unwrap();
+
+        let added: HashSet<(u64, u64)> = edges
+            .par_iter()
+            .enumerate()
+            .filter_map(|(t, (src, dst, w))| {
+                let (src, dst) = ((src % 16) as u64, (dst % 16) as u64);
+                let name = if *w { "w" } else { "v" };
+                let props = vec![(name.to_string(), Prop::U64(t as u64))];
+                g.add_edge(t as i64, src, dst, &props, None)
+                    .ok()
+                    .map(|_| (src, dst))
+            })
+            .chain(rayon::iter::once((0, 0)))
+            .collect();
+
+        let vertices: HashSet<u64> = added.iter().flat_map(|(s, d)| [*s, *d]).collect();
+        assert_eq!(g.num_vertices(), vertices.len());
+        for v in g.vertices() {
+            for n in v.out_neighbours() {
+                assert!(added.contains(&(v.id(), n.id())));
+                assert!(n.in_neighbours().id().any(|id| id == v.id()));
+            }
+            for n in v.in_neighbours() {
+                assert!(added.contains(&(n.id(), v.id())));
+            }
+        }
+        for (src, dst) in added {
+            assert!(g.has_edge(src, dst, None));
+        }
+    }
+
     #[test]
     fn graph_save_to_load_from_file() {
         let vs = vec![
@@ -1022,7 +1796,7 @@ mod db_tests {
         let mut expected = vec![
             format!("{}/shard_1", shards_path),
             format!("{}/shard_0", shards_path),
-            format!("{}/graphdb_nr_shards", shards_path),
+            format!("{}/manifest", shards_path),
         ]
         .iter()
         .map(Path::new)
@@ -1057,6 +1831,447 @@ mod db_tests {
         let _ = tmp_docbrown_path.close();
     }
 
+    #[test]
+    fn loading_reports_missing_and_corrupted_files() {
+        let dir = TempDir::new("docbrown").unwrap();
+        assert!(matches!(
+            Graph::load_from_file(dir.path().join("missing")),
+            Err(GraphError::Io {
+                kind: std::io::ErrorKind::NotFound,
+                ..
+            })
+        ));
+
+        let g = Graph::new(2);
+        g.add_edge(1, 1, 2, &vec![], None).unwrap();
+        g.save_to_file(dir.path()).unwrap();
+
+        let shard = dir.path().join("shard_1");
+        let mut bytes = fs::read(&shard).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        fs::write(&shard, bytes).unwrap();
+        assert_eq!(
+            Graph::load_from_file(dir.path()).err(),
+            Some(GraphError::CorruptedShard { shard: 1 })
+        );
+    }
+
+    #[test]
+    fn interrupted_saves_leave_the_previous_save_in_place() {
+        let dir = TempDir::new("docbrown").unwrap();
+        let g = Graph::new(2);
+        g.add_edge(1, 1, 2, &vec![], None).unwrap();
+        g.save_to_file(dir.path()).unwrap();
+        let saved = contents(&g);
+
+        // a save that died after writing some of its shards, but before its manifest
+        g.add_edge(2, 2, 3, &vec![], None).unwrap();
+        g.shards[0]
+            .save_to_file(dir.path().join(manifest::shard_file(0, 1)))
+            .unwrap();
+        fs::write(dir.path().join(manifest::shard_file(1, 1)), b"half a sh").unwrap();
+        assert_eq!(contents(&Graph::load_from_file(dir.path()).unwrap()), saved);
+
+        // the next save replaces the files of both
+        g.save_to_file(dir.path()).unwrap();
+        assert_eq!(
+            contents(&Graph::load_from_file(dir.path()).unwrap()),
+            contents(&g)
+        );
+        let files = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .sorted()
+            .collect_vec();
+        assert_eq!(files, vec!["manifest", "shard_0.1", "shard_1.1"]);
+    }
+
+    #[test]
+    fn graphs_saved_before_the_manifest_can_be_loaded() {
+        let g = Graph::new(2);
+        g.add_edge(1, 1, 2, &vec![], Some("follows")).unwrap();
+        g.add_edge(2, "Alice", "Bob", &vec![], None).unwrap();
+        let dir = TempDir::new("docbrown").unwrap();
+        g.save_to_file(dir.path()).unwrap();
+
+        // replace the manifest with the file the old format had instead
+        fs::remove_file(dir.path().join("manifest")).unwrap();
+        let legacy = bincode::serialize(&(2usize, g.layer_ids.read().clone())).unwrap();
+        fs::write(dir.path().join("graphdb_nr_shards"), legacy).unwrap();
+
+        let g = Graph::load_from_file(dir.path()).unwrap();
+        assert!(g.has_edge(1, 2, Some("follows")));
+        assert_eq!(g.nr_shards, 2);
+        assert_eq!(g.vertex_by_name("Bob").unwrap().id(), "Bob".id());
+    }
+
+    /// Everything that can be read about the vertices and edges of `g`, in a stable order.
+    pub(crate) fn contents<G: GraphViewOps>(g: &G) -> Vec<String> {
+        let vertices = g.vertices().iter().map(|v| {
+            format!(
+                "{} {} {:?} {:?} {:?} {} {} {} {:?}",
+                v.id(),
+                v.name(),
+                v.history(),
+                v.property_histories()
+                    .into_iter()
+                    .collect::<BTreeMap<_, _>>(),
+                v.properties(true).into_iter().collect::<BTreeMap<_, _>>(),
+                v.degree(),
+                v.in_degree(),
+                v.out_degree(),
+                v.neighbours().id().sorted().collect_vec(),
+            )
+        });
+        let edges = g.edges().map(|e| {
+            format!(
+                "{}->{} {:?} {:?} {:?}",
+                e.src().id(),
+                e.dst().id(),
+                e.history(),
+                e.property_histories()
+                    .into_iter()
+                    // windows of a `Graph` also list the properties without values in them
+                    .filter(|(_, history)| !history.is_empty())
+                    .collect::<BTreeMap<_, _>>(),
+                e.property_names(true)
+                    .into_iter()
+                    .filter_map(|name| Some((name.clone(), e.static_property(name)?)))
+                    .collect::<BTreeMap<_, _>>(),
+            )
+        });
+        vertices.chain(edges).sorted().collect()
+    }
+
+    #[test]
+    fn resharding_keeps_the_graph_the_same() {
+        let g = Graph::new(3);
+        let names = ["Alice", "Bob", "Carol", "Dave", "Eve"];
+        for (t, name) in names.iter().enumerate() {
+            g.add_vertex(
+                t as i64,
+                *name,
+                &vec![("age".to_string(), Prop::I64(t as i64))],
+            )
+            .unwrap();
+            g.add_vertex_properties(*name, &vec![("team".to_string(), Prop::U32(t as u32 % 2))])
+                .unwrap();
+        }
+        for (t, (src, dst)) in names.iter().tuple_combinations().enumerate() {
+            let props = vec![("weight".to_string(), Prop::F64(t as f64))];
+            g.add_edge(t as i64, *src, *dst, &props, None).unwrap();
+            g.add_edge(t as i64 + 10, *src, *dst, &vec![], None)
+                .unwrap();
+            g.add_edge(t as i64, *dst, *src, &vec![], Some("follows"))
+                .unwrap();
+            g.add_edge_properties(*dst, *src, &props, Some("follows"))
+                .unwrap();
+        }
+        g.add_vertex(20, 42, &vec![]).unwrap();
+        g.add_edge(21, 42, 42, &vec![], Some("empty")).unwrap();
+
+        for nr_shards in [1, 2, 7] {
+            let resharded = g.reshard(nr_shards).unwrap();
+            assert_eq!(resharded.nr_shards, nr_shards);
+            assert_eq!(*resharded.layer_ids.read(), *g.layer_ids.read());
+            assert_eq!(contents(&resharded), contents(&g));
+            for layer in ["follows", "empty"] {
+                assert_eq!(
+                    contents(&resharded.layer(layer).unwrap()),
+                    contents(&g.layer(layer).unwrap())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn resharding_keeps_the_schema_and_the_stored_values() {
+        let g = Graph::new(2);
+        g.set_schema(Schema::new().vertex_prop("age", PropType::I64).edge_prop(
+            Some("trades"),
+            "amount",
+            PropType::F64,
+        ))
+        .unwrap();
+        g.add_vertex(0, 1, &vec![("age".to_string(), Prop::I32(40))])
+            .unwrap();
+        g.add_vertex(1, 2, &vec![("nick".to_string(), Prop::I32(7))])
+            .unwrap();
+        g.add_edge(
+            2,
+            1,
+            2,
+            &vec![("amount".to_string(), Prop::F64(1.5))],
+            Some("trades"),
+        )
+        .unwrap();
+
+        let resharded = g.reshard(3).unwrap();
+        assert_eq!(resharded.schema(), g.schema());
+        assert_eq!(contents(&resharded), contents(&g));
+        assert_eq!(
+            resharded
+                .vertex(2)
+                .unwrap()
+                .property("nick".to_string(), true),
+            Some(Prop::I32(7))
+        );
+    }
+
+    #[test]
+    fn graphs_can_be_loaded_with_a_different_number_of_shards() {
+        let dir = TempDir::new("docbrown").unwrap();
+        let g = Graph::new(4);
+        g.add_edge(1, "Alice", "Bob", &vec![], None).unwrap();
+        g.add_edge(2, "Bob", "Carol", &vec![], None).unwrap();
+        g.enable_wal(dir.path()).unwrap();
+        g.add_edge(3, "Carol", "Alice", &vec![], None).unwrap();
+
+        let loaded = Graph::load_from_file_with_shards(dir.path(), 2).unwrap();
+        assert_eq!(loaded.nr_shards, 2);
+        assert_eq!(contents(&loaded), contents(&g));
+
+        // the loaded graph keeps logging and its checkpoints drop the extra shards
+        loaded.add_edge(4, "Alice", "Dave", &vec![], None).unwrap();
+        let reloaded = Graph::load_from_file(dir.path()).unwrap();
+        assert!(reloaded.has_edge("Alice", "Dave", None));
+        loaded.checkpoint().unwrap();
+        assert!(!dir.path().join("shard_2").exists());
+        let reloaded = Graph::load_from_file(dir.path()).unwrap();
+        assert_eq!(reloaded.nr_shards, 2);
+        assert_eq!(contents(&reloaded), contents(&loaded));
+    }
+
+    #[test]
+    fn names_with_the_id_of_another_name_are_rejected() {
+        let g = Graph::new_with_vertex_ids(2, VertexIds::Checked);
+        g.add_vertex(0, "Mallory", &vec![]).unwrap();
+        // stands in for a name whose hash is the same as the hash of "Alice"
+        g.vertex_names.write().insert("Eve", "Alice".id()).unwrap();
+        let collision = || {
+            Err(GraphError::VertexNameCollision {
+                name: "Alice".to_string(),
+                existing: "Eve".to_string(),
+                id: "Alice".id(),
+            })
+        };
+
+        assert_eq!(g.add_vertex(1, "Alice", &vec![]), collision());
+        assert_eq!(
+            g.add_edge(1, "Mallory", "Alice", &vec![], None),
+            collision()
+        );
+        assert_eq!(
+            g.add_edges_batch(vec![(1, "Mallory", "Alice", vec![])], None),
+            vec![collision()]
+        );
+        assert_eq!(
+            g.add_vertices_batch(vec![(1, "Alice", vec![])]),
+            vec![collision()]
+        );
+        assert_eq!(g.num_vertices(), 1);
+        assert_eq!(g.num_edges(), 0);
+        assert!(g.vertex_by_name("Alice").is_none());
+    }
+
+    #[test]
+    fn names_with_the_id_of_a_vertex_given_by_id_are_rejected() {
+        let g = Graph::new_with_vertex_ids(2, VertexIds::Checked);
+        g.add_vertex(0, "Alice".id(), &vec![]).unwrap();
+        assert_eq!(
+            g.add_vertex(1, "Alice", &vec![]),
+            Err(GraphError::VertexNameCollision {
+                name: "Alice".to_string(),
+                existing: "Alice".id().to_string(),
+                id: "Alice".id(),
+            })
+        );
+        assert!(g.vertex_by_name("Alice").is_none());
+
+        // dense ids skip the ids of vertices given by id instead
+        let g = Graph::new_with_vertex_ids(2, VertexIds::Dense);
+        g.add_vertex(0, 0, &vec![]).unwrap();
+        g.add_vertex(1, "Alice", &vec![]).unwrap();
+        assert_eq!(g.vertex("Alice").unwrap().id(), 1);
+        assert_eq!(g.vertex(0).unwrap().history(), vec![0]);
+    }
+
+    #[test]
+    fn vertex_ids_that_are_the_ids_of_names_are_rejected() {
+        for ids in [VertexIds::Checked, VertexIds::Dense] {
+            let g = Graph::new_with_vertex_ids(2, ids);
+            g.add_vertex(0, "Alice", &vec![]).unwrap();
+            let alice = g.vertex("Alice").unwrap().id();
+            let collision = || {
+                Err(GraphError::VertexNameCollision {
+                    name: alice.to_string(),
+                    existing: "Alice".to_string(),
+                    id: alice,
+                })
+            };
+
+            assert_eq!(g.add_vertex(1, alice, &vec![]), collision());
+            assert_eq!(g.add_edge(1, alice, 7, &vec![], None), collision());
+            assert_eq!(
+                g.add_edges_batch(vec![(1, 7, alice, vec![])], None),
+                vec![collision()]
+            );
+            assert_eq!(
+                g.add_vertices_batch(vec![(1, alice, vec![])]),
+                vec![collision()]
+            );
+            assert_eq!(g.num_vertices(), 1);
+            assert_eq!(g.num_edges(), 0);
+            assert_eq!(g.vertex("Alice").unwrap().history(), vec![0]);
+        }
+    }
+
+    #[test]
+    fn names_are_looked_up_in_the_names_of_the_graph() {
+        let g = Graph::new_with_vertex_ids(2, VertexIds::Dense);
+        g.add_edge(1, "Alice", "Bob", &vec![], None).unwrap();
+        g.add_edge(2, "Bob", "Carol", &vec![], Some("friends"))
+            .unwrap();
+
+        assert!(g.has_vertex("Alice"));
+        assert!(!g.has_vertex("Dave"));
+        assert_eq!(g.vertex("Bob").unwrap().id(), 1);
+        assert_eq!(g.vertex("Bob".to_string()).unwrap().id(), 1);
+        assert!(g.has_edge("Alice", "Bob", None));
+        assert!(!g.has_edge("Alice", "Dave", None));
+        assert_eq!(g.edge("Alice", "Bob", None).unwrap().dst().id(), 1);
+        assert_eq!(g.vertices().get("Carol").unwrap().id(), 2);
+
+        let w = g.window(2, 3);
+        assert!(w.has_vertex("Carol"));
+        assert!(!w.has_vertex("Alice"));
+        assert!(w.has_edge("Bob", "Carol", Some("friends")));
+        let friends = g.layer("friends").unwrap();
+        assert!(friends.edge("Bob", "Carol", Some("friends")).is_some());
+    }
+
+    #[test]
+    fn dense_vertex_ids_are_kept_by_the_log_saves_and_resharding() {
+        let dir = TempDir::new("docbrown").unwrap();
+        let g = Graph::new_with_vertex_ids(3, VertexIds::Dense);
+        g.add_edge(1, "Alice", "Bob", &vec![], None).unwrap();
+        g.enable_wal(dir.path()).unwrap();
+        g.add_edges_batch(vec![(2, "Bob", "Carol", vec![])], None);
+        g.add_vertex(3, "Dave", &vec![]).unwrap();
+        g.add_edge_properties(
+            "Alice",
+            "Bob",
+            &vec![("weight".to_string(), Prop::I64(1))],
+            None,
+        )
+        .unwrap();
+
+        let ids = |g: &Graph| {
+            ["Alice", "Bob", "Carol", "Dave"]
+                .map(|name| g.vertex_by_name(name).map(|v| v.id()))
+                .to_vec()
+        };
+        assert_eq!(ids(&g), vec![Some(0), Some(1), Some(2), Some(3)]);
+        assert!(g.has_edge(0, 1, None));
+        assert!(g.has_edge(1, 2, None));
+
+        let loaded = Graph::load_from_file(dir.path()).unwrap();
+        assert_eq!(ids(&loaded), ids(&g));
+        assert_eq!(contents(&loaded), contents(&g));
+        loaded.checkpoint().unwrap();
+        let reloaded = Graph::load_from_file(dir.path()).unwrap();
+        assert_eq!(ids(&reloaded), ids(&g));
+        reloaded.add_vertex(4, "Eve", &vec![]).unwrap();
+        assert_eq!(reloaded.vertex_by_name("Eve").unwrap().id(), 4);
+
+        let resharded = g.reshard(2).unwrap();
+        assert_eq!(ids(&resharded), ids(&g));
+        assert_eq!(contents(&resharded), contents(&g));
+    }
+
+    #[test]
+    fn the_time_unit_is_kept_by_saves_and_resharding() {
+        let dir = TempDir::new("docbrown").unwrap();
+        let g = Graph::new_with_time_unit(2, TimeUnit::Microseconds);
+        g.add_vertex("1970-01-01 00:00:00.000010", "Alice", &vec![])
+            .unwrap();
+        g.add_edge_with_custom_time_format(
+            "1970-01-01 00:00:00.000029",
+            "%Y-%m-%d %H:%M:%S%.f",
+            "Alice",
+            "Bob",
+            &vec![],
+            None,
+        )
+        .unwrap();
+        assert_eq!(g.earliest_time(), Some(10));
+        assert_eq!(g.latest_time(), Some(29));
+
+        let windows = |g: &Graph| {
+            assert_eq!(g.time_unit(), TimeUnit::Microseconds);
+            g.rolling("10 microseconds", None)
+                .unwrap()
+                .map(|w| (w.start().unwrap(), w.end().unwrap()))
+                .collect_vec()
+        };
+        assert_eq!(windows(&g), vec![(10, 20), (20, 30)]);
+        assert!(matches!(
+            g.rolling("1 nanosecond", None),
+            Err(ParseTimeError::NotRepresentable(TimeUnit::Microseconds))
+        ));
+
+        g.save_to_file(dir.path()).unwrap();
+        let loaded = Graph::load_from_file(dir.path()).unwrap();
+        assert_eq!(windows(&loaded), windows(&g));
+        assert_eq!(windows(&g.reshard(3).unwrap()), windows(&g));
+        assert_eq!(windows(&loaded.freeze().unfreeze().unwrap()), windows(&g));
+    }
+
+    #[test]
+    fn nested_props_survive_save_and_load() {
+        let booked = chrono::DateTime::parse_from_rfc3339("2023-04-01T10:00:00+02:00").unwrap();
+        let props = vec![
+            ("booked".to_string(), Prop::DTime(booked)),
+            (
+                "tags".to_string(),
+                Prop::List(vec![Prop::Str("fx".to_string()), Prop::I64(3)]),
+            ),
+            (
+                "meta".to_string(),
+                Prop::Map(Box::new(
+                    [("desk".to_string(), Prop::List(vec![Prop::Bool(true)]))]
+                        .into_iter()
+                        .collect(),
+                )),
+            ),
+            ("signature".to_string(), Prop::Bytes(vec![0xca, 0xfe])),
+        ];
+
+        let g = Graph::new(2);
+        g.add_vertex(1, 1, &props).unwrap();
+        g.add_vertex(2, 1, &vec![("tags".to_string(), Prop::List(vec![]))])
+            .unwrap();
+
+        let tmp_docbrown_path: TempDir = TempDir::new("docbrown").unwrap();
+        g.save_to_file(tmp_docbrown_path.path()).unwrap();
+        let g = Graph::load_from_file(tmp_docbrown_path.path()).unwrap();
+
+        let v = g.vertex(1).unwrap();
+        for (name, prop) in props {
+            assert_eq!(v.at(1).property(name, false), Some(prop));
+        }
+        assert_eq!(
+            v.property_history("tags".to_string()).len(),
+            2,
+            "both lists are kept in the history"
+        );
+        assert_eq!(
+            v.property("booked".to_string(), false).unwrap().to_string(),
+            "2023-04-01T10:00:00+02:00"
+        );
+    }
+
     #[test]
     fn has_edge() {
         let g = Graph::new(2);
@@ -1427,18 +2642,273 @@ mod db_tests {
     }
 
     #[test]
-    #[should_panic]
-    fn changing_property_type_for_vertex_panics() {
+    fn batches_match_single_additions() {
+        let edges = vec![
+            (1, 1, 2, vec![("w".to_string(), Prop::U64(1))]),
+            (2, 2, 3, vec![]),
+            (3, 3, 1, vec![("w".to_string(), Prop::U64(2))]),
+            (4, 1, 2, vec![("w".to_string(), Prop::U64(3))]),
+            (5, 4, 4, vec![]),
+        ];
+        let vertices = vec![
+            (0, 5, vec![("age".to_string(), Prop::I64(3))]),
+            (6, 1, vec![]),
+        ];
+
+        let g = Graph::new(3);
+        for (t, src, dst, props) in edges.clone() {
+            g.add_edge(t, src, dst, &props, Some("layer")).unwrap();
+        }
+        for (t, v, props) in vertices.clone() {
+            g.add_vertex(t, v, &props).unwrap();
+        }
+
+        let batched = Graph::new(3);
+        let results = batched.add_edges_batch(edges, Some("layer"));
+        assert!(results.iter().all(|res| res.is_ok()));
+        let results = batched.add_vertices_batch(vertices);
+        assert!(results.iter().all(|res| res.is_ok()));
+
+        assert_eq!(batched.num_vertices(), g.num_vertices());
+        assert_eq!(batched.num_edges(), g.num_edges());
+        for v in g.vertices() {
+            let w = batched.vertex(v.id()).unwrap();
+            assert_eq!(w.history(), v.history());
+            assert_eq!(w.properties(true), v.properties(true));
+            assert_eq!(
+                w.out_neighbours().id().collect_vec(),
+                v.out_neighbours().id().collect_vec()
+            );
+            assert_eq!(
+                w.in_neighbours().id().collect_vec(),
+                v.in_neighbours().id().collect_vec()
+            );
+        }
+        let e = batched.edge(1, 2, Some("layer")).unwrap();
+        assert_eq!(
+            e.property_history("w".to_string()),
+            vec![(1, Prop::U64(1)), (4, Prop::U64(3))]
+        );
+    }
+
+    #[test]
+    fn batches_report_errors_per_record() {
+        let g = Graph::new(2);
+        g.set_schema(Schema::new().edge_prop(None, "w", PropType::U64))
+            .unwrap();
+        g.add_edge(0, 1, 2, &vec![], None).unwrap();
+        g.add_edge_properties(1, 2, &vec![("s".to_string(), Prop::Bool(true))], None)
+            .unwrap();
+
+        let results = g.add_edges_batch(
+            vec![
+                ("yesterday", 1, 3, vec![]),
+                (
+                    "2021-01-01 00:00:03",
+                    2,
+                    3,
+                    vec![("w".to_string(), Prop::Str("x".to_string()))],
+                ),
+                (
+                    "2021-01-01 00:00:04",
+                    3,
+                    1,
+                    vec![("s".to_string(), Prop::Bool(false))],
+                ),
+                (
+                    "2021-01-01 00:00:05",
+                    3,
+                    4,
+                    vec![("w".to_string(), Prop::U32(1))],
+                ),
+            ],
+            None,
+        );
+        assert!(matches!(results[0], Err(GraphError::ParseTime { .. })));
+        assert!(matches!(
+            results[1],
+            Err(GraphError::PropertyTypeMismatch { .. })
+        ));
+        assert!(matches!(
+            results[2],
+            Err(GraphError::FailedToMutateGraph {
+                source: MutateGraphError::IllegalEdgePropertyKind { .. }
+            })
+        ));
+        assert_eq!(results[3], Ok(()));
+
+        // none of the failed edges was added, not even half of one
+        assert_eq!(g.num_edges(), 2);
+        assert!(!g.has_edge(3, 1, None));
+        assert_eq!(g.vertex(1).unwrap().in_degree(), 0);
+    }
+
+    #[test]
+    fn wal_recovers_changes_since_the_last_checkpoint() {
+        let dir = TempDir::new("docbrown").unwrap();
+        let g = Graph::new(2);
+        g.add_vertex(0, "Alice", &vec![]).unwrap();
+        assert_eq!(g.checkpoint(), Err(GraphError::WalDisabled));
+
+        g.enable_wal(dir.path()).unwrap();
+        g.set_schema(Schema::new().vertex_prop("age", PropType::I64))
+            .unwrap();
+        g.add_vertex(1, "Bob", &vec![("age".to_string(), Prop::I32(42))])
+            .unwrap();
+        g.add_vertex_properties("Bob", &vec![("team".to_string(), Prop::U64(7))])
+            .unwrap();
+        g.add_edge(2, "Alice", "Bob", &vec![], Some("friends"))
+            .unwrap();
+        g.add_edge_properties(
+            "Alice",
+            "Bob",
+            &vec![("since".to_string(), Prop::I64(2))],
+            Some("friends"),
+        )
+        .unwrap();
+        let results = g.add_edges_batch(vec![(3, "Bob", "Carol", vec![])], None);
+        assert_eq!(results, vec![Ok(())]);
+        // failed changes are not logged
+        assert!(g
+            .add_vertex(4, "Dave", &vec![("age".to_string(), Prop::F64(1.0))])
+            .is_err());
+
+        let check = |g: &Graph| {
+            assert_eq!(g.schema(), Schema::new().vertex_prop("age", PropType::I64));
+            let bob = g.vertex("Bob").unwrap();
+            assert_eq!(bob.property("age".to_string(), false), Some(Prop::I64(42)));
+            assert_eq!(bob.static_property("team".to_string()), Some(Prop::U64(7)));
+            let e = g.edge("Alice", "Bob", Some("friends")).unwrap();
+            assert_eq!(e.static_property("since".to_string()), Some(Prop::I64(2)));
+            assert!(g.has_edge("Bob", "Carol", None));
+        };
+
+        let loaded = Graph::load_from_file(dir.path()).unwrap();
+        assert_eq!(loaded.num_vertices(), 3);
+        check(&loaded);
+
+        // the loaded graph keeps logging to the same directory
+        loaded.add_vertex(5, "Erin", &vec![]).unwrap();
+        loaded.checkpoint().unwrap();
+        assert_eq!(fs::metadata(dir.path().join("wal")).unwrap().len(), 0);
+        let loaded = Graph::load_from_file(dir.path()).unwrap();
+        assert!(loaded.has_vertex("Erin"));
+        loaded.add_vertex(6, "Frank", &vec![]).unwrap();
+        let loaded = Graph::load_from_file(dir.path()).unwrap();
+        assert!(loaded.has_vertex("Frank"));
+        check(&loaded);
+    }
+
+    #[test]
+    fn checkpoints_interrupted_at_any_point_lose_nothing() {
+        let dir = TempDir::new("docbrown").unwrap();
+        let g = Graph::new(2);
+        g.add_vertex(0, "Alice", &vec![]).unwrap();
+        g.enable_wal(dir.path()).unwrap();
+        g.add_vertex(1, "Bob", &vec![("age".to_string(), Prop::I64(42))])
+            .unwrap();
+        g.add_vertex_properties("Bob", &vec![("team".to_string(), Prop::U64(7))])
+            .unwrap();
+        g.add_edge(
+            2,
+            "Alice",
+            "Bob",
+            &vec![("amount".to_string(), Prop::F64(1.5))],
+            Some("pays"),
+        )
+        .unwrap();
+        g.add_edge_properties(
+            "Alice",
+            "Bob",
+            &vec![("since".to_string(), Prop::I64(2))],
+            Some("pays"),
+        )
+        .unwrap();
+        g.add_edges_batch(vec![(3, "Bob", "Carol", vec![])], None);
+        g.add_vertex(4, "Dave", &vec![]).unwrap();
+
+        // died while writing the shards of the checkpoint
+        g.shards[0]
+            .save_to_file(dir.path().join(manifest::shard_file(0, 1)))
+            .unwrap();
+        let loaded = Graph::load_from_file(dir.path()).unwrap();
+        assert_eq!(contents(&loaded), contents(&g));
+
+        // died after the snapshot was saved but before the log was emptied, so the log is
+        // replayed on top of a snapshot that already has its changes
+        g.save_to_file(dir.path()).unwrap();
+        assert_ne!(fs::metadata(dir.path().join("wal")).unwrap().len(), 0);
+        let loaded = Graph::load_from_file(dir.path()).unwrap();
+        assert_eq!(contents(&loaded), contents(&g));
+        assert_eq!(
+            contents(&loaded.layer("pays").unwrap()),
+            contents(&g.layer("pays").unwrap())
+        );
+    }
+
+    #[test]
+    fn wal_replays_concurrent_changes_in_the_order_they_were_made() {
+        let dir = TempDir::new("docbrown").unwrap();
+        let g = Graph::new(3);
+        g.enable_wal(dir.path()).unwrap();
+
+        std::thread::scope(|s| {
+            for k in 0..3u64 {
+                let g = &g;
+                let layer = format!("layer_{}", k);
+                let edge = move |i: u64| (k * 100 + i, k * 100 + i + 1);
+                s.spawn(move || {
+                    for i in 0..20 {
+                        let (src, dst) = edge(i);
+                        g.add_edge(i as i64, src, dst, &vec![], Some(&layer))
+                            .unwrap();
+                    }
+                });
+                // adds properties to each edge as soon as it can see it
+                s.spawn(move || {
+                    for i in 0..20 {
+                        let (src, dst) = edge(i);
+                        let layer = format!("layer_{}", k);
+                        while !g.has_edge(src, dst, Some(&layer)) {
+                            std::thread::yield_now();
+                        }
+                        let props = vec![("seen".to_string(), Prop::U64(i))];
+                        g.add_edge_properties(src, dst, &props, Some(&layer))
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        let loaded = Graph::load_from_file(dir.path()).unwrap();
+        assert_eq!(*loaded.layer_ids.read(), *g.layer_ids.read());
+        for k in 0..3 {
+            let layer = format!("layer_{}", k);
+            assert_eq!(
+                contents(&loaded.layer(&layer).unwrap()),
+                contents(&g.layer(&layer).unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn changing_property_kind_for_vertex_fails() {
         let g = Graph::new(4);
         g.add_vertex(0, 11, &vec![("test".to_string(), Prop::Bool(true))])
             .unwrap();
-        g.add_vertex_properties(11, &vec![("test".to_string(), Prop::Bool(true))])
-            .unwrap();
+        let err = g
+            .add_vertex_properties(11, &vec![("test".to_string(), Prop::Bool(true))])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            GraphError::FailedToMutateGraph {
+                source: MutateGraphError::IllegalVertexPropertyKind { vertex_id: 11, .. }
+            }
+        ));
     }
 
     #[test]
-    #[should_panic]
-    fn changing_property_type_for_edge_panics() {
+    fn changing_property_kind_for_edge_fails() {
         let g = Graph::new(4);
         g.add_edge(
             0,
@@ -1448,8 +2918,233 @@ mod db_tests {
             None,
         )
         .unwrap();
-        g.add_edge_properties(11, 22, &vec![("test".to_string(), Prop::Bool(true))], None)
+        let err = g
+            .add_edge_properties(11, 22, &vec![("test".to_string(), Prop::Bool(true))], None)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            GraphError::FailedToMutateGraph {
+                source: MutateGraphError::IllegalEdgePropertyKind {
+                    src_id: 11,
+                    dst_id: 22,
+                    ..
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn schema_coerces_props_and_survives_save_and_load() {
+        let g = Graph::new(2);
+        g.set_schema(Schema::new().vertex_prop("age", PropType::I64).edge_prop(
+            Some("trades"),
+            "amount",
+            PropType::F64,
+        ))
+        .unwrap();
+
+        g.add_vertex(0, 1, &vec![("age".to_string(), Prop::U32(40))])
+            .unwrap();
+        g.add_edge(
+            1,
+            1,
+            2,
+            &vec![("amount".to_string(), Prop::F32(2.5))],
+            Some("trades"),
+        )
+        .unwrap();
+        // the schema only covers the "trades" layer
+        g.add_edge(2, 1, 2, &vec![("amount".to_string(), Prop::I32(1))], None)
+            .unwrap();
+        assert_eq!(
+            g.add_edge(
+                3,
+                1,
+                2,
+                &vec![("amount".to_string(), Prop::I64(1))],
+                Some("trades")
+            ),
+            Err(GraphError::PropertyTypeMismatch {
+                name: "amount".to_string(),
+                expected: PropType::F64,
+                actual: PropType::I64,
+            })
+        );
+
+        let tmp_docbrown_path: TempDir = TempDir::new("docbrown").unwrap();
+        g.save_to_file(tmp_docbrown_path.path()).unwrap();
+        let g = Graph::load_from_file(tmp_docbrown_path.path()).unwrap();
+        assert_eq!(
+            g.schema().edge_props(Some("trades")),
+            Some(
+                &[("amount".to_string(), PropType::F64)]
+                    .into_iter()
+                    .collect()
+            )
+        );
+
+        assert_eq!(
+            g.vertex(1).unwrap().property("age".to_string(), false),
+            Some(Prop::I64(40))
+        );
+        assert_eq!(
+            g.add_vertex_properties(1, &vec![("age".to_string(), Prop::F64(1.0))]),
+            Err(GraphError::PropertyTypeMismatch {
+                name: "age".to_string(),
+                expected: PropType::I64,
+                actual: PropType::F64,
+            })
+        );
+
+        let schema = g.schema();
+        let g = g.freeze().unfreeze().unwrap();
+        assert_eq!(g.schema(), schema);
+    }
+
+    #[test]
+    fn values_of_another_type_than_the_stored_ones_are_rejected() {
+        let g = Graph::new(2);
+        g.add_vertex(0, 1, &vec![("age".to_string(), Prop::I64(40))])
+            .unwrap();
+        g.add_edge(0, 1, 2, &vec![("weight".to_string(), Prop::F64(0.5))], None)
+            .unwrap();
+
+        assert_eq!(
+            g.add_vertex(
+                1,
+                1,
+                &vec![("age".to_string(), Prop::Str("41".to_string()))]
+            ),
+            Err(GraphError::FailedToMutateGraph {
+                source: MutateGraphError::PropertyChangedType {
+                    name: "age".to_string(),
+                    first_type: PropType::I64,
+                    second_type: PropType::Str,
+                }
+            })
+        );
+        assert_eq!(
+            g.add_edge(1, 1, 2, &vec![("weight".to_string(), Prop::I64(1))], None),
+            Err(GraphError::FailedToMutateGraph {
+                source: MutateGraphError::PropertyChangedType {
+                    name: "weight".to_string(),
+                    first_type: PropType::F64,
+                    second_type: PropType::I64,
+                }
+            })
+        );
+
+        // neither change was made
+        assert_eq!(g.vertex(1).unwrap().history(), vec![0]);
+        assert_eq!(
+            g.vertex(1).unwrap().property_history("age".to_string()),
+            vec![(0, Prop::I64(40))]
+        );
+        assert_eq!(
+            g.edge(1, 2, None)
+                .unwrap()
+                .property_history("weight".to_string()),
+            vec![(0, Prop::F64(0.5))]
+        );
+        assert_eq!(
+            g.edge(1, 2, None)
+                .unwrap()
+                .explode()
+                .map(|e| e.time())
+                .collect_vec(),
+            vec![Some(0)]
+        );
+    }
+
+    #[test]
+    fn set_schema_checks_the_properties_already_in_the_graph() {
+        let g = Graph::new(2);
+        g.add_vertex(0, 1, &vec![("age".to_string(), Prop::I32(40))])
             .unwrap();
+        g.add_edge(
+            0,
+            1,
+            2,
+            &vec![("amount".to_string(), Prop::F64(1.5))],
+            Some("trades"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            g.set_schema(Schema::new().vertex_prop("age", PropType::I64)),
+            Err(GraphError::PropertyTypeMismatch {
+                name: "age".to_string(),
+                expected: PropType::I64,
+                actual: PropType::I32,
+            })
+        );
+        assert_eq!(
+            g.set_schema(Schema::new().edge_prop(Some("trades"), "amount", PropType::I64)),
+            Err(GraphError::PropertyTypeMismatch {
+                name: "amount".to_string(),
+                expected: PropType::I64,
+                actual: PropType::F64,
+            })
+        );
+        assert!(g.schema().is_empty());
+
+        let schema = Schema::new()
+            .vertex_prop("age", PropType::I32)
+            .edge_prop(Some("trades"), "amount", PropType::F64)
+            .edge_prop(None, "amount", PropType::I64);
+        g.set_schema(schema.clone()).unwrap();
+        assert_eq!(g.schema(), schema);
+    }
+
+    #[test]
+    fn values_added_while_setting_the_schema_have_the_declared_type() {
+        let schema = Schema::new().vertex_prop("age", PropType::I64).edge_prop(
+            None,
+            "amount",
+            PropType::I64,
+        );
+        let writers = 4;
+        for _ in 0..20 {
+            // logging each change before it is made leaves time for the schema to be set
+            let dir = TempDir::new("graph").unwrap();
+            let g = Graph::new(2);
+            g.enable_wal(dir.path()).unwrap();
+            let start = std::sync::Barrier::new(writers + 1);
+            let set = std::thread::scope(|s| {
+                for i in 0..writers as u64 {
+                    let (g, start) = (&g, &start);
+                    s.spawn(move || {
+                        start.wait();
+                        let props = vec![("age".to_string(), Prop::I32(1))];
+                        g.add_vertex(0, i, &props).unwrap();
+                        let props = vec![("amount".to_string(), Prop::I32(1))];
+                        g.add_edge(0, i, i + 1, &props, None).unwrap();
+                    });
+                }
+                start.wait();
+                g.set_schema(schema.clone())
+            });
+            // values added before the schema was set keep their type and make setting it fail,
+            // the ones added after it are widened
+            if set.is_ok() {
+                assert_eq!(g.set_schema(schema.clone()), Ok(()));
+            }
+        }
+    }
+
+    #[test]
+    fn adding_edge_properties_to_an_unknown_layer_fails() {
+        let g = Graph::new(2);
+        g.add_edge(0, 1, 2, &vec![], Some("trades")).unwrap();
+        let props = vec![("amount".to_string(), Prop::I64(1))];
+
+        assert_eq!(
+            g.add_edge_properties(1, 2, &props, Some("loans")),
+            Err(GraphError::LayerNotFound {
+                name: "loans".to_string()
+            })
+        );
+        g.add_edge_properties(1, 2, &props, Some("trades")).unwrap();
     }
 
     #[test]
@@ -1721,8 +3416,8 @@ mod db_tests {
 
         assert_eq!(to_ids(vertex.in_neighbours()), vec![33]);
         assert_eq!(to_ids(vertex_dft.in_neighbours()), vec![33]);
-        assert_eq!(to_ids(vertex1.in_neighbours()), vec![]);
-        assert_eq!(to_ids(vertex2.in_neighbours()), vec![]);
+        assert_eq!(to_ids(vertex1.in_neighbours()), Vec::<u64>::new());
+        assert_eq!(to_ids(vertex2.in_neighbours()), Vec::<u64>::new());
     }
 
     #[test]
@@ -1838,7 +3533,7 @@ mod db_tests {
 
         assert_eq!(times_of_onetwo, [1, 3]);
         assert_eq!(times_of_four, [4]);
-        assert_eq!(windowed_times_of_four, []);
+        assert_eq!(windowed_times_of_four, Vec::<i64>::new());
     }
 
     #[test]
@@ -1868,7 +3563,7 @@ mod db_tests {
         assert_eq!(times_of_onetwo, [1, 3]);
         assert_eq!(times_of_four, [4]);
         assert_eq!(times_of_four_higher, [6, 7, 8, 9, 10]);
-        assert_eq!(times_of_outside_window, []);
+        assert_eq!(times_of_outside_window, Vec::<i64>::new());
         assert_eq!(windowed_times_of_four, [4]);
         assert_eq!(windowed_times_of_four_higher, [8, 9, 10]);
     }
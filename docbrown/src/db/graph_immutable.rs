@@ -14,8 +14,11 @@
 //! let immutable_graph = graph.freeze();
 //! ```
 
+use crate::core::schema::Schema;
 use crate::core::tgraph::TemporalGraph;
 use crate::core::tgraph_shard::ImmutableTGraphShard;
+use crate::core::time::TimeUnit;
+use crate::core::vertex_names::VertexNames;
 use crate::core::Direction;
 use crate::core::{
     tgraph::{EdgeRef, VertexRef},
@@ -47,6 +50,9 @@ pub struct ImmutableGraph {
     pub(crate) nr_shards: usize,
     pub(crate) shards: Vec<ImmutableTGraphShard<TemporalGraph>>,
     pub(crate) layer_ids: Arc<FxHashMap<String, usize>>,
+    pub(crate) schema: Arc<Schema>,
+    pub(crate) vertex_names: Arc<VertexNames>,
+    pub(crate) time_unit: TimeUnit,
 }
 
 /// Failure if there is an issue with unfreezing a frozen graph
@@ -81,6 +87,10 @@ impl ImmutableGraph {
             nr_shards: self.nr_shards,
             shards,
             layer_ids: Arc::new(parking_lot::RwLock::new((*self.layer_ids).clone())),
+            schema: Arc::new(parking_lot::RwLock::new((*self.schema).clone())),
+            vertex_names: Arc::new(parking_lot::RwLock::new((*self.vertex_names).clone())),
+            time_unit: self.time_unit,
+            wal: Default::default(),
         })
     }
 
@@ -1,5 +1,6 @@
 use crate::core::{
     tgraph::{EdgeRef, VertexRef},
+    time::TimeUnit,
     Direction, Prop,
 };
 use crate::db::view_api::internal::GraphViewInternalOps;
@@ -42,6 +43,10 @@ impl<G: GraphViewInternalOps> GraphViewInternalOps for LayeredGraph<G> {
         self.graph.view_end()
     }
 
+    fn view_time_unit(&self) -> TimeUnit {
+        self.graph.view_time_unit()
+    }
+
     fn earliest_time_global(&self) -> Option<i64> {
         self.graph.earliest_time_global()
     }
@@ -124,6 +129,10 @@ impl<G: GraphViewInternalOps> GraphViewInternalOps for LayeredGraph<G> {
             .unwrap_or(0)
     }
 
+    fn vertex_id_by_name(&self, name: &str) -> Option<u64> {
+        self.graph.vertex_id_by_name(name)
+    }
+
     fn vertex_ref(&self, v: u64) -> Option<VertexRef> {
         self.graph.vertex_ref(v)
     }
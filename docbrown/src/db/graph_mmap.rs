@@ -0,0 +1,1620 @@
+//! Defines the `MmapGraph` struct, a read-only docbrown graph stored in flat column files that
+//! are memory-mapped when it is opened.
+//!
+//! Opening an `MmapGraph` maps its files and checks that the offsets, numbers and values in them
+//! fit together, without copying them. The operating system reads the data in as it is needed
+//! and can drop it again, which also makes graphs larger than the available memory queryable.
+//! It implements the same operations as `Graph`, so views and algorithms work on it unchanged.
+//!
+//! Vertices are placed in the same shards as in the `Graph` that was written, use
+//! `Graph::reshard` first to change their number. They are numbered in the order of their shard
+//! and id and edges in the order of their source, destination and layer. Every column is a file
+//! of little-endian `u64` or `i64` values:
+//!
+//! * `vertex_ids` holds the sorted vertex ids.
+//! * `out_offsets` indexes the edges of each source vertex, whose `edge_src`, `edge_dst` and
+//!   `edge_layer` columns form the out adjacency (CSR). `in_offsets` indexes `in_edges`, the edge
+//!   numbers of each destination vertex ordered by source.
+//! * `vertex_time_offsets`/`vertex_times` and `edge_time_offsets`/`edge_times` hold the sorted
+//!   timestamps of every vertex and edge.
+//! * `{vertex,edge}_{tprops,sprops}_*` hold one row per property value with its name, time for
+//!   temporal properties and value, indexed by `*_offsets` per vertex or edge. The values are
+//!   bincode encoded in `values`, indexed by `value_offsets`.
+//!
+//! The `meta` file holds the format version, where each shard starts, the layers, the property
+//! names, the time unit and the table of vertex names of graphs that keep one (see
+//! `docbrown::core::vertex_names`), which is read when the graph is opened.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use docbrown::db::graph::Graph;
+//! use docbrown::db::graph_mmap::MmapGraph;
+//! use docbrown::db::view_api::*;
+//! use tempdir::TempDir;
+//!
+//! let graph = Graph::new(2);
+//! graph.add_edge(1, "Alice", "Bob", &vec![], None).unwrap();
+//!
+//! let dir = TempDir::new("graph").unwrap();
+//! MmapGraph::write(&graph, dir.path()).unwrap();
+//!
+//! let g = MmapGraph::open(dir.path()).unwrap();
+//! assert!(g.has_edge("Alice", "Bob", None));
+//! assert_eq!(g.vertex("Alice").unwrap().out_degree(), 1);
+//! ```
+
+use crate::core::tgraph::{EdgeRef, VertexRef};
+use crate::core::tgraph_shard::errors::GraphError;
+use crate::core::time::TimeUnit;
+use crate::core::vertex_names::VertexNames;
+use crate::core::{utils, Direction, Prop};
+use crate::db::graph::Graph;
+use crate::db::view_api::internal::GraphViewInternalOps;
+use itertools::Itertools;
+use memmap2::Mmap;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::cmp::{max, min};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::marker::PhantomData;
+use std::ops::{Deref, Range};
+use std::path::Path;
+use std::sync::Arc;
+
+/// The version of the format `MmapGraph::write` writes.
+pub const MMAP_FORMAT_VERSION: u32 = 3;
+
+const META_FILE: &str = "meta";
+
+/// A value that can be stored in a column.
+trait Scalar: Copy + 'static {
+    fn to_le_bytes(self) -> [u8; 8];
+}
+
+impl Scalar for u64 {
+    fn to_le_bytes(self) -> [u8; 8] {
+        u64::to_le_bytes(self)
+    }
+}
+
+impl Scalar for i64 {
+    fn to_le_bytes(self) -> [u8; 8] {
+        i64::to_le_bytes(self)
+    }
+}
+
+/// A memory-mapped column file.
+struct Column<T> {
+    // empty files cannot be mapped
+    map: Option<Mmap>,
+    _type: PhantomData<T>,
+}
+
+impl<T: Scalar> Column<T> {
+    fn open(dir: &Path, name: &str) -> Result<Self, GraphError> {
+        let file = File::open(dir.join(name))?;
+        let len = file.metadata()?.len();
+        if len % std::mem::size_of::<T>() as u64 != 0 {
+            return Err(invalid_data(format!("column {name} is truncated")));
+        }
+        // Safety: the files of a graph are only written by `MmapGraph::write`, before it is
+        // opened, and must not be modified while it is open.
+        let map = if len == 0 {
+            None
+        } else {
+            Some(unsafe { Mmap::map(&file)? })
+        };
+        Ok(Self {
+            map,
+            _type: PhantomData,
+        })
+    }
+}
+
+impl<T: Scalar> Deref for Column<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match &self.map {
+            None => &[],
+            // Safety: maps start at a page boundary so they are aligned for `T`, the length was
+            // checked to be a multiple of its size in `open` and any bits are a valid `u64`/`i64`
+            Some(map) => unsafe {
+                std::slice::from_raw_parts(
+                    map.as_ptr() as *const T,
+                    map.len() / std::mem::size_of::<T>(),
+                )
+            },
+        }
+    }
+}
+
+/// The property rows of every vertex or edge.
+struct PropColumns {
+    offsets: Column<u64>,
+    names: Column<u64>,
+    // only for temporal properties
+    times: Option<Column<i64>>,
+    values: Column<u64>,
+}
+
+impl PropColumns {
+    fn open(dir: &Path, prefix: &str, temporal: bool) -> Result<Self, GraphError> {
+        let times = match temporal {
+            true => Some(Column::open(dir, &format!("{prefix}_times"))?),
+            false => None,
+        };
+        Ok(Self {
+            offsets: Column::open(dir, &format!("{prefix}_offsets"))?,
+            names: Column::open(dir, &format!("{prefix}_names"))?,
+            times,
+            values: Column::open(dir, &format!("{prefix}_values"))?,
+        })
+    }
+
+    fn check(
+        &self,
+        prefix: &str,
+        entities: usize,
+        prop_names: usize,
+        values: usize,
+    ) -> Result<(), GraphError> {
+        let rows = self.names.len();
+        check_offsets(&format!("{prefix}_offsets"), &self.offsets, entities, rows)?;
+        check_indices(&format!("{prefix}_names"), &self.names, prop_names)?;
+        check_indices(&format!("{prefix}_values"), &self.values, values)?;
+        let times_match = self
+            .times
+            .as_ref()
+            .map_or(true, |times| times.len() == rows);
+        if self.values.len() != rows || !times_match {
+            return Err(invalid_data(format!(
+                "the {prefix} columns have different lengths"
+            )));
+        }
+        Ok(())
+    }
+
+    fn rows(&self, entity: usize) -> Range<usize> {
+        self.offsets[entity] as usize..self.offsets[entity + 1] as usize
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Meta {
+    // the first vertex of every shard, followed by the number of vertices
+    shard_offsets: Vec<usize>,
+    layer_ids: FxHashMap<String, usize>,
+    prop_names: Vec<String>,
+    // by layer id
+    layer_edges: Vec<usize>,
+    earliest_time: Option<i64>,
+    latest_time: Option<i64>,
+    time_unit: TimeUnit,
+    vertex_names: VertexNames,
+}
+
+struct Columns {
+    vertex_ids: Column<u64>,
+    vertex_time_offsets: Column<u64>,
+    vertex_times: Column<i64>,
+    vertex_tprops: PropColumns,
+    vertex_sprops: PropColumns,
+    out_offsets: Column<u64>,
+    edge_src: Column<u64>,
+    edge_dst: Column<u64>,
+    edge_layer: Column<u64>,
+    in_offsets: Column<u64>,
+    in_edges: Column<u64>,
+    edge_time_offsets: Column<u64>,
+    edge_times: Column<i64>,
+    edge_tprops: PropColumns,
+    edge_sprops: PropColumns,
+    value_offsets: Column<u64>,
+    values: Option<Mmap>,
+}
+
+struct Inner {
+    meta: Meta,
+    prop_ids: FxHashMap<String, u64>,
+    cols: Columns,
+}
+
+/// A read-only docbrown graph backed by memory-mapped column files, see the module
+/// documentation.
+#[derive(Clone)]
+pub struct MmapGraph {
+    inner: Arc<Inner>,
+}
+
+impl MmapGraph {
+    /// Writes `g` to the directory `path` in the format `open` reads.
+    pub fn write<P: AsRef<Path>>(g: &Graph, path: P) -> Result<(), GraphError> {
+        let dir = path.as_ref();
+        std::fs::create_dir_all(dir)?;
+        let mut w = Writer::create(dir)?;
+
+        let key = |g_id: u64| (utils::get_shard_id_from_global_vid(g_id, g.nr_shards), g_id);
+        let mut vertices = g.vertex_refs().collect_vec();
+        vertices.sort_by_key(|v| key(v.g_id));
+        let ids = vertices.iter().map(|v| v.g_id).collect_vec();
+        let index = |g_id: u64| {
+            ids.binary_search_by_key(&key(g_id), |&id| key(id))
+                .expect("the vertices of every edge are in the graph") as u64
+        };
+        let mut shard_offsets = vec![0; g.nr_shards + 1];
+        for &id in &ids {
+            shard_offsets[key(id).0 + 1] += 1;
+        }
+        for i in 1..shard_offsets.len() {
+            shard_offsets[i] += shard_offsets[i - 1];
+        }
+
+        for &v in &vertices {
+            w.vertex_ids.push(v.g_id)?;
+            w.vertex_times.push_all(g.vertex_timestamps(v))?;
+            w.vertex_time_offsets.push(w.vertex_times.len)?;
+            w.push_temporal_props(Rows::Vertex, g.temporal_vertex_props(v))?;
+            let static_props = g
+                .static_vertex_prop_names(v)
+                .into_iter()
+                .filter_map(|name| Some((name.clone(), g.static_vertex_prop(v, name)?)));
+            w.push_static_props(Rows::Vertex, static_props)?;
+        }
+
+        let mut layer_edges = vec![0; g.layer_ids.read().len() + 1];
+        let mut edge_dst = vec![];
+        for (src, &v) in vertices.iter().enumerate() {
+            let mut out_edges = g
+                .vertex_edges_all_layers(v, Direction::OUT)
+                .map(|e| (index(e.dst_g_id), e))
+                .collect_vec();
+            out_edges.sort_by_key(|(dst, e)| (*dst, e.layer_id));
+            for (dst, e) in out_edges {
+                w.edge_src.push(src as u64)?;
+                w.edge_dst.push(dst)?;
+                w.edge_layer.push(e.layer_id as u64)?;
+                w.edge_times.push_all(g.edge_timestamps(e, None))?;
+                w.edge_time_offsets.push(w.edge_times.len)?;
+                w.push_temporal_props(Rows::Edge, g.temporal_edge_props(e))?;
+                let static_props = g
+                    .static_edge_prop_names(e)
+                    .into_iter()
+                    .filter_map(|name| Some((name.clone(), g.static_edge_prop(e, name)?)));
+                w.push_static_props(Rows::Edge, static_props)?;
+                layer_edges[e.layer_id] += 1;
+                edge_dst.push(dst as usize);
+            }
+            w.out_offsets.push(edge_dst.len() as u64)?;
+        }
+
+        // edges are numbered by source, so placing them by destination in that order sorts the
+        // edges of each destination by source
+        let mut in_offsets = vec![0u64; vertices.len() + 1];
+        for &dst in &edge_dst {
+            in_offsets[dst + 1] += 1;
+        }
+        for i in 1..in_offsets.len() {
+            in_offsets[i] += in_offsets[i - 1];
+        }
+        let mut in_edges = vec![0u64; edge_dst.len()];
+        let mut next = in_offsets.clone();
+        for (e, &dst) in edge_dst.iter().enumerate() {
+            in_edges[next[dst] as usize] = e as u64;
+            next[dst] += 1;
+        }
+        w.in_offsets.push_all(in_offsets[1..].iter().copied())?;
+        w.in_edges.push_all(in_edges)?;
+
+        let meta = Meta {
+            shard_offsets,
+            layer_ids: g.layer_ids.read().clone(),
+            prop_names: std::mem::take(&mut w.prop_names),
+            layer_edges,
+            earliest_time: g.earliest_time_global(),
+            latest_time: g.latest_time_global(),
+            time_unit: g.time_unit,
+            vertex_names: g.vertex_names.read().clone(),
+        };
+        w.finish()?;
+        // written last so that only complete graphs can be opened
+        let f = File::create(dir.join(META_FILE))?;
+        bincode::serialize_into(BufWriter::new(f), &(MMAP_FORMAT_VERSION, meta))?;
+        Ok(())
+    }
+
+    /// Opens the graph written to the directory `path` by `write`.
+    ///
+    /// The lengths of the columns, the offsets and vertex and edge numbers in them, the order of
+    /// the vertex ids in each shard and the encoded values are checked, the files must not be
+    /// modified while the graph is open.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::UnsupportedFormatVersion` if the graph was written in a different
+    /// format and `GraphError::Io` if a file is missing or the columns do not fit together.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, GraphError> {
+        let dir = path.as_ref();
+        if cfg!(target_endian = "big") {
+            return Err(GraphError::Io {
+                kind: io::ErrorKind::Unsupported,
+                message: "memory-mapped graphs are only supported on little-endian targets"
+                    .to_string(),
+            });
+        }
+        let bytes = std::fs::read(dir.join(META_FILE))?;
+        let version: u32 = bincode::deserialize(&bytes)?;
+        if version != MMAP_FORMAT_VERSION {
+            return Err(GraphError::UnsupportedFormatVersion {
+                found: version,
+                supported: MMAP_FORMAT_VERSION,
+            });
+        }
+        let (_, meta): (u32, Meta) = bincode::deserialize(&bytes)?;
+
+        let values_file = File::open(dir.join("values"))?;
+        let cols = Columns {
+            vertex_ids: Column::open(dir, "vertex_ids")?,
+            vertex_time_offsets: Column::open(dir, "vertex_time_offsets")?,
+            vertex_times: Column::open(dir, "vertex_times")?,
+            vertex_tprops: PropColumns::open(dir, "vertex_tprops", true)?,
+            vertex_sprops: PropColumns::open(dir, "vertex_sprops", false)?,
+            out_offsets: Column::open(dir, "out_offsets")?,
+            edge_src: Column::open(dir, "edge_src")?,
+            edge_dst: Column::open(dir, "edge_dst")?,
+            edge_layer: Column::open(dir, "edge_layer")?,
+            in_offsets: Column::open(dir, "in_offsets")?,
+            in_edges: Column::open(dir, "in_edges")?,
+            edge_time_offsets: Column::open(dir, "edge_time_offsets")?,
+            edge_times: Column::open(dir, "edge_times")?,
+            edge_tprops: PropColumns::open(dir, "edge_tprops", true)?,
+            edge_sprops: PropColumns::open(dir, "edge_sprops", false)?,
+            value_offsets: Column::open(dir, "value_offsets")?,
+            // Safety: see `Column::open`
+            values: match values_file.metadata()?.len() {
+                0 => None,
+                _ => Some(unsafe { Mmap::map(&values_file)? }),
+            },
+        };
+        Self::check(&cols, &meta)?;
+
+        let prop_ids = (meta.prop_names.iter().cloned()).zip(0..).collect();
+        Ok(Self {
+            inner: Arc::new(Inner {
+                meta,
+                prop_ids,
+                cols,
+            }),
+        })
+    }
+
+    fn check(cols: &Columns, meta: &Meta) -> Result<(), GraphError> {
+        let n = cols.vertex_ids.len();
+        let m = cols.edge_dst.len();
+        check_offsets(
+            "vertex_time_offsets",
+            &cols.vertex_time_offsets,
+            n,
+            cols.vertex_times.len(),
+        )?;
+        check_offsets("out_offsets", &cols.out_offsets, n, m)?;
+        check_offsets("in_offsets", &cols.in_offsets, n, cols.in_edges.len())?;
+        check_offsets(
+            "edge_time_offsets",
+            &cols.edge_time_offsets,
+            m,
+            cols.edge_times.len(),
+        )?;
+        let values_len = cols.values.as_ref().map_or(0, |v| v.len());
+        let nr_values = cols.value_offsets.len().saturating_sub(1);
+        check_offsets("value_offsets", &cols.value_offsets, nr_values, values_len)?;
+        let nr_names = meta.prop_names.len();
+        cols.vertex_tprops
+            .check("vertex_tprops", n, nr_names, nr_values)?;
+        cols.vertex_sprops
+            .check("vertex_sprops", n, nr_names, nr_values)?;
+        cols.edge_tprops
+            .check("edge_tprops", m, nr_names, nr_values)?;
+        cols.edge_sprops
+            .check("edge_sprops", m, nr_names, nr_values)?;
+        let shards = &meta.shard_offsets;
+        if shards.len() < 2
+            || shards.windows(2).any(|w| w[0] > w[1])
+            || shards[shards.len() - 1] != n
+        {
+            return Err(invalid_data(
+                "the shards do not match the vertices".to_string(),
+            ));
+        }
+        if cols.edge_src.len() != m
+            || cols.edge_layer.len() != m
+            || cols.in_edges.len() != m
+            || meta.layer_edges.iter().sum::<usize>() != m
+        {
+            return Err(invalid_data(
+                "the edge columns have different lengths".to_string(),
+            ));
+        }
+        check_indices("edge_src", &cols.edge_src, n)?;
+        check_indices("edge_dst", &cols.edge_dst, n)?;
+        check_indices("in_edges", &cols.in_edges, m)?;
+        // `index` finds vertices by binary search in the range of their shard
+        let nr_shards = shards.len() - 1;
+        for (shard, range) in shards.windows(2).enumerate() {
+            let ids = &cols.vertex_ids[range[0]..range[1]];
+            if ids.windows(2).any(|w| w[0] >= w[1])
+                || ids
+                    .iter()
+                    .any(|&id| utils::get_shard_id_from_global_vid(id, nr_shards) != shard)
+            {
+                return Err(invalid_data(format!(
+                    "the vertex ids of shard {shard} are not sorted"
+                )));
+            }
+        }
+        let src_matches = cols.out_offsets.windows(2).enumerate().all(|(v, w)| {
+            cols.edge_src[w[0] as usize..w[1] as usize]
+                .iter()
+                .all(|&src| src == v as u64)
+        });
+        if !src_matches {
+            return Err(invalid_data(
+                "column edge_src does not match out_offsets".to_string(),
+            ));
+        }
+        if let Some(values) = &cols.values {
+            for (i, w) in cols.value_offsets.windows(2).enumerate() {
+                bincode::deserialize::<Prop>(&values[w[0] as usize..w[1] as usize])
+                    .map_err(|_| invalid_data(format!("value {i} is not a valid property")))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn cols(&self) -> &Columns {
+        &self.inner.cols
+    }
+
+    fn index(&self, g_id: u64) -> Option<usize> {
+        let shard = utils::get_shard_id_from_global_vid(g_id, self.num_shards());
+        let range = self.shard_range(shard);
+        let i = self.cols().vertex_ids[range.clone()]
+            .binary_search(&g_id)
+            .ok()?;
+        Some(range.start + i)
+    }
+
+    fn local(&self, v: VertexRef) -> usize {
+        v.pid
+            .unwrap_or_else(|| self.index(v.g_id).expect("the vertex is in the graph"))
+    }
+
+    fn vertex_at(&self, pid: usize) -> VertexRef {
+        VertexRef::new(self.cols().vertex_ids[pid], Some(pid))
+    }
+
+    fn times(&self, pid: usize) -> &[i64] {
+        let offsets = &self.cols().vertex_time_offsets;
+        &self.cols().vertex_times[offsets[pid] as usize..offsets[pid + 1] as usize]
+    }
+
+    fn edge_times(&self, e: usize) -> &[i64] {
+        let offsets = &self.cols().edge_time_offsets;
+        &self.cols().edge_times[offsets[e] as usize..offsets[e + 1] as usize]
+    }
+
+    fn is_active(&self, pid: usize, w: &Range<i64>) -> bool {
+        !window(self.times(pid), w).is_empty()
+    }
+
+    fn is_edge_active(&self, e: usize, w: &Range<i64>) -> bool {
+        !window(self.edge_times(e), w).is_empty()
+    }
+
+    fn edge(&self, e: usize) -> EdgeRef {
+        let cols = self.cols();
+        let src = cols.edge_src[e] as usize;
+        let dst = cols.edge_dst[e] as usize;
+        EdgeRef {
+            layer_id: cols.edge_layer[e] as usize,
+            edge_id: e,
+            src_g_id: cols.vertex_ids[src],
+            dst_g_id: cols.vertex_ids[dst],
+            src_id: src,
+            dst_id: dst,
+            time: None,
+            is_remote: false,
+        }
+    }
+
+    fn find_edge(&self, src: VertexRef, dst: VertexRef, layer: usize) -> Option<usize> {
+        let cols = self.cols();
+        let src = self.index(src.g_id)?;
+        let dst = self.index(dst.g_id)? as u64;
+        let end = cols.out_offsets[src + 1] as usize;
+        // the edges of `src` are sorted by destination and then layer
+        let start = cols.out_offsets[src] as usize;
+        let first = start + cols.edge_dst[start..end].partition_point(|&d| d < dst);
+        (first..end)
+            .take_while(|&e| cols.edge_dst[e] == dst)
+            .find(|&e| cols.edge_layer[e] == layer as u64)
+    }
+
+    /// The edges of `v` in direction `d`, sorted by neighbour.
+    fn edges(
+        &self,
+        v: usize,
+        d: Direction,
+        layer: Option<usize>,
+        w: Option<Range<i64>>,
+    ) -> Box<dyn Iterator<Item = EdgeRef> + Send> {
+        let cols = self.cols();
+        let range = match d {
+            Direction::OUT => cols.out_offsets[v] as usize..cols.out_offsets[v + 1] as usize,
+            Direction::IN => cols.in_offsets[v] as usize..cols.in_offsets[v + 1] as usize,
+            Direction::BOTH => {
+                let out = self.edges(v, Direction::OUT, layer, w.clone());
+                let into = self.edges(v, Direction::IN, layer, w);
+                return Box::new(
+                    out.merge_by(into, move |a, b| neighbour(a, v) <= neighbour(b, v)),
+                );
+            }
+        };
+        let g = self.clone();
+        Box::new(range.filter_map(move |i| {
+            let e = match d {
+                Direction::IN => g.cols().in_edges[i] as usize,
+                _ => i,
+            };
+            let in_layer = layer.map_or(true, |l| g.cols().edge_layer[e] as usize == l);
+            let in_window = w.as_ref().map_or(true, |w| g.is_edge_active(e, w));
+            (in_layer && in_window).then(|| g.edge(e))
+        }))
+    }
+
+    fn props(
+        &self,
+        columns: &PropColumns,
+        entity: usize,
+        name: Option<&str>,
+        w: Option<&Range<i64>>,
+    ) -> Vec<(String, Option<i64>, Prop)> {
+        let name_id = match name {
+            Some(name) => match self.inner.prop_ids.get(name) {
+                Some(id) => Some(*id),
+                None => return vec![],
+            },
+            None => None,
+        };
+        columns
+            .rows(entity)
+            .filter(|&row| name_id.map_or(true, |id| columns.names[row] == id))
+            .map(|row| (row, columns.times.as_ref().map(|times| times[row])))
+            .filter(|(_, t)| match (w, t) {
+                (Some(w), Some(t)) => w.contains(t),
+                _ => true,
+            })
+            .map(|(row, t)| {
+                let name = &self.inner.meta.prop_names[columns.names[row] as usize];
+                (name.clone(), t, self.value(columns.values[row] as usize))
+            })
+            .collect()
+    }
+
+    fn value(&self, i: usize) -> Prop {
+        let offsets = &self.cols().value_offsets;
+        let bytes = &self.cols().values.as_ref().expect("values were written")
+            [offsets[i] as usize..offsets[i + 1] as usize];
+        bincode::deserialize(bytes).expect("values are checked when the graph is opened")
+    }
+
+    fn prop_history(
+        &self,
+        columns: &PropColumns,
+        entity: usize,
+        name: &str,
+        w: Option<&Range<i64>>,
+    ) -> Vec<(i64, Prop)> {
+        self.props(columns, entity, Some(name), w)
+            .into_iter()
+            .map(|(_, t, prop)| (t.expect("temporal props have times"), prop))
+            .collect()
+    }
+
+    fn prop_histories(
+        &self,
+        columns: &PropColumns,
+        entity: usize,
+        w: Option<&Range<i64>>,
+    ) -> HashMap<String, Vec<(i64, Prop)>> {
+        let mut histories: HashMap<String, Vec<(i64, Prop)>> = HashMap::new();
+        for (name, t, prop) in self.props(columns, entity, None, w) {
+            histories
+                .entry(name)
+                .or_default()
+                .push((t.expect("temporal props have times"), prop));
+        }
+        histories
+    }
+
+    fn prop_names(&self, columns: &PropColumns, entity: usize) -> Vec<String> {
+        columns
+            .rows(entity)
+            .map(|row| columns.names[row])
+            .dedup()
+            .map(|id| self.inner.meta.prop_names[id as usize].clone())
+            .collect()
+    }
+
+    fn shard_range(&self, shard: usize) -> Range<usize> {
+        let offsets = &self.inner.meta.shard_offsets;
+        offsets[shard]..offsets[shard + 1]
+    }
+
+    fn vertex_range(
+        &self,
+        range: Range<usize>,
+        w: Option<Range<i64>>,
+    ) -> Box<dyn Iterator<Item = VertexRef> + Send> {
+        let g = self.clone();
+        Box::new(
+            range
+                .filter(move |&pid| w.as_ref().map_or(true, |w| g.is_active(pid, w)))
+                .map({
+                    let g = self.clone();
+                    move |pid| g.vertex_at(pid)
+                }),
+        )
+    }
+}
+
+impl GraphViewInternalOps for MmapGraph {
+    fn get_layer(&self, key: Option<&str>) -> Option<usize> {
+        match key {
+            None => Some(0),
+            Some(key) => self.inner.meta.layer_ids.get(key).copied(),
+        }
+    }
+
+    fn view_start(&self) -> Option<i64> {
+        self.earliest_time_global()
+    }
+
+    fn view_end(&self) -> Option<i64> {
+        self.latest_time_global().map(|t| t + 1) // so it is exclusive
+    }
+
+    fn view_time_unit(&self) -> TimeUnit {
+        self.inner.meta.time_unit
+    }
+
+    fn earliest_time_global(&self) -> Option<i64> {
+        self.inner.meta.earliest_time
+    }
+
+    fn earliest_time_window(&self, t_start: i64, t_end: i64) -> Option<i64> {
+        let earliest = self.earliest_time_global()?;
+        if earliest > t_end {
+            None
+        } else {
+            Some(max(earliest, t_start))
+        }
+    }
+
+    fn latest_time_global(&self) -> Option<i64> {
+        self.inner.meta.latest_time
+    }
+
+    fn latest_time_window(&self, t_start: i64, t_end: i64) -> Option<i64> {
+        let latest = self.latest_time_global()?;
+        if latest < t_start {
+            None
+        } else {
+            Some(min(latest, t_end))
+        }
+    }
+
+    fn vertices_len(&self) -> usize {
+        self.cols().vertex_ids.len()
+    }
+
+    fn vertices_len_window(&self, t_start: i64, t_end: i64) -> usize {
+        (0..self.vertices_len())
+            .filter(|&pid| self.is_active(pid, &(t_start..t_end)))
+            .count()
+    }
+
+    fn edges_len(&self, layer: Option<usize>) -> usize {
+        match layer {
+            None => self.cols().edge_dst.len(),
+            Some(layer) => self.inner.meta.layer_edges.get(layer).copied().unwrap_or(0),
+        }
+    }
+
+    fn edges_len_window(&self, t_start: i64, t_end: i64, layer: Option<usize>) -> usize {
+        let cols = self.cols();
+        (0..cols.edge_dst.len())
+            .filter(|&e| layer.map_or(true, |l| cols.edge_layer[e] as usize == l))
+            .filter(|&e| self.is_edge_active(e, &(t_start..t_end)))
+            .count()
+    }
+
+    fn has_edge_ref(&self, src: VertexRef, dst: VertexRef, layer: usize) -> bool {
+        self.find_edge(src, dst, layer).is_some()
+    }
+
+    fn has_edge_ref_window(
+        &self,
+        src: VertexRef,
+        dst: VertexRef,
+        t_start: i64,
+        t_end: i64,
+        layer: usize,
+    ) -> bool {
+        self.edge_ref_window(src, dst, t_start, t_end, layer)
+            .is_some()
+    }
+
+    fn has_vertex_ref(&self, v: VertexRef) -> bool {
+        self.index(v.g_id).is_some()
+    }
+
+    fn has_vertex_ref_window(&self, v: VertexRef, t_start: i64, t_end: i64) -> bool {
+        self.vertex_ref_window(v.g_id, t_start, t_end).is_some()
+    }
+
+    fn degree(&self, v: VertexRef, d: Direction, layer: Option<usize>) -> usize {
+        let v = self.local(v);
+        self.edges(v, d, layer, None)
+            .dedup_by(|a, b| neighbour(a, v) == neighbour(b, v))
+            .count()
+    }
+
+    fn degree_window(
+        &self,
+        v: VertexRef,
+        t_start: i64,
+        t_end: i64,
+        d: Direction,
+        layer: Option<usize>,
+    ) -> usize {
+        let v = self.local(v);
+        self.edges(v, d, layer, Some(t_start..t_end))
+            .dedup_by(|a, b| neighbour(a, v) == neighbour(b, v))
+            .count()
+    }
+
+    fn vertex_id_by_name(&self, name: &str) -> Option<u64> {
+        self.inner.meta.vertex_names.get(name)
+    }
+
+    fn vertex_ref(&self, v: u64) -> Option<VertexRef> {
+        self.index(v).map(|pid| self.vertex_at(pid))
+    }
+
+    fn vertex_ref_window(&self, v: u64, t_start: i64, t_end: i64) -> Option<VertexRef> {
+        let pid = self.index(v)?;
+        self.is_active(pid, &(t_start..t_end))
+            .then(|| self.vertex_at(pid))
+    }
+
+    fn vertex_earliest_time(&self, v: VertexRef) -> Option<i64> {
+        self.times(self.local(v)).first().copied()
+    }
+
+    fn vertex_earliest_time_window(&self, v: VertexRef, t_start: i64, t_end: i64) -> Option<i64> {
+        window(self.times(self.local(v)), &(t_start..t_end))
+            .first()
+            .copied()
+    }
+
+    fn vertex_latest_time(&self, v: VertexRef) -> Option<i64> {
+        self.times(self.local(v)).last().copied()
+    }
+
+    fn vertex_latest_time_window(&self, v: VertexRef, t_start: i64, t_end: i64) -> Option<i64> {
+        window(self.times(self.local(v)), &(t_start..t_end))
+            .last()
+            .copied()
+    }
+
+    fn vertex_ids(&self) -> Box<dyn Iterator<Item = u64> + Send> {
+        Box::new(self.vertex_refs().map(|v| v.g_id))
+    }
+
+    fn vertex_ids_window(&self, t_start: i64, t_end: i64) -> Box<dyn Iterator<Item = u64> + Send> {
+        Box::new(self.vertex_refs_window(t_start, t_end).map(|v| v.g_id))
+    }
+
+    fn vertex_refs(&self) -> Box<dyn Iterator<Item = VertexRef> + Send> {
+        self.vertex_range(0..self.vertices_len(), None)
+    }
+
+    fn vertex_refs_window(
+        &self,
+        t_start: i64,
+        t_end: i64,
+    ) -> Box<dyn Iterator<Item = VertexRef> + Send> {
+        self.vertex_range(0..self.vertices_len(), Some(t_start..t_end))
+    }
+
+    fn vertex_refs_shard(&self, shard: usize) -> Box<dyn Iterator<Item = VertexRef> + Send> {
+        self.vertex_range(self.shard_range(shard), None)
+    }
+
+    fn vertex_refs_window_shard(
+        &self,
+        shard: usize,
+        t_start: i64,
+        t_end: i64,
+    ) -> Box<dyn Iterator<Item = VertexRef> + Send> {
+        self.vertex_range(self.shard_range(shard), Some(t_start..t_end))
+    }
+
+    fn edge_ref(&self, src: VertexRef, dst: VertexRef, layer: usize) -> Option<EdgeRef> {
+        self.find_edge(src, dst, layer).map(|e| self.edge(e))
+    }
+
+    fn edge_ref_window(
+        &self,
+        src: VertexRef,
+        dst: VertexRef,
+        t_start: i64,
+        t_end: i64,
+        layer: usize,
+    ) -> Option<EdgeRef> {
+        self.find_edge(src, dst, layer)
+            .filter(|&e| self.is_edge_active(e, &(t_start..t_end)))
+            .map(|e| self.edge(e))
+    }
+
+    fn edge_refs(&self, layer: Option<usize>) -> Box<dyn Iterator<Item = EdgeRef> + Send> {
+        let g = self.clone();
+        Box::new(
+            (0..self.vertices_len()).flat_map(move |v| g.edges(v, Direction::OUT, layer, None)),
+        )
+    }
+
+    fn edge_refs_window(
+        &self,
+        t_start: i64,
+        t_end: i64,
+        layer: Option<usize>,
+    ) -> Box<dyn Iterator<Item = EdgeRef> + Send> {
+        let g = self.clone();
+        Box::new(
+            (0..self.vertices_len())
+                .flat_map(move |v| g.edges(v, Direction::OUT, layer, Some(t_start..t_end))),
+        )
+    }
+
+    fn vertex_edges_all_layers(
+        &self,
+        v: VertexRef,
+        d: Direction,
+    ) -> Box<dyn Iterator<Item = EdgeRef> + Send> {
+        self.edges(self.local(v), d, None, None)
+    }
+
+    fn vertex_edges_single_layer(
+        &self,
+        v: VertexRef,
+        d: Direction,
+        layer: usize,
+    ) -> Box<dyn Iterator<Item = EdgeRef> + Send> {
+        self.edges(self.local(v), d, Some(layer), None)
+    }
+
+    fn vertex_edges_t(
+        &self,
+        v: VertexRef,
+        d: Direction,
+        layer: Option<usize>,
+    ) -> Box<dyn Iterator<Item = EdgeRef> + Send> {
+        self.vertex_edges_window_t(v, i64::MIN, i64::MAX, d, layer)
+    }
+
+    fn vertex_edges_window(
+        &self,
+        v: VertexRef,
+        t_start: i64,
+        t_end: i64,
+        d: Direction,
+        layer: Option<usize>,
+    ) -> Box<dyn Iterator<Item = EdgeRef> + Send> {
+        self.edges(self.local(v), d, layer, Some(t_start..t_end))
+    }
+
+    fn vertex_edges_window_t(
+        &self,
+        v: VertexRef,
+        t_start: i64,
+        t_end: i64,
+        d: Direction,
+        layer: Option<usize>,
+    ) -> Box<dyn Iterator<Item = EdgeRef> + Send> {
+        let directions = match d {
+            Direction::BOTH => vec![Direction::IN, Direction::OUT],
+            d => vec![d],
+        };
+        let v = self.local(v);
+        let g = self.clone();
+        Box::new(directions.into_iter().flat_map(move |d| {
+            let g = g.clone();
+            g.edges(v, d, layer, Some(t_start..t_end))
+                .flat_map(move |e| {
+                    window(g.edge_times(e.edge_id), &(t_start..t_end))
+                        .iter()
+                        .map(|t| EdgeRef {
+                            time: Some(*t),
+                            ..e
+                        })
+                        .collect_vec()
+                })
+        }))
+    }
+
+    fn neighbours(
+        &self,
+        v: VertexRef,
+        d: Direction,
+        layer: Option<usize>,
+    ) -> Box<dyn Iterator<Item = VertexRef> + Send> {
+        let v = self.local(v);
+        let g = self.clone();
+        Box::new(
+            self.edges(v, d, layer, None)
+                .dedup_by(move |a, b| neighbour(a, v) == neighbour(b, v))
+                .map(move |e| g.vertex_at(neighbour(&e, v))),
+        )
+    }
+
+    fn neighbours_window(
+        &self,
+        v: VertexRef,
+        t_start: i64,
+        t_end: i64,
+        d: Direction,
+        layer: Option<usize>,
+    ) -> Box<dyn Iterator<Item = VertexRef> + Send> {
+        let v = self.local(v);
+        let g = self.clone();
+        let edges = self.edges(v, d, layer, Some(t_start..t_end));
+        // like `Graph`, only neighbours in both directions are deduplicated in a window
+        let edges: Box<dyn Iterator<Item = EdgeRef> + Send> = match d {
+            Direction::BOTH => {
+                Box::new(edges.dedup_by(move |a, b| neighbour(a, v) == neighbour(b, v)))
+            }
+            _ => edges,
+        };
+        Box::new(edges.map(move |e| g.vertex_at(neighbour(&e, v))))
+    }
+
+    fn neighbours_ids(
+        &self,
+        v: VertexRef,
+        d: Direction,
+        layer: Option<usize>,
+    ) -> Box<dyn Iterator<Item = u64> + Send> {
+        Box::new(self.neighbours(v, d, layer).map(|v| v.g_id))
+    }
+
+    fn neighbours_ids_window(
+        &self,
+        v: VertexRef,
+        t_start: i64,
+        t_end: i64,
+        d: Direction,
+        layer: Option<usize>,
+    ) -> Box<dyn Iterator<Item = u64> + Send> {
+        Box::new(
+            self.neighbours_window(v, t_start, t_end, d, layer)
+                .map(|v| v.g_id),
+        )
+    }
+
+    fn static_vertex_prop(&self, v: VertexRef, name: String) -> Option<Prop> {
+        let columns = &self.cols().vertex_sprops;
+        let (_, _, prop) = self
+            .props(columns, self.local(v), Some(&name), None)
+            .pop()?;
+        Some(prop)
+    }
+
+    fn static_vertex_prop_names(&self, v: VertexRef) -> Vec<String> {
+        self.prop_names(&self.cols().vertex_sprops, self.local(v))
+    }
+
+    fn temporal_vertex_prop_names(&self, v: VertexRef) -> Vec<String> {
+        self.prop_names(&self.cols().vertex_tprops, self.local(v))
+    }
+
+    fn temporal_vertex_prop_vec(&self, v: VertexRef, name: String) -> Vec<(i64, Prop)> {
+        self.prop_history(&self.cols().vertex_tprops, self.local(v), &name, None)
+    }
+
+    fn vertex_timestamps(&self, v: VertexRef) -> Vec<i64> {
+        self.times(self.local(v)).to_vec()
+    }
+
+    fn vertex_timestamps_window(&self, v: VertexRef, t_start: i64, t_end: i64) -> Vec<i64> {
+        window(self.times(self.local(v)), &(t_start..t_end)).to_vec()
+    }
+
+    fn temporal_vertex_prop_vec_window(
+        &self,
+        v: VertexRef,
+        name: String,
+        t_start: i64,
+        t_end: i64,
+    ) -> Vec<(i64, Prop)> {
+        let columns = &self.cols().vertex_tprops;
+        self.prop_history(columns, self.local(v), &name, Some(&(t_start..t_end)))
+    }
+
+    fn temporal_vertex_props(&self, v: VertexRef) -> HashMap<String, Vec<(i64, Prop)>> {
+        self.prop_histories(&self.cols().vertex_tprops, self.local(v), None)
+    }
+
+    fn temporal_vertex_props_window(
+        &self,
+        v: VertexRef,
+        t_start: i64,
+        t_end: i64,
+    ) -> HashMap<String, Vec<(i64, Prop)>> {
+        let columns = &self.cols().vertex_tprops;
+        self.prop_histories(columns, self.local(v), Some(&(t_start..t_end)))
+    }
+
+    fn static_edge_prop(&self, e: EdgeRef, name: String) -> Option<Prop> {
+        let columns = &self.cols().edge_sprops;
+        let (_, _, prop) = self.props(columns, e.edge_id, Some(&name), None).pop()?;
+        Some(prop)
+    }
+
+    fn static_edge_prop_names(&self, e: EdgeRef) -> Vec<String> {
+        self.prop_names(&self.cols().edge_sprops, e.edge_id)
+    }
+
+    fn temporal_edge_prop_names(&self, e: EdgeRef) -> Vec<String> {
+        self.prop_names(&self.cols().edge_tprops, e.edge_id)
+    }
+
+    fn temporal_edge_props_vec(&self, e: EdgeRef, name: String) -> Vec<(i64, Prop)> {
+        self.prop_history(&self.cols().edge_tprops, e.edge_id, &name, None)
+    }
+
+    fn temporal_edge_props_vec_window(
+        &self,
+        e: EdgeRef,
+        name: String,
+        t_start: i64,
+        t_end: i64,
+    ) -> Vec<(i64, Prop)> {
+        let columns = &self.cols().edge_tprops;
+        self.prop_history(columns, e.edge_id, &name, Some(&(t_start..t_end)))
+    }
+
+    fn edge_timestamps(&self, e: EdgeRef, window: Option<Range<i64>>) -> Vec<i64> {
+        let times = self.edge_times(e.edge_id);
+        match window {
+            None => times.to_vec(),
+            Some(w) => self::window(times, &w).to_vec(),
+        }
+    }
+
+    fn temporal_edge_props(&self, e: EdgeRef) -> HashMap<String, Vec<(i64, Prop)>> {
+        self.prop_histories(&self.cols().edge_tprops, e.edge_id, None)
+    }
+
+    fn temporal_edge_props_window(
+        &self,
+        e: EdgeRef,
+        t_start: i64,
+        t_end: i64,
+    ) -> HashMap<String, Vec<(i64, Prop)>> {
+        let columns = &self.cols().edge_tprops;
+        self.prop_histories(columns, e.edge_id, Some(&(t_start..t_end)))
+    }
+
+    fn num_shards(&self) -> usize {
+        self.inner.meta.shard_offsets.len() - 1
+    }
+
+    fn vertices_shard(&self, shard_id: usize) -> Box<dyn Iterator<Item = VertexRef> + Send> {
+        self.vertex_refs_shard(shard_id)
+    }
+
+    fn vertices_shard_window(
+        &self,
+        shard_id: usize,
+        t_start: i64,
+        t_end: i64,
+    ) -> Box<dyn Iterator<Item = VertexRef> + Send> {
+        self.vertex_refs_window_shard(shard_id, t_start, t_end)
+    }
+}
+
+/// The part of the sorted `times` that falls in `w`.
+fn window<'a>(times: &'a [i64], w: &Range<i64>) -> &'a [i64] {
+    let start = times.partition_point(|t| *t < w.start);
+    let end = times.partition_point(|t| *t < w.end);
+    &times[start..max(start, end)]
+}
+
+/// The other vertex of an edge of `v`, which is `v` itself for self loops.
+fn neighbour(e: &EdgeRef, v: usize) -> usize {
+    if e.src_id == v {
+        e.dst_id
+    } else {
+        e.src_id
+    }
+}
+
+fn invalid_data(message: String) -> GraphError {
+    GraphError::Io {
+        kind: io::ErrorKind::InvalidData,
+        message,
+    }
+}
+
+/// Checks that `offsets` indexes `data_len` values for `entities` vertices or edges.
+fn check_offsets(
+    name: &str,
+    offsets: &[u64],
+    entities: usize,
+    data_len: usize,
+) -> Result<(), GraphError> {
+    if offsets.len() != entities + 1
+        || offsets[entities] as usize != data_len
+        || offsets.windows(2).any(|w| w[0] > w[1])
+    {
+        return Err(invalid_data(format!(
+            "column {name} does not match the data it indexes"
+        )));
+    }
+    Ok(())
+}
+
+/// Checks that the vertex, edge, name or value numbers in `column` are below `len`.
+fn check_indices(name: &str, column: &[u64], len: usize) -> Result<(), GraphError> {
+    if column.iter().any(|&i| i >= len as u64) {
+        return Err(invalid_data(format!("column {name} is out of range")));
+    }
+    Ok(())
+}
+
+struct ColumnWriter {
+    file: BufWriter<File>,
+    len: u64,
+}
+
+impl ColumnWriter {
+    fn create(dir: &Path, name: &str) -> io::Result<Self> {
+        Ok(Self {
+            file: BufWriter::new(File::create(dir.join(name))?),
+            len: 0,
+        })
+    }
+
+    /// Starts an offsets column with its leading zero.
+    fn create_offsets(dir: &Path, name: &str) -> io::Result<Self> {
+        let mut column = Self::create(dir, name)?;
+        column.push(0u64)?;
+        Ok(column)
+    }
+
+    fn push<T: Scalar>(&mut self, value: T) -> io::Result<()> {
+        self.len += 1;
+        self.file.write_all(&value.to_le_bytes())
+    }
+
+    fn push_all<T: Scalar, I: IntoIterator<Item = T>>(&mut self, values: I) -> io::Result<()> {
+        values.into_iter().try_for_each(|value| self.push(value))
+    }
+}
+
+struct PropWriter {
+    offsets: ColumnWriter,
+    names: ColumnWriter,
+    times: Option<ColumnWriter>,
+    values: ColumnWriter,
+}
+
+impl PropWriter {
+    fn create(dir: &Path, prefix: &str, temporal: bool) -> io::Result<Self> {
+        let times = match temporal {
+            true => Some(ColumnWriter::create(dir, &format!("{prefix}_times"))?),
+            false => None,
+        };
+        Ok(Self {
+            offsets: ColumnWriter::create_offsets(dir, &format!("{prefix}_offsets"))?,
+            names: ColumnWriter::create(dir, &format!("{prefix}_names"))?,
+            times,
+            values: ColumnWriter::create(dir, &format!("{prefix}_values"))?,
+        })
+    }
+
+    fn columns(self) -> impl Iterator<Item = ColumnWriter> {
+        [self.offsets, self.names, self.values]
+            .into_iter()
+            .chain(self.times)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Rows {
+    Vertex,
+    Edge,
+}
+
+struct Writer {
+    vertex_ids: ColumnWriter,
+    vertex_time_offsets: ColumnWriter,
+    vertex_times: ColumnWriter,
+    vertex_tprops: PropWriter,
+    vertex_sprops: PropWriter,
+    out_offsets: ColumnWriter,
+    edge_src: ColumnWriter,
+    edge_dst: ColumnWriter,
+    edge_layer: ColumnWriter,
+    in_offsets: ColumnWriter,
+    in_edges: ColumnWriter,
+    edge_time_offsets: ColumnWriter,
+    edge_times: ColumnWriter,
+    edge_tprops: PropWriter,
+    edge_sprops: PropWriter,
+    value_offsets: ColumnWriter,
+    values: BufWriter<File>,
+    values_len: u64,
+    prop_names: Vec<String>,
+    prop_ids: FxHashMap<String, u64>,
+}
+
+impl Writer {
+    fn create(dir: &Path) -> io::Result<Self> {
+        Ok(Self {
+            vertex_ids: ColumnWriter::create(dir, "vertex_ids")?,
+            vertex_time_offsets: ColumnWriter::create_offsets(dir, "vertex_time_offsets")?,
+            vertex_times: ColumnWriter::create(dir, "vertex_times")?,
+            vertex_tprops: PropWriter::create(dir, "vertex_tprops", true)?,
+            vertex_sprops: PropWriter::create(dir, "vertex_sprops", false)?,
+            out_offsets: ColumnWriter::create_offsets(dir, "out_offsets")?,
+            edge_src: ColumnWriter::create(dir, "edge_src")?,
+            edge_dst: ColumnWriter::create(dir, "edge_dst")?,
+            edge_layer: ColumnWriter::create(dir, "edge_layer")?,
+            in_offsets: ColumnWriter::create_offsets(dir, "in_offsets")?,
+            in_edges: ColumnWriter::create(dir, "in_edges")?,
+            edge_time_offsets: ColumnWriter::create_offsets(dir, "edge_time_offsets")?,
+            edge_times: ColumnWriter::create(dir, "edge_times")?,
+            edge_tprops: PropWriter::create(dir, "edge_tprops", true)?,
+            edge_sprops: PropWriter::create(dir, "edge_sprops", false)?,
+            value_offsets: ColumnWriter::create_offsets(dir, "value_offsets")?,
+            values: BufWriter::new(File::create(dir.join("values"))?),
+            values_len: 0,
+            prop_names: vec![],
+            prop_ids: Default::default(),
+        })
+    }
+
+    fn prop_id(&mut self, name: String) -> u64 {
+        let next = self.prop_names.len() as u64;
+        *self.prop_ids.entry(name).or_insert_with_key(|name| {
+            self.prop_names.push(name.clone());
+            next
+        })
+    }
+
+    fn push_value(&mut self, prop: &Prop) -> Result<u64, GraphError> {
+        let bytes = bincode::serialize(prop)?;
+        self.values.write_all(&bytes)?;
+        self.values_len += bytes.len() as u64;
+        self.value_offsets.push(self.values_len)?;
+        Ok(self.value_offsets.len - 2)
+    }
+
+    fn push_temporal_props(
+        &mut self,
+        rows: Rows,
+        props: HashMap<String, Vec<(i64, Prop)>>,
+    ) -> Result<(), GraphError> {
+        for (name, history) in props.into_iter().sorted_by(|(a, _), (b, _)| a.cmp(b)) {
+            let name = self.prop_id(name);
+            for (t, prop) in history {
+                let value = self.push_value(&prop)?;
+                let columns = self.tprops(rows);
+                columns.names.push(name)?;
+                columns.times.as_mut().expect("temporal").push(t)?;
+                columns.values.push(value)?;
+            }
+        }
+        let columns = self.tprops(rows);
+        columns.offsets.push(columns.names.len)?;
+        Ok(())
+    }
+
+    fn push_static_props<I: Iterator<Item = (String, Prop)>>(
+        &mut self,
+        rows: Rows,
+        props: I,
+    ) -> Result<(), GraphError> {
+        for (name, prop) in props.sorted_by(|(a, _), (b, _)| a.cmp(b)) {
+            let name = self.prop_id(name);
+            let value = self.push_value(&prop)?;
+            let columns = self.sprops(rows);
+            columns.names.push(name)?;
+            columns.values.push(value)?;
+        }
+        let columns = self.sprops(rows);
+        columns.offsets.push(columns.names.len)?;
+        Ok(())
+    }
+
+    fn tprops(&mut self, rows: Rows) -> &mut PropWriter {
+        match rows {
+            Rows::Vertex => &mut self.vertex_tprops,
+            Rows::Edge => &mut self.edge_tprops,
+        }
+    }
+
+    fn sprops(&mut self, rows: Rows) -> &mut PropWriter {
+        match rows {
+            Rows::Vertex => &mut self.vertex_sprops,
+            Rows::Edge => &mut self.edge_sprops,
+        }
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        self.values.flush()?;
+        let columns = [
+            self.vertex_ids,
+            self.vertex_time_offsets,
+            self.vertex_times,
+            self.out_offsets,
+            self.edge_src,
+            self.edge_dst,
+            self.edge_layer,
+            self.in_offsets,
+            self.in_edges,
+            self.edge_time_offsets,
+            self.edge_times,
+            self.value_offsets,
+        ];
+        let props = [
+            self.vertex_tprops,
+            self.vertex_sprops,
+            self.edge_tprops,
+            self.edge_sprops,
+        ];
+        for mut column in columns
+            .into_iter()
+            .chain(props.into_iter().flat_map(|p| p.columns()))
+        {
+            column.file.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod graph_mmap_tests {
+    use super::*;
+    use crate::algorithms::connected_components::weakly_connected_components;
+    use crate::core::vertex_names::VertexIds;
+    use crate::db::graph::db_tests::contents;
+    use crate::db::view_api::*;
+    use tempdir::TempDir;
+
+    fn graph() -> Graph {
+        let g = Graph::new(3);
+        let names = ["Alice", "Bob", "Carol", "Dave", "Eve"];
+        for (t, name) in names.iter().enumerate() {
+            g.add_vertex(
+                t as i64,
+                *name,
+                &vec![("age".to_string(), Prop::I64(t as i64))],
+            )
+            .unwrap();
+            g.add_vertex_properties(*name, &vec![("team".to_string(), Prop::U32(t as u32 % 2))])
+                .unwrap();
+        }
+        for (t, (src, dst)) in names.iter().tuple_combinations().enumerate() {
+            let props = vec![("weight".to_string(), Prop::F64(t as f64))];
+            g.add_edge(t as i64, *src, *dst, &props, None).unwrap();
+            g.add_edge(t as i64 + 10, *src, *dst, &vec![], None)
+                .unwrap();
+            g.add_edge(t as i64, *dst, *src, &vec![], Some("follows"))
+                .unwrap();
+            g.add_edge_properties(*dst, *src, &props, Some("follows"))
+                .unwrap();
+        }
+        g.add_vertex(20, 42, &vec![]).unwrap();
+        g.add_edge(21, 42, 42, &vec![], Some("empty")).unwrap();
+        g.add_edge(19, 43, 44, &vec![], None).unwrap();
+        g.add_vertex(30, "Eve", &vec![]).unwrap();
+        g
+    }
+
+    #[test]
+    fn mmap_graphs_answer_like_the_graph_they_were_written_from() {
+        let g = graph();
+        let dir = TempDir::new("docbrown").unwrap();
+        MmapGraph::write(&g, dir.path()).unwrap();
+        let mg = MmapGraph::open(dir.path()).unwrap();
+
+        assert_eq!(mg.num_vertices(), g.num_vertices());
+        assert_eq!(mg.num_edges(), g.num_edges());
+        assert_eq!(mg.earliest_time(), g.earliest_time());
+        assert_eq!(mg.latest_time(), g.latest_time());
+        assert_eq!(contents(&mg), contents(&g));
+        for (start, end) in [(0, 5), (3, 12), (10, 30), (-5, 0)] {
+            assert_eq!(
+                contents(&mg.window(start, end)),
+                contents(&g.window(start, end))
+            );
+        }
+        for layer in ["follows", "empty"] {
+            assert_eq!(
+                contents(&mg.layer(layer).unwrap()),
+                contents(&g.layer(layer).unwrap())
+            );
+        }
+        assert!(mg.layer("missing").is_none());
+        assert!(mg.vertex("Zoe").is_none());
+        assert!(!mg.has_edge("Bob", "Alice", None));
+        assert!(mg.has_edge("Bob", "Alice", Some("follows")));
+
+        let components = weakly_connected_components(&mg);
+        assert_eq!(
+            components.assignments().get_all(),
+            weakly_connected_components(&g).assignments().get_all()
+        );
+    }
+
+    #[test]
+    fn mmap_graphs_keep_the_shards_of_their_graph() {
+        let g = graph();
+        let dir = TempDir::new("docbrown").unwrap();
+        MmapGraph::write(&g, dir.path()).unwrap();
+        let mg = MmapGraph::open(dir.path()).unwrap();
+
+        assert_eq!(mg.num_shards(), g.nr_shards);
+        for shard in 0..g.nr_shards {
+            let ids = mg.vertex_refs_shard(shard).map(|v| v.g_id);
+            assert_eq!(
+                ids.sorted().collect_vec(),
+                g.vertex_refs_shard(shard)
+                    .map(|v| v.g_id)
+                    .sorted()
+                    .collect_vec()
+            );
+        }
+    }
+
+    #[test]
+    fn opening_reports_missing_and_unsupported_files() {
+        let dir = TempDir::new("docbrown").unwrap();
+        assert!(matches!(
+            MmapGraph::open(dir.path()),
+            Err(GraphError::Io {
+                kind: io::ErrorKind::NotFound,
+                ..
+            })
+        ));
+
+        MmapGraph::write(&graph(), dir.path()).unwrap();
+        let meta = dir.path().join(META_FILE);
+        let (_, meta_data): (u32, Meta) =
+            bincode::deserialize(&std::fs::read(&meta).unwrap()).unwrap();
+        std::fs::write(
+            &meta,
+            bincode::serialize(&(MMAP_FORMAT_VERSION + 1, meta_data)).unwrap(),
+        )
+        .unwrap();
+        assert!(matches!(
+            MmapGraph::open(dir.path()),
+            Err(GraphError::UnsupportedFormatVersion {
+                found,
+                supported: MMAP_FORMAT_VERSION,
+            }) if found == MMAP_FORMAT_VERSION + 1
+        ));
+
+        MmapGraph::write(&graph(), dir.path()).unwrap();
+        std::fs::write(dir.path().join("edge_dst"), [0u8; 3]).unwrap();
+        assert!(matches!(
+            MmapGraph::open(dir.path()),
+            Err(GraphError::Io {
+                kind: io::ErrorKind::InvalidData,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn opening_reports_corrupt_offsets_and_numbers() {
+        let dir = TempDir::new("docbrown").unwrap();
+        let corrupt = |name: &str, f: &dyn Fn(&mut Vec<u64>)| {
+            MmapGraph::write(&graph(), dir.path()).unwrap();
+            let path = dir.path().join(name);
+            let bytes = std::fs::read(&path).unwrap();
+            let mut values = bytes
+                .chunks(8)
+                .map(|c| u64::from_le_bytes(c.try_into().unwrap()))
+                .collect_vec();
+            f(&mut values);
+            std::fs::write(
+                &path,
+                values.iter().flat_map(|v| v.to_le_bytes()).collect_vec(),
+            )
+            .unwrap();
+            MmapGraph::open(dir.path()).err()
+        };
+
+        // the last offset still matches the number of edges
+        let decreasing = corrupt("out_offsets", &|offsets| {
+            offsets[1] = offsets[offsets.len() - 1];
+            offsets[2] = 0;
+        });
+        let too_large = corrupt("vertex_time_offsets", &|offsets| offsets[1] = u64::MAX);
+        let missing_vertex = corrupt("edge_dst", &|dst| dst[0] = 1000);
+        let missing_edge = corrupt("in_edges", &|edges| edges[0] = 1000);
+        let unsorted = corrupt("vertex_ids", &|ids| ids.swap(0, 1));
+        let wrong_src = corrupt("edge_src", &|src| src[0] += 1);
+        for err in [
+            decreasing,
+            too_large,
+            missing_vertex,
+            missing_edge,
+            unsorted,
+            wrong_src,
+        ] {
+            assert!(matches!(
+                err,
+                Some(GraphError::Io {
+                    kind: io::ErrorKind::InvalidData,
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn opening_reports_corrupt_values() {
+        let dir = TempDir::new("docbrown").unwrap();
+        MmapGraph::write(&graph(), dir.path()).unwrap();
+        let path = dir.path().join("values");
+        let mut bytes = std::fs::read(&path).unwrap();
+        // the first value starts with the number of its variant
+        bytes[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        std::fs::write(&path, bytes).unwrap();
+        let err = MmapGraph::open(dir.path()).err();
+        assert!(matches!(
+            err,
+            Some(GraphError::Io {
+                kind: io::ErrorKind::InvalidData,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn mmap_graphs_keep_the_vertex_names() {
+        let g = Graph::new_with_vertex_ids(2, VertexIds::Dense);
+        g.add_edge(1, "Alice", "Bob", &vec![], None).unwrap();
+        g.add_edge(2, 4, 7, &vec![], None).unwrap();
+        // the history of an edge view ends before the latest time of the graph
+        g.add_vertex(3, 9, &vec![]).unwrap();
+        let dir = TempDir::new("docbrown").unwrap();
+        MmapGraph::write(&g, dir.path()).unwrap();
+        let mg = MmapGraph::open(dir.path()).unwrap();
+
+        assert_eq!(mg.vertex("Alice").unwrap().id(), 0);
+        assert_eq!(mg.vertex("Bob").unwrap().id(), 1);
+        assert!(mg.has_edge("Alice", "Bob", None));
+        assert!(mg.vertex("Carol").is_none());
+        assert_eq!(contents(&mg), contents(&g));
+    }
+}
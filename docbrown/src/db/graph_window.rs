@@ -39,6 +39,7 @@
 
 use crate::core::{
     tgraph::{EdgeRef, VertexRef},
+    time::TimeUnit,
     Direction, Prop,
 };
 use crate::db::view_api::internal::GraphViewInternalOps;
@@ -73,6 +74,10 @@ impl<G: GraphViewOps> GraphViewInternalOps for WindowedGraph<G> {
         Some(self.t_end)
     }
 
+    fn view_time_unit(&self) -> TimeUnit {
+        self.graph.view_time_unit()
+    }
+
     fn earliest_time_global(&self) -> Option<i64> {
         self.graph.earliest_time_window(self.t_start, self.t_end)
     }
@@ -273,6 +278,19 @@ impl<G: GraphViewOps> GraphViewInternalOps for WindowedGraph<G> {
         )
     }
 
+    /// Get the id of the vertex with the name `name`
+    ///
+    /// # Arguments
+    ///
+    /// - `name` - The name of the vertex
+    ///
+    /// # Returns
+    ///
+    /// The id of the name in the underlying graph, whether or not the vertex is in the window.
+    fn vertex_id_by_name(&self, name: &str) -> Option<u64> {
+        self.graph.vertex_id_by_name(name)
+    }
+
     /// Get the reference of the vertex with ID v if it exists
     ///
     /// # Arguments
@@ -0,0 +1,295 @@
+//! The manifest describing a graph saved to a directory.
+//!
+//! A saved graph is a directory holding one file per shard and a `manifest` file. The manifest
+//! starts with a magic string and the version of the format the directory was written in,
+//! followed by the bincode encoded `Manifest` which records the number of shards, the layers,
+//! the schema, the ids of the vertices given by name, the time unit, the generation of the shard
+//! files and the crc32 checksum of every shard file.
+//!
+//! Every save writes its shards to new files, named after the generation of the save (see
+//! `shard_file`), and only then replaces the manifest, so that a save that is interrupted leaves
+//! the previous save as it was. The files of other generations are removed once the new manifest
+//! is in place.
+//!
+//! Directories written in an older format are upgraded when they are read by the entry for
+//! their version in `MIGRATIONS`. Directories written before the manifest existed, which hold a
+//! `graphdb_nr_shards` file instead, are format version 0.
+
+use crate::core::schema::Schema;
+use crate::core::tgraph_shard::errors::GraphError;
+use crate::core::time::TimeUnit;
+use crate::core::utils;
+use crate::core::vertex_names::{VertexIds, VertexNames};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+/// The version of the format `Graph::save_to_file` writes.
+pub const FORMAT_VERSION: u32 = 4;
+
+/// The name of the manifest file in the graph directory.
+pub(crate) const MANIFEST_FILE: &str = "manifest";
+
+// the file that held the number of shards, the layers and the schema in format version 0
+const LEGACY_FILE: &str = "graphdb_nr_shards";
+
+const MAGIC: &[u8; 8] = b"DOCBROWN";
+
+// magic and version
+const HEADER_LEN: usize = 12;
+
+/// Upgrades the manifest of a directory in the format version at its index in `MIGRATIONS`,
+/// given the directory and the manifest bytes after the header, to the current format.
+type Migration = fn(&Path, &[u8]) -> Result<Manifest, GraphError>;
+
+const MIGRATIONS: [Migration; FORMAT_VERSION as usize] = [
+    migrate_from_v0,
+    migrate_from_v1,
+    migrate_from_v2,
+    migrate_from_v3,
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct Manifest {
+    pub(crate) nr_shards: usize,
+    pub(crate) layer_ids: FxHashMap<String, usize>,
+    pub(crate) schema: Schema,
+    pub(crate) vertex_names: VertexNames,
+    pub(crate) time_unit: TimeUnit,
+    pub(crate) generation: u64,
+    pub(crate) shard_checksums: Vec<u32>,
+}
+
+/// The name of the file of shard `i` written by the save of `generation`. Directories written
+/// before generations were recorded are generation 0.
+pub(crate) fn shard_file(i: usize, generation: u64) -> String {
+    match generation {
+        0 => format!("shard_{i}"),
+        generation => format!("shard_{i}.{generation}"),
+    }
+}
+
+#[derive(Deserialize)]
+struct ManifestV1 {
+    nr_shards: usize,
+    layer_ids: FxHashMap<String, usize>,
+    schema: Schema,
+    shard_checksums: Vec<u32>,
+}
+
+#[derive(Deserialize)]
+struct ManifestV2 {
+    nr_shards: usize,
+    layer_ids: FxHashMap<String, usize>,
+    schema: Schema,
+    vertex_names: VertexNames,
+    shard_checksums: Vec<u32>,
+}
+
+#[derive(Deserialize)]
+struct ManifestV3 {
+    nr_shards: usize,
+    layer_ids: FxHashMap<String, usize>,
+    schema: Schema,
+    vertex_names: VertexNames,
+    time_unit: TimeUnit,
+    shard_checksums: Vec<u32>,
+}
+
+impl Manifest {
+    /// Writes the manifest to `dir`, replacing the previous one only once it is complete.
+    pub(crate) fn write(&self, dir: &Path) -> Result<(), GraphError> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bincode::serialize_into(&mut bytes, self)?;
+
+        utils::write_atomically(&dir.join(MANIFEST_FILE), &bytes)?;
+        Ok(())
+    }
+
+    /// Reads the manifest of the graph saved in `dir`, upgrading it if it is in an older format.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::UnsupportedFormatVersion` if the directory was written in a newer
+    /// format and `GraphError::Io` if the directory has no manifest or it cannot be read.
+    pub(crate) fn read(dir: &Path) -> Result<Self, GraphError> {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.exists() && dir.join(LEGACY_FILE).exists() {
+            let bytes = std::fs::read(dir.join(LEGACY_FILE))?;
+            return MIGRATIONS[0](dir, &bytes);
+        }
+
+        let bytes = std::fs::read(path)?;
+        if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(invalid_data(format!(
+                "{} is not a docbrown manifest",
+                dir.join(MANIFEST_FILE).display()
+            )));
+        }
+        let version = u32::from_le_bytes(bytes[MAGIC.len()..HEADER_LEN].try_into().unwrap());
+        let body = &bytes[HEADER_LEN..];
+
+        let manifest: Manifest = match version {
+            FORMAT_VERSION => bincode::deserialize(body)?,
+            v if v < FORMAT_VERSION => MIGRATIONS[v as usize](dir, body)?,
+            found => {
+                return Err(GraphError::UnsupportedFormatVersion {
+                    found,
+                    supported: FORMAT_VERSION,
+                })
+            }
+        };
+        if manifest.shard_checksums.len() != manifest.nr_shards {
+            return Err(invalid_data(format!(
+                "the manifest lists {} shard checksums for {} shards",
+                manifest.shard_checksums.len(),
+                manifest.nr_shards
+            )));
+        }
+        Ok(manifest)
+    }
+}
+
+fn invalid_data(message: String) -> GraphError {
+    GraphError::Io {
+        kind: io::ErrorKind::InvalidData,
+        message,
+    }
+}
+
+/// Version 0 stored `(nr_shards, layer_ids)`, later followed by the schema, in
+/// `graphdb_nr_shards` and had no checksums, so they are taken from the shard files as they are.
+/// Names were hashed as in `migrate_from_v1`, times were in milliseconds.
+fn migrate_from_v0(dir: &Path, bytes: &[u8]) -> Result<Manifest, GraphError> {
+    let (nr_shards, layer_ids, schema) = bincode::deserialize(bytes).or_else(|_| {
+        bincode::deserialize::<(usize, FxHashMap<String, usize>)>(bytes)
+            .map(|(nr_shards, layer_ids)| (nr_shards, layer_ids, Schema::default()))
+    })?;
+    let shard_checksums = (0..nr_shards)
+        .map(|i| Ok(crc32fast::hash(&std::fs::read(dir.join(shard_file(i, 0)))?)))
+        .collect::<Result<_, GraphError>>()?;
+    Ok(Manifest {
+        nr_shards,
+        layer_ids,
+        schema,
+        vertex_names: VertexNames::new(VertexIds::Hashed),
+        time_unit: TimeUnit::Milliseconds,
+        generation: 0,
+        shard_checksums,
+    })
+}
+
+/// Version 1 had no table of vertex names, as names were always hashed the graph keeps hashing
+/// them. Times were in milliseconds.
+fn migrate_from_v1(_dir: &Path, bytes: &[u8]) -> Result<Manifest, GraphError> {
+    let v1: ManifestV1 = bincode::deserialize(bytes)?;
+    Ok(Manifest {
+        nr_shards: v1.nr_shards,
+        layer_ids: v1.layer_ids,
+        schema: v1.schema,
+        vertex_names: VertexNames::new(VertexIds::Hashed),
+        time_unit: TimeUnit::Milliseconds,
+        generation: 0,
+        shard_checksums: v1.shard_checksums,
+    })
+}
+
+/// Version 2 had no time unit, as times were always in milliseconds.
+fn migrate_from_v2(_dir: &Path, bytes: &[u8]) -> Result<Manifest, GraphError> {
+    let v2: ManifestV2 = bincode::deserialize(bytes)?;
+    Ok(Manifest {
+        nr_shards: v2.nr_shards,
+        layer_ids: v2.layer_ids,
+        schema: v2.schema,
+        vertex_names: v2.vertex_names,
+        time_unit: TimeUnit::Milliseconds,
+        generation: 0,
+        shard_checksums: v2.shard_checksums,
+    })
+}
+
+/// Version 3 wrote every save to the same `shard_N` files, which are generation 0.
+fn migrate_from_v3(_dir: &Path, bytes: &[u8]) -> Result<Manifest, GraphError> {
+    let v3: ManifestV3 = bincode::deserialize(bytes)?;
+    Ok(Manifest {
+        nr_shards: v3.nr_shards,
+        layer_ids: v3.layer_ids,
+        schema: v3.schema,
+        vertex_names: v3.vertex_names,
+        time_unit: v3.time_unit,
+        generation: 0,
+        shard_checksums: v3.shard_checksums,
+    })
+}
+
+#[cfg(test)]
+mod manifest_tests {
+    use super::*;
+    use crate::core::vertex::InputVertex;
+    use crate::db::graph::Graph;
+    use tempdir::TempDir;
+
+    #[test]
+    fn manifests_from_newer_versions_are_rejected() {
+        let dir = TempDir::new("docbrown").unwrap();
+        let manifest = Manifest {
+            nr_shards: 0,
+            layer_ids: Default::default(),
+            schema: Schema::default(),
+            vertex_names: VertexNames::default(),
+            time_unit: TimeUnit::Nanoseconds,
+            generation: 3,
+            shard_checksums: vec![],
+        };
+        manifest.write(dir.path()).unwrap();
+        assert_eq!(Manifest::read(dir.path()), Ok(manifest));
+
+        let path = dir.path().join(MANIFEST_FILE);
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[MAGIC.len()..HEADER_LEN].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        std::fs::write(&path, bytes).unwrap();
+        assert_eq!(
+            Manifest::read(dir.path()),
+            Err(GraphError::UnsupportedFormatVersion {
+                found: FORMAT_VERSION + 1,
+                supported: FORMAT_VERSION,
+            })
+        );
+
+        std::fs::write(&path, b"not a manifest").unwrap();
+        assert!(matches!(
+            Manifest::read(dir.path()),
+            Err(GraphError::Io {
+                kind: io::ErrorKind::InvalidData,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn version_1_directories_keep_hashing_names() {
+        let dir = TempDir::new("docbrown").unwrap();
+        let g = Graph::new(2);
+        g.add_edge(1, "Alice", "Bob", &vec![], None).unwrap();
+        g.add_vertex(2, 7, &vec![]).unwrap();
+        g.save_to_file(dir.path()).unwrap();
+        let manifest = Manifest::read(dir.path()).unwrap();
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        let v1 = (
+            manifest.nr_shards,
+            &manifest.layer_ids,
+            &manifest.schema,
+            &manifest.shard_checksums,
+        );
+        bincode::serialize_into(&mut bytes, &v1).unwrap();
+        std::fs::write(dir.path().join(MANIFEST_FILE), bytes).unwrap();
+
+        let migrated = Manifest::read(dir.path()).unwrap();
+        assert_eq!(migrated, manifest);
+        assert_eq!(migrated.vertex_names.get("Alice"), Some("Alice".id()));
+    }
+}
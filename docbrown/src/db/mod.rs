@@ -2,9 +2,13 @@ pub mod edge;
 pub mod graph;
 pub mod graph_immutable;
 pub mod graph_layer;
+pub mod graph_mmap;
 pub mod graph_window;
+pub mod manifest;
 pub mod path;
 pub mod program;
+pub mod subgraph;
 pub mod vertex;
 pub mod vertices;
 pub mod view_api;
+mod wal;
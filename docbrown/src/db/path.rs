@@ -1,4 +1,5 @@
 use crate::core::tgraph::VertexRef;
+use crate::core::time::TimeUnit;
 use crate::core::{Direction, Prop};
 use crate::db::edge::EdgeView;
 use crate::db::vertex::VertexView;
@@ -271,6 +272,10 @@ impl<G: GraphViewOps> TimeOps for PathFromGraph<G> {
         }
     }
 
+    fn time_unit(&self) -> TimeUnit {
+        self.graph.time_unit()
+    }
+
     fn window(&self, t_start: i64, t_end: i64) -> Self::WindowedViewType {
         Self {
             graph: self.graph.clone(),
@@ -475,6 +480,10 @@ impl<G: GraphViewOps> TimeOps for PathFromVertex<G> {
         }
     }
 
+    fn time_unit(&self) -> TimeUnit {
+        self.graph.time_unit()
+    }
+
     fn window(&self, t_start: i64, t_end: i64) -> Self::WindowedViewType {
         Self {
             graph: self.graph.clone(),
@@ -1,23 +1,233 @@
 //!  Defines the `Program` trait, which represents code that is used to evaluate
 //!  algorithms and custom code that can be run on the graph.
+//!
+//!  A program is vertex-centric: `local_eval` runs on every shard and updates the accumulators
+//!  of vertices and their neighbours, `post_eval` merges the shard states and decides which
+//!  vertices stay active for the next step. Accumulators are declared with the constructors in
+//!  `core::state::def` and are identified by an id that has to be unique within the program.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use docbrown::core::state;
+//! use docbrown::db::graph::Graph;
+//! use docbrown::db::program::{GlobalEvalState, LocalState, Program};
+//! use docbrown::db::view_api::*;
+//! use std::collections::HashMap;
+//!
+//! /// Counts the in-neighbours of every vertex by having each vertex notify its out-neighbours.
+//! struct InNeighbours;
+//!
+//! impl Program for InNeighbours {
+//!     type Out = (HashMap<u64, usize>, usize);
+//!
+//!     fn local_eval<G: GraphViewOps>(&self, c: &LocalState<G>) {
+//!         let count = c.agg(state::def::sum::<usize>(0));
+//!         let total = c.global_agg(state::def::sum::<usize>(1));
+//!
+//!         c.step(|v| {
+//!             for n in v.neighbours_out() {
+//!                 n.update(&count, 1);
+//!                 v.global_update(&total, 1);
+//!             }
+//!         })
+//!     }
+//!
+//!     fn post_eval<G: GraphViewOps>(&self, c: &mut GlobalEvalState<G>) {
+//!         let _ = c.agg(state::def::sum::<usize>(0));
+//!         let _ = c.global_agg(state::def::sum::<usize>(1));
+//!         c.step(|_| false)
+//!     }
+//!
+//!     fn produce_output<G: GraphViewOps>(&self, g: &G, gs: &GlobalEvalState<G>) -> Self::Out {
+//!         let mut counts = HashMap::new();
+//!         for shard in 0..g.num_shards() {
+//!             gs.fold_state(&state::def::sum::<usize>(0), shard, &mut counts, |res, v_id, n| {
+//!                 res.insert(*v_id, n);
+//!                 res
+//!             });
+//!         }
+//!         let total = gs.read_global_state(&state::def::sum::<usize>(1)).unwrap_or(0);
+//!         (counts, total)
+//!     }
+//! }
+//!
+//! let g = Graph::new(2);
+//! for (src, dst) in [(1, 3), (2, 3), (3, 1)] {
+//!     g.add_edge(0, src, dst, &vec![], None).unwrap();
+//! }
+//!
+//! let program = InNeighbours;
+//! let gs = program.run(&g, false, 1);
+//! let (counts, total) = program.produce_output(&g, &gs);
+//!
+//! assert_eq!(counts[&3], 2);
+//! assert_eq!(counts[&1], 1);
+//! assert_eq!(total, 3);
+//! ```
+//!
+//! Vertices can also exchange messages, which are delivered in the next superstep. Below are hop
+//! distances from a source vertex, combining messages with `min` so every vertex only sees the
+//! shortest distance it was offered.
+//!
+//! ```rust
+//! use docbrown::core::state;
+//! use docbrown::db::graph::Graph;
+//! use docbrown::db::program::{GlobalEvalState, LocalState, Program, Runner, Termination};
+//! use docbrown::db::view_api::*;
+//! use std::collections::HashMap;
+//!
+//! struct HopDistance {
+//!     source: u64,
+//! }
+//!
+//! impl Program for HopDistance {
+//!     type Out = HashMap<u64, u64>;
+//!
+//!     fn local_eval<G: GraphViewOps>(&self, c: &LocalState<G>) {
+//!         let distance = c.agg(state::def::min::<u64>(0));
+//!         let mailbox = c.agg(state::def::min::<u64>(1));
+//!
+//!         c.step(|v| {
+//!             let offered = if v.global_id() == self.source {
+//!                 0
+//!             } else {
+//!                 v.inbox(&mailbox)
+//!             };
+//!             if offered < v.read(&distance) {
+//!                 v.update(&distance, offered);
+//!                 v.send_to_out_neighbours(&mailbox, offered + 1);
+//!             }
+//!             v.vote_to_halt();
+//!         })
+//!     }
+//!
+//!     fn post_eval<G: GraphViewOps>(&self, c: &mut GlobalEvalState<G>) {
+//!         let _ = c.agg(state::def::min::<u64>(0));
+//!         let _ = c.agg_reset(state::def::min::<u64>(1));
+//!         c.step(|_| true)
+//!     }
+//!
+//!     fn produce_output<G: GraphViewOps>(&self, g: &G, gs: &GlobalEvalState<G>) -> Self::Out {
+//!         let mut distances = HashMap::new();
+//!         for shard in 0..g.num_shards() {
+//!             gs.fold_state(&state::def::min::<u64>(0), shard, &mut distances, |res, v_id, d| {
+//!                 if d != u64::MAX {
+//!                     res.insert(*v_id, d);
+//!                 }
+//!                 res
+//!             });
+//!         }
+//!         distances
+//!     }
+//! }
+//!
+//! let g = Graph::new(2);
+//! for (src, dst) in [(1, 2), (2, 3), (1, 4), (4, 3), (3, 5), (6, 1)] {
+//!     g.add_edge(0, src, dst, &vec![], None).unwrap();
+//! }
+//!
+//! let program = HopDistance { source: 1 };
+//! let mut gs = GlobalEvalState::new(g.clone(), true);
+//! let report = Runner::new().step(&program).run(&g, &mut gs).unwrap();
+//! assert_eq!(report.termination, Termination::AllHalted);
+//!
+//! let distances = program.produce_output(&g, &gs);
+//! assert_eq!(distances[&3], 2);
+//! assert_eq!(distances[&5], 3);
+//! assert!(!distances.contains_key(&6));
+//! ```
+//!
+//! Programs can also walk the edges of a vertex, read their properties and updates and keep
+//! state per edge. Below every vertex sums the weights of its outgoing updates while every edge
+//! counts how many times it was updated.
+//!
+//! ```rust
+//! use docbrown::core::{state, Prop};
+//! use docbrown::db::graph::Graph;
+//! use docbrown::db::program::{GlobalEvalState, LocalState, Program};
+//! use docbrown::db::view_api::*;
+//! use std::collections::HashMap;
+//!
+//! struct Strength;
+//!
+//! impl Program for Strength {
+//!     type Out = ();
+//!
+//!     fn local_eval<G: GraphViewOps>(&self, c: &LocalState<G>) {
+//!         let strength = c.agg(state::def::sum::<f64>(0));
+//!         let updates = c.agg(state::def::sum::<usize>(1));
+//!
+//!         c.step(|v| {
+//!             for e in v.out_edges() {
+//!                 for (_, weight) in e.property_history("weight".to_string()) {
+//!                     if let Prop::F64(w) = weight {
+//!                         v.update(&strength, w);
+//!                     }
+//!                 }
+//!                 e.update(&updates, e.explode().count());
+//!             }
+//!         })
+//!     }
+//!
+//!     fn post_eval<G: GraphViewOps>(&self, c: &mut GlobalEvalState<G>) {
+//!         let _ = c.agg(state::def::sum::<f64>(0));
+//!         let _ = c.agg(state::def::sum::<usize>(1));
+//!         c.step(|_| false)
+//!     }
+//!
+//!     fn produce_output<G: GraphViewOps>(&self, _g: &G, _gs: &GlobalEvalState<G>) {}
+//! }
+//!
+//! let g = Graph::new(2);
+//! for (t, src, dst, w) in [(1, 1, 2, 0.5), (2, 1, 2, 1.5), (3, 1, 3, 2.0), (4, 3, 1, 1.0)] {
+//!     g.add_edge(t, src, dst, &vec![("weight".to_string(), Prop::F64(w))], None)
+//!         .unwrap();
+//! }
+//!
+//! let gs = Strength.run(&g, false, 1);
+//!
+//! let mut strength = HashMap::new();
+//! for shard in 0..2 {
+//!     gs.fold_state(&state::def::sum::<f64>(0), shard, &mut strength, |res, v_id, s| {
+//!         res.insert(*v_id, s);
+//!         res
+//!     });
+//! }
+//! assert_eq!(strength[&1], 4.0);
+//! assert_eq!(strength[&3], 1.0);
+//! assert_eq!(gs.read_edge_state(&state::def::sum::<usize>(1), 1, 2, None), Some(2));
+//! assert_eq!(gs.read_edge_state(&state::def::sum::<usize>(1), 3, 1, None), Some(1));
+//! assert_eq!(gs.read_edge_state(&state::def::sum::<usize>(1), 2, 1, None), None);
+//! ```
 
 use std::{
-    cell::{Ref, RefCell},
-    fmt::Debug,
+    cell::{Cell, Ref, RefCell},
+    fmt::{Debug, Display},
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
     rc::Rc,
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use crate::core::{
     agg::Accumulator,
-    state::{AccId, ShuffleComputeState},
-    state::{ComputeStateMap, StateType},
+    cancel::Budget,
+    state::{AccId, AccumulatorRegistry, ShuffleComputeState},
+    state::{ComputeStateMap, EncodedState, StateType},
+    tgraph_shard::errors::GraphError,
+    utils::get_shard_id_from_global_vid,
+    Prop,
 };
+use crate::db::edge::EdgeView;
 use crate::db::vertex::VertexView;
 use crate::db::view_api::{GraphViewOps, VertexViewOps};
 use itertools::Itertools;
 use rayon::prelude::*;
 use rustc_hash::FxHashSet;
+use serde::{Deserialize, Serialize};
 
 type CS = ComputeStateMap;
 
@@ -45,7 +255,7 @@ where
     /// # Returns
     ///
     /// An `AggRef` object.
-    pub(crate) fn new(agg_ref: AccId<A, IN, OUT, ACC>) -> Self {
+    pub fn new(agg_ref: AccId<A, IN, OUT, ACC>) -> Self {
         Self(agg_ref)
     }
 }
@@ -57,6 +267,11 @@ pub struct LocalState<G: GraphViewOps> {
     graph: G,
     shard_local_state: Rc<RefCell<ShuffleComputeState<CS>>>,
     next_vertex_set: Option<Arc<FxHashSet<u64>>>,
+    halted: Rc<RefCell<FxHashSet<u64>>>,
+    active_vertices: Cell<usize>,
+    accumulators: Arc<parking_lot::Mutex<AccumulatorRegistry>>,
+    // the first accumulator that failed its check, steps do nothing once one has
+    error: RefCell<Option<GraphError>>,
 }
 
 impl<G: GraphViewOps> LocalState<G> {
@@ -70,6 +285,7 @@ impl<G: GraphViewOps> LocalState<G> {
     /// * `window` - The range of the window.
     /// * `shard_local_state` - The local state of the shard.
     /// * `next_vertex_set` - An optional set of vertices to process in the next iteration.
+    /// * `accumulators` - The registry the accumulators used are checked against.
     ///
     /// # Returns
     ///
@@ -80,6 +296,7 @@ impl<G: GraphViewOps> LocalState<G> {
         graph: G,
         shard_local_state: Rc<RefCell<ShuffleComputeState<CS>>>,
         next_vertex_set: Option<Arc<FxHashSet<u64>>>,
+        accumulators: Arc<parking_lot::Mutex<AccumulatorRegistry>>,
     ) -> Self {
         Self {
             ss,
@@ -87,11 +304,35 @@ impl<G: GraphViewOps> LocalState<G> {
             graph,
             shard_local_state,
             next_vertex_set,
+            halted: Rc::new(RefCell::new(FxHashSet::default())),
+            active_vertices: Cell::new(0),
+            accumulators,
+            error: RefCell::new(None),
+        }
+    }
+
+    fn check<A, IN, OUT, ACC>(&self, agg_ref: &AccId<A, IN, OUT, ACC>)
+    where
+        A: StateType,
+        IN: 'static,
+        OUT: 'static,
+        ACC: Accumulator<A, IN, OUT> + 'static,
+    {
+        let checked = self.accumulators.lock().check(agg_ref).and_then(|_| {
+            self.shard_local_state
+                .borrow_mut()
+                .decode_pending(agg_ref.id())
+        });
+        if let Err(e) = checked {
+            self.error.borrow_mut().get_or_insert(e);
         }
     }
 
     /// Creates an `AggRef` object for the specified accumulator.
     ///
+    /// If the id of the accumulator is used with another accumulator type, see
+    /// `AccumulatorRegistry::check`, the steps of this state do nothing and the run fails.
+    ///
     /// # Arguments
     ///
     /// * `agg_ref` - The ID of the accumulator to reference.
@@ -99,17 +340,19 @@ impl<G: GraphViewOps> LocalState<G> {
     /// # Returns
     ///
     /// An `AggRef` object.
-    pub(crate) fn agg<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+    pub fn agg<A, IN: 'static, OUT: 'static, ACC: Accumulator<A, IN, OUT> + 'static>(
         &self,
         agg_ref: AccId<A, IN, OUT, ACC>,
     ) -> AggRef<A, IN, OUT, ACC>
     where
         A: StateType,
     {
+        self.check(&agg_ref);
         AggRef(agg_ref)
     }
 
-    /// Creates an `AggRef` object for the specified global accumulator.
+    /// Creates an `AggRef` object for the specified global accumulator, checked like the
+    /// accumulators of `agg`.
     ///
     /// # Arguments
     ///
@@ -118,13 +361,14 @@ impl<G: GraphViewOps> LocalState<G> {
     /// # Returns
     ///
     /// An `AggRef` object.
-    pub(crate) fn global_agg<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+    pub fn global_agg<A, IN: 'static, OUT: 'static, ACC: Accumulator<A, IN, OUT> + 'static>(
         &self,
         agg_ref: AccId<A, IN, OUT, ACC>,
     ) -> AggRef<A, IN, OUT, ACC>
     where
         A: StateType,
     {
+        self.check(&agg_ref);
         AggRef(agg_ref)
     }
 
@@ -135,10 +379,13 @@ impl<G: GraphViewOps> LocalState<G> {
     /// # Arguments
     ///
     /// * `f` - The function to execute on each vertex.
-    pub(crate) fn step<F>(&self, f: F)
+    pub fn step<F>(&self, f: F)
     where
         F: Fn(EvalVertexView<G>),
     {
+        if self.error.borrow().is_some() {
+            return;
+        }
         let graph = self.graph.clone();
 
         let iter: Box<dyn Iterator<Item = VertexView<G>>> = match self.next_vertex_set {
@@ -157,21 +404,38 @@ impl<G: GraphViewOps> LocalState<G> {
 
         let mut c = 0;
         iter.for_each(|v| {
-            f(EvalVertexView::new(
-                self.ss,
-                v,
-                self.shard_local_state.clone(),
-            ));
+            f(
+                EvalVertexView::new(self.ss, v, self.shard_local_state.clone())
+                    .with_halted(self.halted.clone()),
+            );
             c += 1;
         });
+        self.active_vertices.set(self.active_vertices.get() + c);
     }
 
-    /// Returns the local state of the worker as a ShuffleComputeState instance.
-    fn consume(self) -> ShuffleComputeState<CS> {
-        Rc::try_unwrap(self.shard_local_state).unwrap().into_inner()
+    /// Returns the local state of the worker as a ShuffleComputeState instance, together with
+    /// the vertices that voted to halt, the number of vertices evaluated and the first
+    /// accumulator that failed its check.
+    fn consume(
+        self,
+    ) -> (
+        ShuffleComputeState<CS>,
+        FxHashSet<u64>,
+        usize,
+        Option<GraphError>,
+    ) {
+        (
+            Rc::try_unwrap(self.shard_local_state).unwrap().into_inner(),
+            Rc::try_unwrap(self.halted).unwrap().into_inner(),
+            self.active_vertices.get(),
+            self.error.into_inner(),
+        )
     }
 }
 
+// the state of a shard, taken out while a step works on it
+type SharedState = Arc<parking_lot::RwLock<Option<ShuffleComputeState<CS>>>>;
+
 /// GlobalEvalState represents the state of the computation across all shards.
 ///
 /// # Arguments
@@ -189,10 +453,20 @@ pub struct GlobalEvalState<G: GraphViewOps> {
     pub ss: usize,
     g: G,
     pub keep_past_state: bool,
+    // merge the shards in order instead of in parallel
+    deterministic: bool,
     // running state
     pub next_vertex_set: Option<Vec<Arc<FxHashSet<u64>>>>,
-    states: Vec<Arc<parking_lot::RwLock<Option<ShuffleComputeState<CS>>>>>,
+    states: Vec<SharedState>,
     resetable_states: Vec<u32>,
+    accumulators: Arc<parking_lot::Mutex<AccumulatorRegistry>>,
+    // the first error a program ran into, see `error`
+    error: Option<GraphError>,
+    // vertices that voted to halt in the last local step, per shard
+    halted: Vec<FxHashSet<u64>>,
+    // keys whose state changed in the last step, per shard
+    changed_keys: Vec<usize>,
+    observers: Observers,
 }
 
 /// Implementation of the GlobalEvalState struct.
@@ -223,15 +497,16 @@ impl<G: GraphViewOps> GlobalEvalState<G> {
     /// # Constraints
     ///
     /// * `OUT: StateType`
-    /// * `A: 'static`
+    /// * `A: StateType`
     pub fn read_vec_partitions<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
         &self,
         agg: &AccId<A, IN, OUT, ACC>,
     ) -> Vec<Vec<Vec<OUT>>>
     where
         OUT: StateType,
-        A: 'static,
+        A: StateType,
     {
+        self.decode_readable(agg.id());
         self.states
             .iter()
             .map(|state| {
@@ -284,6 +559,7 @@ impl<G: GraphViewOps> GlobalEvalState<G> {
         B: Debug,
         F: Fn(B, &u64, OUT) -> B + Copy,
     {
+        self.decode_readable(agg.id());
         let part_state = self.states[part_id].read();
         let part_state = part_state.as_ref().unwrap();
 
@@ -315,11 +591,39 @@ impl<G: GraphViewOps> GlobalEvalState<G> {
         OUT: StateType,
         A: StateType,
     {
+        self.decode_readable(agg.id());
         let state = self.states[0].read();
         let state = state.as_ref().unwrap();
         state.read_global(self.ss, agg)
     }
 
+    /// Reads the state of the edge from `src` to `dst` in `layer` for a given accumulator. If the
+    /// state or the layer does not exist, returns None.
+    ///
+    /// # Arguments
+    ///
+    /// * `agg` - A reference to the `AccId` struct representing the accumulator.
+    /// * `src` - The global id of the source vertex.
+    /// * `dst` - The global id of the destination vertex.
+    /// * `layer` - The name of the layer of the edge, `None` for the default layer.
+    pub fn read_edge_state<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+        &self,
+        agg: &AccId<A, IN, OUT, ACC>,
+        src: u64,
+        dst: u64,
+        layer: Option<&str>,
+    ) -> Option<OUT>
+    where
+        OUT: StateType,
+        A: StateType,
+    {
+        let layer = self.g.get_layer(layer)?;
+        self.decode_readable(agg.id());
+        let state = self.states[get_shard_id_from_global_vid(src, self.states.len())].read();
+        let state = state.as_ref().unwrap();
+        state.read_edge(self.ss, src, dst, layer, agg)
+    }
+
     /// Determines whether the `next_vertex_set` is empty or not.
     ///
     /// # Return Value
@@ -336,6 +640,81 @@ impl<G: GraphViewOps> GlobalEvalState<G> {
         }) == Some(true)
     }
 
+    /// Writes the state of the computation to `writer` with bincode, it can be read back with
+    /// `read_checkpoint` on the same graph. Should be called between supersteps.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Where to write the checkpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::Io` if the checkpoint can't be written.
+    pub fn write_checkpoint<W: Write>(&self, writer: W) -> Result<(), GraphError> {
+        let encoded = self
+            .states
+            .iter()
+            .map(|state| state.read().as_ref().unwrap().encode())
+            .collect::<Result<_, _>>()?;
+        let checkpoint = CheckpointRef {
+            graph: GraphFingerprint::of(&self.g),
+            ss: self.ss,
+            keep_past_state: self.keep_past_state,
+            deterministic: self.deterministic,
+            next_vertex_set: &self.next_vertex_set,
+            accumulators: &self.accumulators.lock(),
+            states: encoded,
+        };
+        Ok(bincode::serialize_into(writer, &checkpoint)?)
+    }
+
+    /// Reads a state written by `write_checkpoint`.
+    ///
+    /// # Arguments
+    ///
+    /// * `g` - The graph the checkpoint was written for.
+    /// * `reader` - Where to read the checkpoint from.
+    ///
+    /// The accumulators have to keep their ids and types across the restart, programs that
+    /// use one with another type fail, see `AccumulatorRegistry::check`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::CheckpointGraphMismatch` if the checkpoint was written for a graph
+    /// with different shards, vertices, edges or times, or `GraphError::Io` if the checkpoint
+    /// can't be read.
+    pub fn read_checkpoint<R: Read>(g: G, reader: R) -> Result<Self, GraphError> {
+        let checkpoint: Checkpoint = bincode::deserialize_from(reader)?;
+        let graph = GraphFingerprint::of(&g);
+        if checkpoint.graph != graph {
+            return Err(GraphError::CheckpointGraphMismatch {
+                expected: checkpoint.graph.to_string(),
+                found: graph.to_string(),
+            });
+        }
+        Ok(Self {
+            ss: checkpoint.ss,
+            g,
+            keep_past_state: checkpoint.keep_past_state,
+            deterministic: checkpoint.deterministic,
+            next_vertex_set: checkpoint.next_vertex_set,
+            states: checkpoint
+                .states
+                .iter()
+                .map(|state| {
+                    let state = state.decode()?;
+                    Ok(Arc::new(parking_lot::RwLock::new(Some(state))))
+                })
+                .collect::<Result<_, GraphError>>()?,
+            resetable_states: Vec::new(),
+            accumulators: Arc::new(parking_lot::Mutex::new(checkpoint.accumulators)),
+            error: None,
+            halted: Vec::new(),
+            changed_keys: Vec::new(),
+            observers: Observers::default(),
+        })
+    }
+
     /// Creates a new `Context` object with the specified parameters with n_parts as input.
     ///
     /// # Arguments
@@ -359,12 +738,202 @@ impl<G: GraphViewOps> GlobalEvalState<G> {
             ss: 0,
             g,
             keep_past_state,
+            deterministic: false,
             next_vertex_set: None,
             states,
             resetable_states: Vec::new(),
+            accumulators: Default::default(),
+            error: None,
+            halted: Vec::new(),
+            changed_keys: Vec::new(),
+            observers: Observers::default(),
         }
     }
 
+    /// Merges the state of the shards in shard order instead of in parallel, so floating point
+    /// accumulators such as sums give bit-identical results across runs and thread counts. The
+    /// merge of every accumulator runs on a single thread, so this is slower on many shards.
+    /// Float sums should also use `state::def::stable_sum`, whose compensated summation keeps
+    /// the rounding error of the sum from growing with the number of values and shards.
+    ///
+    /// The setting is kept in checkpoints.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use docbrown::core::state;
+    /// use docbrown::db::graph::Graph;
+    /// use docbrown::db::program::{GlobalEvalState, LocalState, Program};
+    /// use docbrown::db::view_api::*;
+    ///
+    /// // the values cancel out differently depending on the order the shards are merged in
+    /// struct Total;
+    ///
+    /// impl Program for Total {
+    ///     type Out = ();
+    ///
+    ///     fn local_eval<G: GraphViewOps>(&self, c: &LocalState<G>) {
+    ///         let total = c.global_agg(state::def::sum::<f32>(0));
+    ///         c.step(|v| {
+    ///             v.global_update(&total, [1e8, 1.0, -1e8, 1.0][v.global_id() as usize % 4])
+    ///         })
+    ///     }
+    ///
+    ///     fn post_eval<G: GraphViewOps>(&self, c: &mut GlobalEvalState<G>) {
+    ///         let _ = c.global_agg(state::def::sum::<f32>(0));
+    ///         c.step(|_| false)
+    ///     }
+    ///
+    ///     fn produce_output<G: GraphViewOps>(&self, _g: &G, _gs: &GlobalEvalState<G>) {}
+    /// }
+    ///
+    /// // one vertex per shard
+    /// let g = Graph::new(8);
+    /// for v in 0..8 {
+    ///     g.add_vertex(0, v, &vec![]).unwrap();
+    /// }
+    ///
+    /// let total = |threads, deterministic| {
+    ///     let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+    ///     pool.install(|| {
+    ///         let mut gs = GlobalEvalState::new(g.clone(), false).deterministic(deterministic);
+    ///         Total.run_step(&g, &mut gs);
+    ///         gs.read_global_state(&state::def::sum::<f32>(0)).unwrap()
+    ///     })
+    /// };
+    ///
+    /// for threads in [1, 2, 8] {
+    ///     assert_eq!(total(threads, true), 1.0);
+    /// }
+    /// assert_ne!(total(1, false), total(8, false));
+    /// ```
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Returns the accumulator registered under `name`, registering it on first use. Programs
+    /// that run on the same state get the same accumulator for the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the accumulator.
+    /// * `def` - The constructor of the accumulator, e.g. `state::def::sum::<u64>`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::AccumulatorTypeMismatch` if `name` was already registered with a
+    /// different accumulator type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use docbrown::core::state;
+    /// use docbrown::db::graph::Graph;
+    /// use docbrown::db::program::GlobalEvalState;
+    ///
+    /// let g = Graph::new(2);
+    /// let mut gs = GlobalEvalState::new(g, false);
+    ///
+    /// let score = gs.accumulator("score", state::def::sum::<f64>).unwrap();
+    /// let same = gs.accumulator("score", state::def::sum::<f64>).unwrap();
+    /// assert_eq!(score.id(), same.id());
+    ///
+    /// // a later program in the pipeline expects a different type
+    /// assert!(gs.accumulator("score", state::def::min::<u64>).is_err());
+    /// ```
+    pub fn accumulator<A, IN, OUT, ACC, F>(
+        &mut self,
+        name: &str,
+        def: F,
+    ) -> Result<AccId<A, IN, OUT, ACC>, GraphError>
+    where
+        A: StateType,
+        IN: 'static,
+        OUT: 'static,
+        ACC: Accumulator<A, IN, OUT> + 'static,
+        F: FnOnce(u32) -> AccId<A, IN, OUT, ACC>,
+    {
+        self.accumulators.lock().register(name, def)
+    }
+
+    /// Returns the first error a program ran into on this state, if any. Programs fail when
+    /// they use an accumulator id with another accumulator type than before, see
+    /// `AccumulatorRegistry::check`, and the steps of a failed program do nothing. A `Runner`
+    /// stops at the error and returns it, taking it out of the state.
+    pub fn error(&self) -> Option<&GraphError> {
+        self.error.as_ref()
+    }
+
+    fn check_error(&mut self) -> Result<(), GraphError> {
+        self.error.take().map_or(Ok(()), Err)
+    }
+
+    // states read from a checkpoint stay encoded until their accumulator is used, see
+    // `EncodedState::decode`
+    fn decode_pending(&self, id: u32) -> Result<(), GraphError> {
+        self.states
+            .iter()
+            .try_for_each(|state| state.write().as_mut().unwrap().decode_pending(id))
+    }
+
+    // readers see a state that can't be decoded as missing, programs fail on it
+    fn decode_readable(&self, id: u32) {
+        let _ = self.decode_pending(id);
+    }
+
+    /// Registers an observer that is called with the statistics of every superstep run on this
+    /// state, e.g. to report progress or to spot skew between shards.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use docbrown::core::state;
+    /// use docbrown::db::graph::Graph;
+    /// use docbrown::db::program::{GlobalEvalState, LocalState, Program, SuperstepStats};
+    /// use docbrown::db::view_api::*;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// struct OutDegree;
+    ///
+    /// impl Program for OutDegree {
+    ///     type Out = ();
+    ///
+    ///     fn local_eval<G: GraphViewOps>(&self, c: &LocalState<G>) {
+    ///         let degree = c.agg(state::def::sum::<usize>(0));
+    ///         c.step(|v| v.update(&degree, v.out_degree()))
+    ///     }
+    ///
+    ///     fn post_eval<G: GraphViewOps>(&self, c: &mut GlobalEvalState<G>) {
+    ///         let _ = c.agg(state::def::sum::<usize>(0));
+    ///         c.step(|_| false)
+    ///     }
+    ///
+    ///     fn produce_output<G: GraphViewOps>(&self, _g: &G, _gs: &GlobalEvalState<G>) {}
+    /// }
+    ///
+    /// let g = Graph::new(2);
+    /// for (src, dst) in [(1, 2), (2, 3), (3, 1)] {
+    ///     g.add_edge(0, src, dst, &vec![], None).unwrap();
+    /// }
+    ///
+    /// let seen = Arc::new(Mutex::new(Vec::new()));
+    /// let observed = seen.clone();
+    ///
+    /// let mut gs = GlobalEvalState::new(g.clone(), false);
+    /// gs.observe(move |stats: &SuperstepStats| observed.lock().unwrap().push(stats.clone()));
+    /// OutDegree.run_step(&g, &mut gs);
+    ///
+    /// let seen = seen.lock().unwrap();
+    /// assert_eq!(seen.len(), 1);
+    /// assert_eq!(seen[0].shards.len(), 2);
+    /// assert_eq!(seen[0].active_vertices(), 3);
+    /// assert_eq!(seen[0].updates(), 3);
+    /// ```
+    pub fn observe<O: SuperstepObserver + 'static>(&mut self, observer: O) {
+        self.observers.0.push(Box::new(observer));
+    }
+
     /// Runs the global aggregation function for the given accumulator.
     ///
     /// # Arguments
@@ -381,7 +950,7 @@ impl<G: GraphViewOps> GlobalEvalState<G> {
     /// # Return Value
     ///
     /// An `AggRef` object representing the new state for the accumulator.
-    pub(crate) fn global_agg<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+    pub fn global_agg<A, IN: 'static, OUT: 'static, ACC: Accumulator<A, IN, OUT> + 'static>(
         &mut self,
         agg: AccId<A, IN, OUT, ACC>,
     ) -> AggRef<A, IN, OUT, ACC>
@@ -407,7 +976,7 @@ impl<G: GraphViewOps> GlobalEvalState<G> {
     /// # Return Value
     ///
     /// An `AggRef` object representing the new state for the accumulator.
-    pub(crate) fn global_agg_reset<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+    pub fn global_agg_reset<A, IN: 'static, OUT: 'static, ACC: Accumulator<A, IN, OUT> + 'static>(
         &mut self,
         agg: AccId<A, IN, OUT, ACC>,
     ) -> AggRef<A, IN, OUT, ACC>
@@ -444,7 +1013,7 @@ impl<G: GraphViewOps> GlobalEvalState<G> {
     ///
     /// An `AggRef` representing the result of the accumulator operation.
     ///
-    pub(crate) fn agg_reset<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+    pub fn agg_reset<A, IN: 'static, OUT: 'static, ACC: Accumulator<A, IN, OUT> + 'static>(
         &mut self,
         agg: AccId<A, IN, OUT, ACC>,
     ) -> AggRef<A, IN, OUT, ACC>
@@ -480,7 +1049,7 @@ impl<G: GraphViewOps> GlobalEvalState<G> {
     ///
     /// An `AggRef` representing the result of the accumulator operation.
     ///
-    pub(crate) fn agg<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+    pub fn agg<A, IN: 'static, OUT: 'static, ACC: Accumulator<A, IN, OUT> + 'static>(
         &mut self,
         agg: AccId<A, IN, OUT, ACC>,
     ) -> AggRef<A, IN, OUT, ACC>
@@ -490,7 +1059,7 @@ impl<G: GraphViewOps> GlobalEvalState<G> {
         self.agg_internal(agg, false)
     }
 
-    fn agg_internal<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+    fn agg_internal<A, IN: 'static, OUT: 'static, ACC: Accumulator<A, IN, OUT> + 'static>(
         &mut self,
         agg: AccId<A, IN, OUT, ACC>,
         reset: bool,
@@ -498,6 +1067,15 @@ impl<G: GraphViewOps> GlobalEvalState<G> {
     where
         A: StateType,
     {
+        let checked = self
+            .accumulators
+            .lock()
+            .check(&agg)
+            .and_then(|_| self.decode_pending(agg.id()));
+        if let Err(e) = checked {
+            self.error.get_or_insert(e);
+            return AggRef(agg);
+        }
         if reset {
             self.resetable_states.push(agg.id());
         }
@@ -507,42 +1085,60 @@ impl<G: GraphViewOps> GlobalEvalState<G> {
         // remove the accumulated state represendet by agg_ref from the states
         // then merge it accross all states (in parallel)
         // update the post_agg_state
-        let new_global_state = states
-            .into_par_iter()
-            .reduce_with(|left, right| {
-                // peel left
-                let left_placeholder = &mut left.write();
-                let mut state1 = left_placeholder.take().unwrap();
-                // peel right
-                let right_placeholder = &mut right.write();
-                let state2 = right_placeholder.take().unwrap();
-
-                state1.merge_mut(&state2, &agg, self.ss);
-                state1.merge_mut_global(&state2, &agg, self.ss);
-
-                **left_placeholder = Some(state1);
-                **right_placeholder = Some(state2);
-
-                left.clone()
-            })
-            .unwrap();
+        let merge = |left: SharedState, right: SharedState| {
+            // peel left
+            let left_placeholder = &mut left.write();
+            let mut state1 = left_placeholder.take().unwrap();
+            // peel right
+            let right_placeholder = &mut right.write();
+            let state2 = right_placeholder.take().unwrap();
+
+            state1.merge_mut(&state2, &agg, self.ss);
+            state1.merge_mut_global(&state2, &agg, self.ss);
+
+            **left_placeholder = Some(state1);
+            **right_placeholder = Some(state2);
+
+            left.clone()
+        };
+
+        // the shape of the parallel reduction depends on how rayon splits the work, folding
+        // in shard order fixes the order floating point values are combined in
+        let new_global_state = if self.deterministic {
+            states.into_iter().reduce(merge).unwrap()
+        } else {
+            states.into_par_iter().reduce_with(merge).unwrap()
+        };
 
         // selective broadcast
         // we set the state with id agg in shard_state to the value in global_state
-        self.states.par_iter().for_each(|shard_state| {
-            if !Arc::ptr_eq(&new_global_state, &shard_state) {
-                let shard_state_pl = &mut shard_state.write();
-                let mut shard_state = shard_state_pl.take().unwrap();
+        self.states
+            .par_iter()
+            .enumerate()
+            .for_each(|(shard, shard_state)| {
+                if !Arc::ptr_eq(&new_global_state, shard_state) {
+                    let shard_state_pl = &mut shard_state.write();
+                    let mut shard_state = shard_state_pl.take().unwrap();
 
-                let global_state_pl = new_global_state.read();
+                    let global_state_pl = new_global_state.read();
 
-                if let Some(global_state) = &global_state_pl.as_ref() {
-                    shard_state.set_from_other(&global_state, &agg, self.ss);
+                    if let Some(global_state) = &global_state_pl.as_ref() {
+                        shard_state.set_from_other(global_state, &agg, self.ss, shard);
+                    }
+
+                    **shard_state_pl = Some(shard_state);
                 }
+            });
 
-                **shard_state_pl = Some(shard_state);
-            }
-        });
+        // the merged state is also the state of a shard, it only keeps the edges that shard sees
+        let global_shard = self
+            .states
+            .iter()
+            .position(|state| Arc::ptr_eq(&new_global_state, state))
+            .unwrap();
+        if let Some(global_state) = new_global_state.write().as_mut() {
+            global_state.retain_edges(&agg, global_shard);
+        }
 
         // if the new state is not the same as the old one then we merge them too
         AggRef(agg)
@@ -555,7 +1151,7 @@ impl<G: GraphViewOps> GlobalEvalState<G> {
     /// * `f` - A closure taking an `EvalVertexView` and returning a boolean value.
     /// The closure is used to determine which vertices to include in the next step.
     ///
-    pub(crate) fn step<F>(&mut self, f: F)
+    pub fn step<F>(&mut self, f: F)
     where
         F: Fn(EvalVertexView<G>) -> bool + Sync,
     {
@@ -572,6 +1168,7 @@ impl<G: GraphViewOps> GlobalEvalState<G> {
                 let own_state = (local_state).take().unwrap();
 
                 let mut next_vertex_set = own_state.changed_keys(i, ss).collect::<FxHashSet<_>>(); // FxHashSet::default();
+                let changed_keys = next_vertex_set.len();
                 let prev_vertex_set = self
                     .next_vertex_set
                     .as_ref()
@@ -579,12 +1176,14 @@ impl<G: GraphViewOps> GlobalEvalState<G> {
                     .unwrap_or_else(|| Arc::new(own_state.keys(i).collect()));
 
                 let rc_state = Rc::new(RefCell::new(own_state));
+                let halted = self.halted.get(i);
 
                 for vv in prev_vertex_set.iter().flat_map(|v_id| graph.vertex(*v_id)) {
                     let evv = EvalVertexView::new(self.ss, vv, rc_state.clone());
                     let g_id = evv.global_id();
-                    // we need to account for the vertices that will be included in the next step
-                    if f(evv) {
+                    // we need to account for the vertices that will be included in the next step,
+                    // halted vertices only come back if their state changed
+                    if f(evv) && !halted.map_or(false, |h| h.contains(&g_id)) {
                         next_vertex_set.insert(g_id);
                     }
                 }
@@ -600,15 +1199,73 @@ impl<G: GraphViewOps> GlobalEvalState<G> {
 
                 // put back the local state
                 **local_state = Some(own_state);
-                Arc::new(next_vertex_set)
+                (Arc::new(next_vertex_set), changed_keys)
             })
             .collect::<Vec<_>>();
+        let (next_vertex_set, changed_keys) = next_vertex_set.into_iter().unzip();
 
         self.resetable_states.clear();
+        self.halted.clear();
+        self.changed_keys = changed_keys;
         self.next_vertex_set = Some(next_vertex_set);
     }
 }
 
+// a checkpoint can only be resumed on a graph that looks the same
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct GraphFingerprint {
+    n_shards: usize,
+    n_vertices: usize,
+    n_edges: usize,
+    earliest_time: Option<i64>,
+    latest_time: Option<i64>,
+}
+
+impl GraphFingerprint {
+    fn of<G: GraphViewOps>(g: &G) -> Self {
+        Self {
+            n_shards: g.num_shards(),
+            n_vertices: g.num_vertices(),
+            n_edges: g.num_edges(),
+            earliest_time: g.earliest_time(),
+            latest_time: g.latest_time(),
+        }
+    }
+}
+
+impl Display for GraphFingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} shards, {} vertices, {} edges and times {:?} to {:?}",
+            self.n_shards, self.n_vertices, self.n_edges, self.earliest_time, self.latest_time
+        )
+    }
+}
+
+// the fields of both have to stay in the same order, bincode is not self-describing
+#[derive(Serialize)]
+struct CheckpointRef<'a> {
+    graph: GraphFingerprint,
+    ss: usize,
+    keep_past_state: bool,
+    deterministic: bool,
+    next_vertex_set: &'a Option<Vec<Arc<FxHashSet<u64>>>>,
+    accumulators: &'a AccumulatorRegistry,
+    states: Vec<EncodedState>,
+}
+
+#[derive(Deserialize)]
+struct Checkpoint {
+    graph: GraphFingerprint,
+    ss: usize,
+    keep_past_state: bool,
+    deterministic: bool,
+    next_vertex_set: Option<Vec<Arc<FxHashSet<u64>>>>,
+    accumulators: AccumulatorRegistry,
+    states: Vec<EncodedState>,
+}
+
 /// Represents an entry in the shuffle table.
 ///
 /// The entry contains a reference to a `ShuffleComputeState` and an `AccId` representing the accumulator
@@ -659,6 +1316,7 @@ pub struct EvalVertexView<G: GraphViewOps> {
     ss: usize,
     vv: VertexView<G>,
     state: Rc<RefCell<ShuffleComputeState<CS>>>,
+    halted: Option<Rc<RefCell<FxHashSet<u64>>>>,
 }
 
 /// `EvalVertexView` represents a view of a vertex in a computation graph.
@@ -775,6 +1433,139 @@ impl<G: GraphViewOps> EvalVertexView<G> {
             .unwrap()
     }
 
+    /// Sends a message to the vertex with global id `to` through a mailbox. The mailbox is an
+    /// accumulator, use `state::def::messages` to keep every message or any other accumulator
+    /// to combine them, e.g. `state::def::min` for shortest paths.
+    ///
+    /// Messages are delivered to the inbox in the next superstep, this needs the state to be
+    /// created with `keep_past_state` and the mailbox to be reset with `agg_reset` in
+    /// `post_eval`. Receiving a message wakes up a vertex that voted to halt.
+    ///
+    /// # Examples
+    ///
+    /// Every vertex sends its id to its out-neighbours, which pass it on once more, so every
+    /// vertex learns which vertices reach it in one and in two hops.
+    ///
+    /// ```rust
+    /// use docbrown::core::state;
+    /// use docbrown::db::graph::Graph;
+    /// use docbrown::db::program::{GlobalEvalState, LocalState, Program, Runner, Termination};
+    /// use docbrown::db::view_api::*;
+    /// use std::collections::HashMap;
+    ///
+    /// struct Start;
+    ///
+    /// impl Program for Start {
+    ///     type Out = ();
+    ///
+    ///     fn local_eval<G: GraphViewOps>(&self, c: &LocalState<G>) {
+    ///         let mailbox = c.agg(state::def::messages::<(u64, u64)>(0));
+    ///         c.step(|v| v.send_to_out_neighbours(&mailbox, (v.global_id(), 1)))
+    ///     }
+    ///
+    ///     fn post_eval<G: GraphViewOps>(&self, c: &mut GlobalEvalState<G>) {
+    ///         let _ = c.agg_reset(state::def::messages::<(u64, u64)>(0));
+    ///         c.step(|_| true)
+    ///     }
+    ///
+    ///     fn produce_output<G: GraphViewOps>(&self, _g: &G, _gs: &GlobalEvalState<G>) {}
+    /// }
+    ///
+    /// struct Forward;
+    ///
+    /// impl Program for Forward {
+    ///     type Out = ();
+    ///
+    ///     fn local_eval<G: GraphViewOps>(&self, c: &LocalState<G>) {
+    ///         let mailbox = c.agg(state::def::messages::<(u64, u64)>(0));
+    ///         let received = c.agg(state::def::hash_set::<(u64, u64)>(1));
+    ///         c.step(|v| {
+    ///             for (origin, hops) in v.inbox(&mailbox) {
+    ///                 v.update(&received, (origin, hops));
+    ///                 if hops < 2 {
+    ///                     v.send_to_out_neighbours(&mailbox, (origin, hops + 1));
+    ///                 }
+    ///             }
+    ///             v.vote_to_halt();
+    ///         })
+    ///     }
+    ///
+    ///     fn post_eval<G: GraphViewOps>(&self, c: &mut GlobalEvalState<G>) {
+    ///         let _ = c.agg_reset(state::def::messages::<(u64, u64)>(0));
+    ///         let _ = c.agg(state::def::hash_set::<(u64, u64)>(1));
+    ///         c.step(|_| true)
+    ///     }
+    ///
+    ///     fn produce_output<G: GraphViewOps>(&self, _g: &G, _gs: &GlobalEvalState<G>) {}
+    /// }
+    ///
+    /// // odd vertices are in shard 1 and even ones in shard 0, so every message changes shard
+    /// let g = Graph::new(2);
+    /// for (src, dst) in [(1, 2), (2, 3), (3, 4), (1, 4)] {
+    ///     g.add_edge(0, src, dst, &vec![], None).unwrap();
+    /// }
+    ///
+    /// let mut gs = GlobalEvalState::new(g.clone(), true);
+    /// let report = Runner::new().init(&Start).step(&Forward).run(&g, &mut gs).unwrap();
+    /// assert_eq!(report.termination, Termination::AllHalted);
+    ///
+    /// let mut received = HashMap::new();
+    /// for shard in 0..2 {
+    ///     let inboxes = state::def::hash_set::<(u64, u64)>(1);
+    ///     gs.fold_state(&inboxes, shard, &mut received, |res, v_id, messages| {
+    ///         let mut messages = messages.into_iter().collect::<Vec<_>>();
+    ///         messages.sort();
+    ///         res.insert(*v_id, messages);
+    ///         res
+    ///     });
+    /// }
+    /// assert_eq!(received[&2], vec![(1, 1)]);
+    /// assert_eq!(received[&3], vec![(1, 2), (2, 1)]);
+    /// assert_eq!(received[&4], vec![(1, 1), (2, 2), (3, 1)]);
+    /// assert!(!received.contains_key(&1));
+    /// ```
+    pub fn send<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+        &self,
+        mailbox: &AggRef<A, IN, OUT, ACC>,
+        to: u64,
+        msg: IN,
+    ) where
+        A: StateType,
+    {
+        let AggRef(agg) = mailbox;
+        self.state
+            .borrow_mut()
+            .accumulate_into(self.ss, to as usize, msg, agg)
+    }
+
+    /// Sends a copy of the message to every out-neighbour of the vertex, see `send`.
+    pub fn send_to_out_neighbours<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+        &self,
+        mailbox: &AggRef<A, IN, OUT, ACC>,
+        msg: IN,
+    ) where
+        A: StateType,
+        IN: Clone,
+    {
+        for n in self.vv.out_neighbours() {
+            self.send(mailbox, n.id(), msg.clone());
+        }
+    }
+
+    /// Returns the messages sent to the vertex in the previous superstep. For
+    /// `state::def::messages` this is the list of messages, for other accumulators it is the
+    /// combined message, or the accumulator's zero if nothing was received.
+    pub fn inbox<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+        &self,
+        mailbox: &AggRef<A, IN, OUT, ACC>,
+    ) -> OUT
+    where
+        A: StateType,
+        OUT: Debug,
+    {
+        self.read_prev(mailbox)
+    }
+
     /// Create a new `EvalVertexView` from the given super-step counter, `WindowedVertex` and
     /// `ShuffleComputeState`.
     ///
@@ -788,7 +1579,26 @@ impl<G: GraphViewOps> EvalVertexView<G> {
     ///
     /// A new `EvalVertexView`.
     pub fn new(ss: usize, vv: VertexView<G>, state: Rc<RefCell<ShuffleComputeState<CS>>>) -> Self {
-        Self { ss, vv, state }
+        Self {
+            ss,
+            vv,
+            state,
+            halted: None,
+        }
+    }
+
+    fn with_halted(mut self, halted: Rc<RefCell<FxHashSet<u64>>>) -> Self {
+        self.halted = Some(halted);
+        self
+    }
+
+    /// Votes to halt the vertex. A halted vertex is left out of the next steps until one of its
+    /// accumulators is updated, e.g. by a neighbour. Only has an effect in `local_eval` and only
+    /// for the vertex being evaluated, not for its neighbours.
+    pub fn vote_to_halt(&self) {
+        if let Some(halted) = &self.halted {
+            halted.borrow_mut().insert(self.global_id());
+        }
     }
 
     /// Obtain the global id of the vertex.
@@ -796,6 +1606,11 @@ impl<G: GraphViewOps> EvalVertexView<G> {
         self.vv.id()
     }
 
+    /// Returns the graph view the vertex is evaluated against.
+    pub fn graph(&self) -> &G {
+        &self.vv.graph
+    }
+
     pub fn out_degree(&self) -> usize {
         self.vv.out_degree()
     }
@@ -832,6 +1647,213 @@ impl<G: GraphViewOps> EvalVertexView<G> {
             .iter()
             .map(move |vv| EvalVertexView::new(self.ss, vv, self.state.clone()))
     }
+
+    /// Return an iterator over the outbound edges of this vertex.
+    ///
+    /// Each edge is returned as an `EvalEdgeView`, which gives access to the edge's properties and
+    /// updates and can be used to read and update the edge's state.
+    pub fn out_edges(&self) -> impl Iterator<Item = EvalEdgeView<G>> + '_ {
+        self.vv
+            .out_edges()
+            .map(move |ev| EvalEdgeView::new(self.ss, ev, self.state.clone()))
+    }
+
+    /// Return an iterator over the inbound edges of this vertex, see `out_edges`.
+    pub fn in_edges(&self) -> impl Iterator<Item = EvalEdgeView<G>> + '_ {
+        self.vv
+            .in_edges()
+            .map(move |ev| EvalEdgeView::new(self.ss, ev, self.state.clone()))
+    }
+
+    /// Return an iterator over the edges of this vertex (inbound and outbound), see `out_edges`.
+    pub fn edges(&self) -> impl Iterator<Item = EvalEdgeView<G>> + '_ {
+        self.vv
+            .edges()
+            .map(move |ev| EvalEdgeView::new(self.ss, ev, self.state.clone()))
+    }
+}
+
+/// `EvalEdgeView` represents a view of an edge in a computation graph.
+///
+/// Edge state is kept apart from vertex state: it is keyed by the source and destination of the
+/// edge, so all the updates of an exploded edge share the same state. It is merged by the same
+/// `agg` calls in `post_eval` as vertex state, but updating it doesn't activate any vertex. Only
+/// the shards of the source and destination keep the state of an edge, and only the edges
+/// updated since the last merge are sent to them.
+pub struct EvalEdgeView<G: GraphViewOps> {
+    ss: usize,
+    ev: EdgeView<G>,
+    state: Rc<RefCell<ShuffleComputeState<CS>>>,
+}
+
+impl<G: GraphViewOps> EvalEdgeView<G> {
+    /// Create a new `EvalEdgeView` from the given super-step counter, `EdgeView` and
+    /// `ShuffleComputeState`.
+    pub fn new(ss: usize, ev: EdgeView<G>, state: Rc<RefCell<ShuffleComputeState<CS>>>) -> Self {
+        Self { ss, ev, state }
+    }
+
+    /// Returns the underlying `EdgeView`.
+    pub fn edge(&self) -> &EdgeView<G> {
+        &self.ev
+    }
+
+    /// Returns the source vertex of the edge, which can be used to read and update its state.
+    pub fn src(&self) -> EvalVertexView<G> {
+        EvalVertexView::new(self.ss, self.ev.src(), self.state.clone())
+    }
+
+    /// Returns the destination vertex of the edge, which can be used to read and update its
+    /// state.
+    pub fn dst(&self) -> EvalVertexView<G> {
+        EvalVertexView::new(self.ss, self.ev.dst(), self.state.clone())
+    }
+
+    /// Returns the value of the property `name` of the edge.
+    pub fn property(&self, name: String, include_static: bool) -> Option<Prop> {
+        self.ev.property(name, include_static)
+    }
+
+    /// Returns the history of the property `name` of the edge as `(time, value)` pairs.
+    pub fn property_history(&self, name: String) -> Vec<(i64, Prop)> {
+        self.ev.property_history(name)
+    }
+
+    /// Returns every update of the edge as a separate edge, see `time`.
+    pub fn explode(&self) -> impl Iterator<Item = EvalEdgeView<G>> + '_ {
+        self.ev
+            .explode()
+            .map(move |ev| EvalEdgeView::new(self.ss, ev, self.state.clone()))
+    }
+
+    /// Returns the time of the update for an exploded edge, `None` otherwise.
+    pub fn time(&self) -> Option<i64> {
+        self.ev.time()
+    }
+
+    /// Update the edge state with the provided input value using the given accumulator.
+    pub fn update<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+        &self,
+        agg_r: &AggRef<A, IN, OUT, ACC>,
+        a: IN,
+    ) where
+        A: StateType,
+    {
+        let AggRef(agg) = agg_r;
+        let edge = self.ev.as_ref();
+        self.state.borrow_mut().accumulate_into_edge(
+            self.ss,
+            edge.src_g_id,
+            edge.dst_g_id,
+            edge.layer_id,
+            a,
+            agg,
+        )
+    }
+
+    /// Read the current value of the edge state using the given accumulator.
+    /// Returns a default value if the value is not present.
+    pub fn read<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+        &self,
+        agg_r: &AggRef<A, IN, OUT, ACC>,
+    ) -> OUT
+    where
+        A: StateType,
+        OUT: Debug,
+    {
+        self.read_at(self.ss, agg_r)
+    }
+
+    /// Read the previous value of the edge state using the given accumulator.
+    pub fn read_prev<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+        &self,
+        agg_r: &AggRef<A, IN, OUT, ACC>,
+    ) -> OUT
+    where
+        A: StateType,
+        OUT: Debug,
+    {
+        self.read_at(self.ss + 1, agg_r)
+    }
+
+    fn read_at<A, IN, OUT, ACC: Accumulator<A, IN, OUT>>(
+        &self,
+        ss: usize,
+        agg_r: &AggRef<A, IN, OUT, ACC>,
+    ) -> OUT
+    where
+        A: StateType,
+        OUT: Debug,
+    {
+        let edge = self.ev.as_ref();
+        self.state
+            .borrow()
+            .read_edge(ss, edge.src_g_id, edge.dst_g_id, edge.layer_id, &agg_r.0)
+            .unwrap_or(ACC::finish(&ACC::zero()))
+    }
+}
+
+/// The statistics of one shard in a superstep.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardStats {
+    pub shard: usize,
+    /// Time spent in `local_eval`
+    pub local_eval: Duration,
+    /// The number of vertices evaluated by `local_eval`
+    pub active_vertices: usize,
+    /// The number of accumulator updates made by `local_eval`
+    pub updates: usize,
+    /// The number of vertices whose state changed, they are active in the next superstep
+    pub changed_keys: usize,
+}
+
+/// The statistics of a superstep, passed to the observers of a `GlobalEvalState`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuperstepStats {
+    /// The evaluation step of the state when the superstep started
+    pub ss: usize,
+    pub shards: Vec<ShardStats>,
+    /// Time spent in `post_eval`, merging the shard states
+    pub post_eval: Duration,
+}
+
+impl SuperstepStats {
+    pub fn active_vertices(&self) -> usize {
+        self.shards.iter().map(|s| s.active_vertices).sum()
+    }
+
+    pub fn updates(&self) -> usize {
+        self.shards.iter().map(|s| s.updates).sum()
+    }
+
+    pub fn changed_keys(&self) -> usize {
+        self.shards.iter().map(|s| s.changed_keys).sum()
+    }
+
+    /// Returns the shard that took the longest in `local_eval`.
+    pub fn slowest_shard(&self) -> Option<&ShardStats> {
+        self.shards.iter().max_by_key(|s| s.local_eval)
+    }
+}
+
+/// Observes the supersteps run on a `GlobalEvalState`, see `GlobalEvalState::observe`.
+pub trait SuperstepObserver: Send + Sync {
+    fn on_superstep(&self, stats: &SuperstepStats);
+}
+
+impl<F: Fn(&SuperstepStats) + Send + Sync> SuperstepObserver for F {
+    fn on_superstep(&self, stats: &SuperstepStats) {
+        self(stats)
+    }
+}
+
+#[derive(Default)]
+struct Observers(Vec<Box<dyn SuperstepObserver>>);
+
+impl Debug for Observers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Observers({})", self.0.len())
+    }
 }
 
 /// Represents a program that can be executed on a graph. We use this to run algorithms on graphs.
@@ -861,35 +1883,73 @@ pub trait Program {
     {
         let next_vertex_set = c.next_vertex_set.clone();
         let graph = g.clone();
+        let ss = c.ss;
 
-        (0..g.num_shards())
+        let results: Vec<_> = (0..g.num_shards())
             .collect_vec()
             .par_iter()
-            .for_each(|shard| {
+            .map(|shard| {
                 let i = *shard;
                 let local_state = c.states[i].clone();
                 // take control of the actual state
                 let local_state = &mut local_state
                     .try_write()
                     .expect("STATE LOCK SHOULD NOT BE CONTENDED");
-                let own_state = (local_state).take().unwrap();
+                let mut own_state = (local_state).take().unwrap();
+                own_state.take_updates();
 
+                let start = Instant::now();
                 let rc_state = LocalState::new(
                     c.ss,
                     i,
                     graph.clone(),
                     Rc::new(RefCell::new(own_state)),
                     next_vertex_set.as_ref().map(|v| v[i].clone()),
+                    c.accumulators.clone(),
                 );
 
                 self.local_eval(&rc_state);
 
                 // put back the state
-                **local_state = Some(rc_state.consume());
-            });
+                let (mut own_state, halted, active_vertices, error) = rc_state.consume();
+                let stats = ShardStats {
+                    shard: i,
+                    local_eval: start.elapsed(),
+                    active_vertices,
+                    updates: own_state.take_updates(),
+                    changed_keys: 0,
+                };
+                **local_state = Some(own_state);
+                (halted, stats, error)
+            })
+            .collect();
+        let mut shards = Vec::with_capacity(results.len());
+        c.halted.clear();
+        for (halted, stats, error) in results {
+            c.halted.push(halted);
+            shards.push(stats);
+            if let Some(e) = error {
+                c.error.get_or_insert(e);
+            }
+        }
 
         // here we merge all the accumulators
+        let start = Instant::now();
+        c.changed_keys.clear();
         self.post_eval(c);
+        let post_eval = start.elapsed();
+
+        if !c.observers.0.is_empty() {
+            for (stats, changed_keys) in shards.iter_mut().zip(&c.changed_keys) {
+                stats.changed_keys = *changed_keys;
+            }
+            let stats = SuperstepStats {
+                ss,
+                shards,
+                post_eval,
+            };
+            c.observers.0.iter().for_each(|o| o.on_superstep(&stats));
+        }
     }
 
     /// Runs the program on a graph, with a given window and iteration count.
@@ -907,7 +1967,8 @@ pub trait Program {
     ///
     /// # Panics
     ///
-    /// Panics if the state lock is contended.
+    /// Panics if the state lock is contended, or if the program uses an accumulator id with two
+    /// accumulator types, see `AccumulatorRegistry::check`.
     fn run<G: GraphViewOps>(
         &self,
         g: &G,
@@ -915,21 +1976,48 @@ pub trait Program {
         iter_count: usize,
     ) -> GlobalEvalState<G>
     where
-        Self: Sync,
+        Self: Sync + Sized,
     {
         let mut c = GlobalEvalState::new(g.clone(), keep_past_state);
-
-        let mut i = 0;
-        while c.do_loop() && i < iter_count {
-            self.run_step(g, &mut c);
-            if c.keep_past_state {
-                c.ss += 1;
-            }
-            i += 1;
-        }
+        Runner::new()
+            .step(self)
+            .max_supersteps(iter_count)
+            .run(g, &mut c)
+            .expect("the program uses its accumulators with one type each");
         c
     }
 
+    /// Runs the program like `run` but stops between supersteps once `budget` runs out.
+    ///
+    /// # Returns
+    ///
+    /// The state, partial if the run was stopped, together with the report of the run. A run
+    /// stopped by the budget ends with `Termination::Cancelled` or
+    /// `Termination::DeadlineExceeded`.
+    ///
+    /// # Panics
+    ///
+    /// Panics like `run`.
+    fn run_with_budget<G: GraphViewOps>(
+        &self,
+        g: &G,
+        keep_past_state: bool,
+        iter_count: usize,
+        budget: &Budget,
+    ) -> (GlobalEvalState<G>, RunReport)
+    where
+        Self: Sync + Sized,
+    {
+        let mut c = GlobalEvalState::new(g.clone(), keep_past_state);
+        let report = Runner::new()
+            .step(self)
+            .max_supersteps(iter_count)
+            .budget(budget)
+            .run(g, &mut c)
+            .expect("the program uses its accumulators with one type each");
+        (c, report)
+    }
+
     /// Produces the output of the program for a given graph and global evaluation state.
     ///
     /// # Arguments
@@ -950,3 +2038,495 @@ pub trait Program {
     where
         Self: Sync;
 }
+
+/// An object safe view of a `Program`, so programs of different types can be chained in a
+/// `Runner`. It is implemented for every `Program`.
+pub trait ProgramStep<G: GraphViewOps>: Sync {
+    /// Runs a single step of the program, see `Program::run_step`.
+    fn eval_step(&self, g: &G, c: &mut GlobalEvalState<G>);
+}
+
+impl<G: GraphViewOps, P: Program + Sync> ProgramStep<G> for P {
+    fn eval_step(&self, g: &G, c: &mut GlobalEvalState<G>) {
+        self.run_step(g, c)
+    }
+}
+
+/// Why a `Runner` stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    /// The convergence predicate returned true
+    Converged,
+    /// No vertex is active anymore, every vertex voted to halt and received no updates
+    AllHalted,
+    /// The maximum number of supersteps was reached
+    MaxSupersteps,
+    /// The cancellation token of the budget was cancelled
+    Cancelled,
+    /// The deadline of the budget has passed
+    DeadlineExceeded,
+}
+
+impl Termination {
+    /// Returns the error for a run that was stopped by its budget.
+    pub fn error(self) -> Option<GraphError> {
+        match self {
+            Termination::Cancelled => Some(GraphError::Cancelled),
+            Termination::DeadlineExceeded => Some(GraphError::DeadlineExceeded),
+            _ => None,
+        }
+    }
+}
+
+/// The outcome of a `Runner::run`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunReport {
+    /// The number of supersteps that ran, not counting the init program
+    pub supersteps: usize,
+    pub termination: Termination,
+}
+
+type ConvergencePredicate<'a, G> = Box<dyn Fn(&GlobalEvalState<G>) -> bool + 'a>;
+
+/// Runs a pipeline of programs until it converges.
+///
+/// The init program runs once, then every superstep runs the step programs in order, until the
+/// convergence predicate holds, every vertex has halted or the maximum number of supersteps is
+/// reached.
+///
+/// # Examples
+///
+/// ```rust
+/// use docbrown::core::state;
+/// use docbrown::db::graph::Graph;
+/// use docbrown::db::program::{GlobalEvalState, LocalState, Program, Runner, Termination};
+/// use docbrown::db::view_api::*;
+///
+/// /// Spreads the smallest vertex id, vertices halt once they stop changing.
+/// struct MinId;
+///
+/// impl Program for MinId {
+///     type Out = ();
+///
+///     fn local_eval<G: GraphViewOps>(&self, c: &LocalState<G>) {
+///         let min = c.agg(state::def::min::<u64>(0));
+///         c.step(|v| {
+///             v.update(&min, v.global_id());
+///             let my_min = v.read(&min);
+///             for n in v.neighbours() {
+///                 n.update(&min, my_min);
+///             }
+///             v.vote_to_halt();
+///         })
+///     }
+///
+///     fn post_eval<G: GraphViewOps>(&self, c: &mut GlobalEvalState<G>) {
+///         let _ = c.agg(state::def::min::<u64>(0));
+///         c.step(|_| true)
+///     }
+///
+///     fn produce_output<G: GraphViewOps>(&self, _g: &G, _gs: &GlobalEvalState<G>) {}
+/// }
+///
+/// let g = Graph::new(2);
+/// for (src, dst) in [(1, 2), (2, 3), (3, 4)] {
+///     g.add_edge(0, src, dst, &vec![], None).unwrap();
+/// }
+///
+/// let mut gs = GlobalEvalState::new(g.clone(), true);
+/// let report = Runner::new().step(&MinId).run(&g, &mut gs).unwrap();
+///
+/// assert_eq!(report.termination, Termination::AllHalted);
+/// assert!(report.supersteps <= 5);
+///
+/// let mut gs = GlobalEvalState::new(g.clone(), true);
+/// let report = Runner::new().step(&MinId).max_supersteps(1).run(&g, &mut gs).unwrap();
+/// assert_eq!(report.termination, Termination::MaxSupersteps);
+/// assert_eq!(report.supersteps, 1);
+/// ```
+pub struct Runner<'a, G: GraphViewOps> {
+    init: Option<&'a dyn ProgramStep<G>>,
+    steps: Vec<&'a dyn ProgramStep<G>>,
+    converged: Option<ConvergencePredicate<'a, G>>,
+    max_supersteps: usize,
+    checkpoint: Option<(usize, PathBuf)>,
+    budget: Budget,
+}
+
+impl<'a, G: GraphViewOps> Default for Runner<'a, G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, G: GraphViewOps> Runner<'a, G> {
+    /// Creates a runner with no programs and no limit on the number of supersteps.
+    pub fn new() -> Self {
+        Self {
+            init: None,
+            steps: Vec::new(),
+            converged: None,
+            max_supersteps: usize::MAX,
+            checkpoint: None,
+            budget: Budget::default(),
+        }
+    }
+
+    /// Sets the program that runs once before the first superstep.
+    pub fn init(mut self, program: &'a dyn ProgramStep<G>) -> Self {
+        self.init = Some(program);
+        self
+    }
+
+    /// Adds a program to every superstep, programs run in the order they are added.
+    pub fn step(mut self, program: &'a dyn ProgramStep<G>) -> Self {
+        self.steps.push(program);
+        self
+    }
+
+    /// Sets the convergence predicate, it is checked after every superstep and usually reads
+    /// global accumulators.
+    pub fn converged_when<F>(mut self, converged: F) -> Self
+    where
+        F: Fn(&GlobalEvalState<G>) -> bool + 'a,
+    {
+        self.converged = Some(Box::new(converged));
+        self
+    }
+
+    /// Sets the maximum number of supersteps.
+    pub fn max_supersteps(mut self, max_supersteps: usize) -> Self {
+        self.max_supersteps = max_supersteps;
+        self
+    }
+
+    /// Stops the run between supersteps once `budget` runs out, the state keeps the results of
+    /// the supersteps that completed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use docbrown::core::cancel::Budget;
+    /// use docbrown::core::state;
+    /// use docbrown::db::graph::Graph;
+    /// use docbrown::db::program::{GlobalEvalState, LocalState, Program, Runner, Termination};
+    /// use docbrown::db::view_api::*;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// /// Counts its supersteps, the second one lasts until `slow_until`.
+    /// struct Slow {
+    ///     slow_until: Instant,
+    /// }
+    ///
+    /// impl Program for Slow {
+    ///     type Out = ();
+    ///
+    ///     fn local_eval<G: GraphViewOps>(&self, c: &LocalState<G>) {
+    ///         let steps = c.agg(state::def::max::<u64>(0));
+    ///         c.step(|v| v.update(&steps, v.read(&steps) + 1))
+    ///     }
+    ///
+    ///     fn post_eval<G: GraphViewOps>(&self, c: &mut GlobalEvalState<G>) {
+    ///         let _ = c.agg(state::def::max::<u64>(0));
+    ///         if c.fold_state(&state::def::max::<u64>(0), 1, 0, |_, _, n| n) == 2 {
+    ///             std::thread::sleep(self.slow_until.saturating_duration_since(Instant::now()));
+    ///         }
+    ///         c.step(|_| true)
+    ///     }
+    ///
+    ///     fn produce_output<G: GraphViewOps>(&self, _g: &G, _gs: &GlobalEvalState<G>) {}
+    /// }
+    ///
+    /// let g = Graph::new(2);
+    /// g.add_vertex(0, 1, &vec![]).unwrap();
+    ///
+    /// let deadline = Instant::now() + Duration::from_millis(200);
+    /// let slow = Slow { slow_until: deadline };
+    /// let mut gs = GlobalEvalState::new(g.clone(), true);
+    /// let report = Runner::new()
+    ///     .step(&slow)
+    ///     .max_supersteps(10)
+    ///     .budget(&Budget::default().with_deadline(deadline))
+    ///     .run(&g, &mut gs)
+    ///     .unwrap();
+    /// assert_eq!(report.termination, Termination::DeadlineExceeded);
+    /// assert_eq!(report.supersteps, 2);
+    /// // the state of the completed supersteps is kept
+    /// assert_eq!(gs.fold_state(&state::def::max::<u64>(0), 1, 0, |_, _, n| n), 2);
+    ///
+    /// // the maximum number of supersteps stops the run before the budget does
+    /// let slow = Slow { slow_until: Instant::now() };
+    /// let mut gs = GlobalEvalState::new(g.clone(), true);
+    /// let report = Runner::new()
+    ///     .step(&slow)
+    ///     .max_supersteps(3)
+    ///     .budget(&Budget::default().with_timeout(Duration::from_secs(60)))
+    ///     .run(&g, &mut gs)
+    ///     .unwrap();
+    /// assert_eq!(report.termination, Termination::MaxSupersteps);
+    /// assert_eq!(report.supersteps, 3);
+    /// ```
+    pub fn budget(mut self, budget: &Budget) -> Self {
+        self.budget = budget.clone();
+        self
+    }
+
+    /// Writes a checkpoint to `path` after every `supersteps` supersteps, a run can be resumed
+    /// from it with `resume`. The file is replaced on every checkpoint.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `supersteps` is 0.
+    pub fn checkpoint_every<P: AsRef<Path>>(mut self, supersteps: usize, path: P) -> Self {
+        assert!(
+            supersteps > 0,
+            "checkpoints need at least one superstep in between"
+        );
+        self.checkpoint = Some((supersteps, path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Runs the programs on `g` using and updating the state `c`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::AccumulatorTypeMismatch` or `GraphError::ReservedAccumulatorId` if
+    /// a program uses an accumulator id with another type than before, see
+    /// `AccumulatorRegistry::check`, the failing program changes no state. Returns
+    /// an error if a checkpoint can't be written, see `GlobalEvalState::write_checkpoint`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use docbrown::core::state;
+    /// use docbrown::core::tgraph_shard::errors::GraphError;
+    /// use docbrown::db::graph::Graph;
+    /// use docbrown::db::program::{GlobalEvalState, LocalState, Program, Runner};
+    /// use docbrown::db::view_api::*;
+    ///
+    /// struct Degree;
+    ///
+    /// impl Program for Degree {
+    ///     type Out = ();
+    ///
+    ///     fn local_eval<G: GraphViewOps>(&self, c: &LocalState<G>) {
+    ///         let degree = c.agg(state::def::sum::<u64>(0));
+    ///         c.step(|v| v.update(&degree, v.out_degree() as u64))
+    ///     }
+    ///
+    ///     fn post_eval<G: GraphViewOps>(&self, c: &mut GlobalEvalState<G>) {
+    ///         let _ = c.agg(state::def::sum::<u64>(0));
+    ///         c.step(|_| true)
+    ///     }
+    ///
+    ///     fn produce_output<G: GraphViewOps>(&self, _g: &G, _gs: &GlobalEvalState<G>) {}
+    /// }
+    ///
+    /// /// Picked the same id for a different accumulator.
+    /// struct MaxDegree;
+    ///
+    /// impl Program for MaxDegree {
+    ///     type Out = ();
+    ///
+    ///     fn local_eval<G: GraphViewOps>(&self, c: &LocalState<G>) {
+    ///         let degree = c.agg(state::def::max::<u64>(0));
+    ///         c.step(|v| v.update(&degree, v.out_degree() as u64))
+    ///     }
+    ///
+    ///     fn post_eval<G: GraphViewOps>(&self, c: &mut GlobalEvalState<G>) {
+    ///         let _ = c.agg(state::def::max::<u64>(0));
+    ///         c.step(|_| true)
+    ///     }
+    ///
+    ///     fn produce_output<G: GraphViewOps>(&self, _g: &G, _gs: &GlobalEvalState<G>) {}
+    /// }
+    ///
+    /// let g = Graph::new(2);
+    /// g.add_edge(0, 1, 2, &vec![], None).unwrap();
+    ///
+    /// let mut gs = GlobalEvalState::new(g.clone(), false);
+    /// let result = Runner::new()
+    ///     .step(&Degree)
+    ///     .step(&MaxDegree)
+    ///     .max_supersteps(3)
+    ///     .run(&g, &mut gs);
+    /// assert!(matches!(
+    ///     result,
+    ///     Err(GraphError::AccumulatorTypeMismatch { name, .. }) if name == "0"
+    /// ));
+    ///
+    /// // ids from the registered range have to be registered
+    /// struct Reserved;
+    ///
+    /// impl Program for Reserved {
+    ///     type Out = ();
+    ///
+    ///     fn local_eval<G: GraphViewOps>(&self, c: &LocalState<G>) {
+    ///         let _ = c.agg(state::def::sum::<u64>(state::REGISTERED_IDS_START));
+    ///     }
+    ///
+    ///     fn post_eval<G: GraphViewOps>(&self, c: &mut GlobalEvalState<G>) {
+    ///         c.step(|_| true)
+    ///     }
+    ///
+    ///     fn produce_output<G: GraphViewOps>(&self, _g: &G, _gs: &GlobalEvalState<G>) {}
+    /// }
+    ///
+    /// let mut gs = GlobalEvalState::new(g.clone(), false);
+    /// assert_eq!(
+    ///     Runner::new().step(&Reserved).run(&g, &mut gs),
+    ///     Err(GraphError::ReservedAccumulatorId {
+    ///         id: state::REGISTERED_IDS_START
+    ///     })
+    /// );
+    /// ```
+    pub fn run(&self, g: &G, c: &mut GlobalEvalState<G>) -> Result<RunReport, GraphError> {
+        if let Some(init) = self.init {
+            init.eval_step(g, c);
+            c.check_error()?;
+        }
+        self.run_from(g, c, 0)
+    }
+
+    /// Resumes a run from the checkpoint at `path`, written by a runner with the same programs
+    /// on the same graph. The init program doesn't run again, and the supersteps in the report
+    /// and the maximum number of supersteps include the ones that ran before the checkpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the checkpoint can't be read, e.g. because it was written for a
+    /// different graph, or a new checkpoint can't be written, see
+    /// `GlobalEvalState::read_checkpoint`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use docbrown::core::state;
+    /// use docbrown::core::tgraph_shard::errors::GraphError;
+    /// use docbrown::db::graph::Graph;
+    /// use docbrown::db::program::{GlobalEvalState, LocalState, Program, Runner, Termination};
+    /// use docbrown::db::view_api::*;
+    ///
+    /// /// Counts the steps every vertex was active for.
+    /// struct Steps;
+    ///
+    /// impl Program for Steps {
+    ///     type Out = ();
+    ///
+    ///     fn local_eval<G: GraphViewOps>(&self, c: &LocalState<G>) {
+    ///         let steps = c.agg(state::def::max::<u64>(0));
+    ///         c.step(|v| v.update(&steps, v.read(&steps) + 1))
+    ///     }
+    ///
+    ///     fn post_eval<G: GraphViewOps>(&self, c: &mut GlobalEvalState<G>) {
+    ///         let _ = c.agg(state::def::max::<u64>(0));
+    ///         c.step(|_| true)
+    ///     }
+    ///
+    ///     fn produce_output<G: GraphViewOps>(&self, _g: &G, _gs: &GlobalEvalState<G>) {}
+    /// }
+    ///
+    /// let g = Graph::new(2);
+    /// g.add_edge(0, 1, 2, &vec![], None).unwrap();
+    ///
+    /// let dir = tempdir::TempDir::new("checkpoint").unwrap();
+    /// let path = dir.path().join("steps");
+    ///
+    /// // the job stops after 2 supersteps, e.g. because it was interrupted
+    /// let mut gs = GlobalEvalState::new(g.clone(), true);
+    /// let steps = state::def::max::<u64>(0);
+    /// let runner = Runner::new().step(&Steps).checkpoint_every(1, &path);
+    /// runner.max_supersteps(2).run(&g, &mut gs).unwrap();
+    ///
+    /// let runner = Runner::new().step(&Steps).max_supersteps(5);
+    /// let (gs, report) = runner.resume(&g, &path).unwrap();
+    /// assert_eq!(report.supersteps, 5);
+    /// assert_eq!(report.termination, Termination::MaxSupersteps);
+    ///
+    /// assert_eq!(gs.fold_state(&steps, 1, 0, |_, _, n| n), 5);
+    ///
+    /// // the checkpoint only fits the graph it was written for
+    /// g.add_vertex(3, 3, &vec![]).unwrap();
+    /// assert!(matches!(
+    ///     runner.resume(&g, &path),
+    ///     Err(GraphError::CheckpointGraphMismatch { .. })
+    /// ));
+    /// ```
+    pub fn resume<P: AsRef<Path>>(
+        &self,
+        g: &G,
+        path: P,
+    ) -> Result<(GlobalEvalState<G>, RunReport), GraphError> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let supersteps = bincode::deserialize_from(&mut reader)?;
+        let mut c = GlobalEvalState::read_checkpoint(g.clone(), reader)?;
+        let report = self.run_from(g, &mut c, supersteps)?;
+        Ok((c, report))
+    }
+
+    fn run_from(
+        &self,
+        g: &G,
+        c: &mut GlobalEvalState<G>,
+        mut supersteps: usize,
+    ) -> Result<RunReport, GraphError> {
+        let termination = loop {
+            if supersteps >= self.max_supersteps {
+                break Termination::MaxSupersteps;
+            }
+            match self.budget.check() {
+                Err(GraphError::Cancelled) => break Termination::Cancelled,
+                Err(_) => break Termination::DeadlineExceeded,
+                Ok(()) => {}
+            }
+            if !c.do_loop() {
+                break Termination::AllHalted;
+            }
+
+            for step in &self.steps {
+                step.eval_step(g, c);
+                c.check_error()?;
+            }
+            supersteps += 1;
+
+            if self
+                .converged
+                .as_ref()
+                .map_or(false, |converged| converged(c))
+            {
+                break Termination::Converged;
+            }
+            if c.keep_past_state {
+                c.ss += 1;
+            }
+
+            if let Some((every, path)) = &self.checkpoint {
+                if supersteps % every == 0 {
+                    self.write_checkpoint(path, supersteps, c)?;
+                }
+            }
+        };
+
+        Ok(RunReport {
+            supersteps,
+            termination,
+        })
+    }
+
+    // writes next to the checkpoint first so an interrupted write can't corrupt the last one
+    fn write_checkpoint(
+        &self,
+        path: &Path,
+        supersteps: usize,
+        c: &GlobalEvalState<G>,
+    ) -> Result<(), GraphError> {
+        let tmp = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        bincode::serialize_into(&mut writer, &supersteps)?;
+        c.write_checkpoint(&mut writer)?;
+        writer.flush()?;
+        drop(writer);
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+}
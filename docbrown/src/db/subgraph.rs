@@ -0,0 +1,565 @@
+//! Defines the `SubGraph` view, which restricts an underlying view to a set of vertices and/or
+//! a set of edges.
+//!
+//! Edges are identified by their `(src, dst)` pair, so keeping an edge keeps all its layers
+//! and updates. An edge is only part of the subgraph if both of its endpoints are.
+
+use crate::core::{
+    tgraph::{EdgeRef, VertexRef},
+    time::TimeUnit,
+    Direction, Prop,
+};
+use crate::db::view_api::internal::GraphViewInternalOps;
+use itertools::Itertools;
+use rustc_hash::FxHashSet;
+use std::{collections::HashMap, ops::Range, sync::Arc};
+
+#[derive(Debug, Clone)]
+pub struct SubGraph<G: GraphViewInternalOps> {
+    /// The underlying `Graph` object.
+    pub graph: G,
+    /// The global ids of the vertices to keep, all vertices are kept if `None`
+    vertices: Option<Arc<FxHashSet<u64>>>,
+    /// The `(src, dst)` pairs of the edges to keep, all edges are kept if `None`
+    edges: Option<Arc<FxHashSet<(u64, u64)>>>,
+}
+
+impl<G: GraphViewInternalOps> SubGraph<G> {
+    pub fn new(
+        graph: G,
+        vertices: Option<FxHashSet<u64>>,
+        edges: Option<FxHashSet<(u64, u64)>>,
+    ) -> Self {
+        Self {
+            graph,
+            vertices: vertices.map(Arc::new),
+            edges: edges.map(Arc::new),
+        }
+    }
+
+    fn keep_vertex(&self, v: u64) -> bool {
+        self.vertices.as_ref().map_or(true, |vs| vs.contains(&v))
+    }
+
+    fn filter_vertex_refs(
+        &self,
+        iter: Box<dyn Iterator<Item = VertexRef> + Send>,
+    ) -> Box<dyn Iterator<Item = VertexRef> + Send> {
+        match self.vertices.clone() {
+            None => iter,
+            Some(vs) => Box::new(iter.filter(move |v| vs.contains(&v.g_id))),
+        }
+    }
+
+    fn filter_vertex_ids(
+        &self,
+        iter: Box<dyn Iterator<Item = u64> + Send>,
+    ) -> Box<dyn Iterator<Item = u64> + Send> {
+        match self.vertices.clone() {
+            None => iter,
+            Some(vs) => Box::new(iter.filter(move |v| vs.contains(v))),
+        }
+    }
+
+    fn filter_edges(
+        &self,
+        iter: Box<dyn Iterator<Item = EdgeRef> + Send>,
+    ) -> Box<dyn Iterator<Item = EdgeRef> + Send> {
+        if self.vertices.is_none() && self.edges.is_none() {
+            return iter;
+        }
+        let vertices = self.vertices.clone();
+        let edges = self.edges.clone();
+        Box::new(iter.filter(move |e| keep_edge(&vertices, &edges, e.src_g_id, e.dst_g_id)))
+    }
+
+    fn keep_edge(&self, src: u64, dst: u64) -> bool {
+        keep_edge(&self.vertices, &self.edges, src, dst)
+    }
+
+    /// Distinct neighbours of `v` reachable through the edges of the subgraph
+    fn neighbour_ids_of(
+        v: VertexRef,
+        edges: Box<dyn Iterator<Item = EdgeRef> + Send>,
+    ) -> Box<dyn Iterator<Item = u64> + Send> {
+        Box::new(
+            edges
+                .map(move |e| {
+                    if e.src_g_id == v.g_id {
+                        e.dst_g_id
+                    } else {
+                        e.src_g_id
+                    }
+                })
+                .unique(),
+        )
+    }
+}
+
+fn keep_edge(
+    vertices: &Option<Arc<FxHashSet<u64>>>,
+    edges: &Option<Arc<FxHashSet<(u64, u64)>>>,
+    src: u64,
+    dst: u64,
+) -> bool {
+    vertices
+        .as_ref()
+        .map_or(true, |vs| vs.contains(&src) && vs.contains(&dst))
+        && edges.as_ref().map_or(true, |es| es.contains(&(src, dst)))
+}
+
+impl<G: GraphViewInternalOps> GraphViewInternalOps for SubGraph<G> {
+    fn get_layer(&self, key: Option<&str>) -> Option<usize> {
+        self.graph.get_layer(key)
+    }
+
+    fn view_start(&self) -> Option<i64> {
+        self.graph.view_start()
+    }
+
+    fn view_end(&self) -> Option<i64> {
+        self.graph.view_end()
+    }
+
+    fn view_time_unit(&self) -> TimeUnit {
+        self.graph.view_time_unit()
+    }
+
+    fn earliest_time_global(&self) -> Option<i64> {
+        self.graph.earliest_time_global()
+    }
+
+    fn earliest_time_window(&self, t_start: i64, t_end: i64) -> Option<i64> {
+        self.graph.earliest_time_window(t_start, t_end)
+    }
+
+    fn latest_time_global(&self) -> Option<i64> {
+        self.graph.latest_time_global()
+    }
+
+    fn latest_time_window(&self, t_start: i64, t_end: i64) -> Option<i64> {
+        self.graph.latest_time_window(t_start, t_end)
+    }
+
+    fn vertices_len(&self) -> usize {
+        match self.vertices {
+            None => self.graph.vertices_len(),
+            Some(_) => self.vertex_ids().count(),
+        }
+    }
+
+    fn vertices_len_window(&self, t_start: i64, t_end: i64) -> usize {
+        match self.vertices {
+            None => self.graph.vertices_len_window(t_start, t_end),
+            Some(_) => self.vertex_ids_window(t_start, t_end).count(),
+        }
+    }
+
+    fn edges_len(&self, layer: Option<usize>) -> usize {
+        self.edge_refs(layer).count()
+    }
+
+    fn edges_len_window(&self, t_start: i64, t_end: i64, layer: Option<usize>) -> usize {
+        self.edge_refs_window(t_start, t_end, layer).count()
+    }
+
+    fn has_edge_ref(&self, src: VertexRef, dst: VertexRef, layer: usize) -> bool {
+        self.keep_edge(src.g_id, dst.g_id) && self.graph.has_edge_ref(src, dst, layer)
+    }
+
+    fn has_edge_ref_window(
+        &self,
+        src: VertexRef,
+        dst: VertexRef,
+        t_start: i64,
+        t_end: i64,
+        layer: usize,
+    ) -> bool {
+        self.keep_edge(src.g_id, dst.g_id)
+            && self
+                .graph
+                .has_edge_ref_window(src, dst, t_start, t_end, layer)
+    }
+
+    fn has_vertex_ref(&self, v: VertexRef) -> bool {
+        self.keep_vertex(v.g_id) && self.graph.has_vertex_ref(v)
+    }
+
+    fn has_vertex_ref_window(&self, v: VertexRef, t_start: i64, t_end: i64) -> bool {
+        self.keep_vertex(v.g_id) && self.graph.has_vertex_ref_window(v, t_start, t_end)
+    }
+
+    fn degree(&self, v: VertexRef, d: Direction, layer: Option<usize>) -> usize {
+        self.neighbours_ids(v, d, layer).count()
+    }
+
+    fn degree_window(
+        &self,
+        v: VertexRef,
+        t_start: i64,
+        t_end: i64,
+        d: Direction,
+        layer: Option<usize>,
+    ) -> usize {
+        self.neighbours_ids_window(v, t_start, t_end, d, layer)
+            .count()
+    }
+
+    fn vertex_id_by_name(&self, name: &str) -> Option<u64> {
+        self.graph.vertex_id_by_name(name)
+    }
+
+    fn vertex_ref(&self, v: u64) -> Option<VertexRef> {
+        self.graph
+            .vertex_ref(v)
+            .filter(|v| self.keep_vertex(v.g_id))
+    }
+
+    fn vertex_ref_window(&self, v: u64, t_start: i64, t_end: i64) -> Option<VertexRef> {
+        self.graph
+            .vertex_ref_window(v, t_start, t_end)
+            .filter(|v| self.keep_vertex(v.g_id))
+    }
+
+    fn vertex_earliest_time(&self, v: VertexRef) -> Option<i64> {
+        self.graph.vertex_earliest_time(v)
+    }
+
+    fn vertex_earliest_time_window(&self, v: VertexRef, t_start: i64, t_end: i64) -> Option<i64> {
+        self.graph.vertex_earliest_time_window(v, t_start, t_end)
+    }
+
+    fn vertex_latest_time(&self, v: VertexRef) -> Option<i64> {
+        self.graph.vertex_latest_time(v)
+    }
+
+    fn vertex_latest_time_window(&self, v: VertexRef, t_start: i64, t_end: i64) -> Option<i64> {
+        self.graph.vertex_latest_time_window(v, t_start, t_end)
+    }
+
+    fn vertex_ids(&self) -> Box<dyn Iterator<Item = u64> + Send> {
+        self.filter_vertex_ids(self.graph.vertex_ids())
+    }
+
+    fn vertex_ids_window(&self, t_start: i64, t_end: i64) -> Box<dyn Iterator<Item = u64> + Send> {
+        self.filter_vertex_ids(self.graph.vertex_ids_window(t_start, t_end))
+    }
+
+    fn vertex_refs(&self) -> Box<dyn Iterator<Item = VertexRef> + Send> {
+        self.filter_vertex_refs(self.graph.vertex_refs())
+    }
+
+    fn vertex_refs_window(
+        &self,
+        t_start: i64,
+        t_end: i64,
+    ) -> Box<dyn Iterator<Item = VertexRef> + Send> {
+        self.filter_vertex_refs(self.graph.vertex_refs_window(t_start, t_end))
+    }
+
+    fn vertex_refs_shard(&self, shard: usize) -> Box<dyn Iterator<Item = VertexRef> + Send> {
+        self.filter_vertex_refs(self.graph.vertex_refs_shard(shard))
+    }
+
+    fn vertex_refs_window_shard(
+        &self,
+        shard: usize,
+        t_start: i64,
+        t_end: i64,
+    ) -> Box<dyn Iterator<Item = VertexRef> + Send> {
+        self.filter_vertex_refs(self.graph.vertex_refs_window_shard(shard, t_start, t_end))
+    }
+
+    fn edge_ref(&self, src: VertexRef, dst: VertexRef, layer: usize) -> Option<EdgeRef> {
+        self.keep_edge(src.g_id, dst.g_id)
+            .then(|| self.graph.edge_ref(src, dst, layer))
+            .flatten()
+    }
+
+    fn edge_ref_window(
+        &self,
+        src: VertexRef,
+        dst: VertexRef,
+        t_start: i64,
+        t_end: i64,
+        layer: usize,
+    ) -> Option<EdgeRef> {
+        self.keep_edge(src.g_id, dst.g_id)
+            .then(|| self.graph.edge_ref_window(src, dst, t_start, t_end, layer))
+            .flatten()
+    }
+
+    fn edge_refs(&self, layer: Option<usize>) -> Box<dyn Iterator<Item = EdgeRef> + Send> {
+        self.filter_edges(self.graph.edge_refs(layer))
+    }
+
+    fn edge_refs_window(
+        &self,
+        t_start: i64,
+        t_end: i64,
+        layer: Option<usize>,
+    ) -> Box<dyn Iterator<Item = EdgeRef> + Send> {
+        self.filter_edges(self.graph.edge_refs_window(t_start, t_end, layer))
+    }
+
+    fn vertex_edges_all_layers(
+        &self,
+        v: VertexRef,
+        d: Direction,
+    ) -> Box<dyn Iterator<Item = EdgeRef> + Send> {
+        self.filter_edges(self.graph.vertex_edges_all_layers(v, d))
+    }
+
+    fn vertex_edges_single_layer(
+        &self,
+        v: VertexRef,
+        d: Direction,
+        layer: usize,
+    ) -> Box<dyn Iterator<Item = EdgeRef> + Send> {
+        self.filter_edges(self.graph.vertex_edges_single_layer(v, d, layer))
+    }
+
+    fn vertex_edges_t(
+        &self,
+        v: VertexRef,
+        d: Direction,
+        layer: Option<usize>,
+    ) -> Box<dyn Iterator<Item = EdgeRef> + Send> {
+        self.filter_edges(self.graph.vertex_edges_t(v, d, layer))
+    }
+
+    fn vertex_edges_window(
+        &self,
+        v: VertexRef,
+        t_start: i64,
+        t_end: i64,
+        d: Direction,
+        layer: Option<usize>,
+    ) -> Box<dyn Iterator<Item = EdgeRef> + Send> {
+        self.filter_edges(self.graph.vertex_edges_window(v, t_start, t_end, d, layer))
+    }
+
+    fn vertex_edges_window_t(
+        &self,
+        v: VertexRef,
+        t_start: i64,
+        t_end: i64,
+        d: Direction,
+        layer: Option<usize>,
+    ) -> Box<dyn Iterator<Item = EdgeRef> + Send> {
+        self.filter_edges(
+            self.graph
+                .vertex_edges_window_t(v, t_start, t_end, d, layer),
+        )
+    }
+
+    fn neighbours(
+        &self,
+        v: VertexRef,
+        d: Direction,
+        layer: Option<usize>,
+    ) -> Box<dyn Iterator<Item = VertexRef> + Send> {
+        Box::new(
+            self.neighbours_ids(v, d, layer)
+                .map(|g_id| VertexRef { g_id, pid: None }),
+        )
+    }
+
+    fn neighbours_window(
+        &self,
+        v: VertexRef,
+        t_start: i64,
+        t_end: i64,
+        d: Direction,
+        layer: Option<usize>,
+    ) -> Box<dyn Iterator<Item = VertexRef> + Send> {
+        Box::new(
+            self.neighbours_ids_window(v, t_start, t_end, d, layer)
+                .map(|g_id| VertexRef { g_id, pid: None }),
+        )
+    }
+
+    fn neighbours_ids(
+        &self,
+        v: VertexRef,
+        d: Direction,
+        layer: Option<usize>,
+    ) -> Box<dyn Iterator<Item = u64> + Send> {
+        if !self.keep_vertex(v.g_id) {
+            return Box::new(std::iter::empty());
+        }
+        let edges = match layer {
+            None => self.vertex_edges_all_layers(v, d),
+            Some(layer) => self.vertex_edges_single_layer(v, d, layer),
+        };
+        Self::neighbour_ids_of(v, edges)
+    }
+
+    fn neighbours_ids_window(
+        &self,
+        v: VertexRef,
+        t_start: i64,
+        t_end: i64,
+        d: Direction,
+        layer: Option<usize>,
+    ) -> Box<dyn Iterator<Item = u64> + Send> {
+        if !self.keep_vertex(v.g_id) {
+            return Box::new(std::iter::empty());
+        }
+        Self::neighbour_ids_of(v, self.vertex_edges_window(v, t_start, t_end, d, layer))
+    }
+
+    fn static_vertex_prop(&self, v: VertexRef, name: String) -> Option<Prop> {
+        self.graph.static_vertex_prop(v, name)
+    }
+
+    fn static_vertex_prop_names(&self, v: VertexRef) -> Vec<String> {
+        self.graph.static_vertex_prop_names(v)
+    }
+
+    fn temporal_vertex_prop_names(&self, v: VertexRef) -> Vec<String> {
+        self.graph.temporal_vertex_prop_names(v)
+    }
+
+    fn temporal_vertex_prop_vec(&self, v: VertexRef, name: String) -> Vec<(i64, Prop)> {
+        self.graph.temporal_vertex_prop_vec(v, name)
+    }
+
+    fn vertex_timestamps(&self, v: VertexRef) -> Vec<i64> {
+        self.graph.vertex_timestamps(v)
+    }
+
+    fn vertex_timestamps_window(&self, v: VertexRef, t_start: i64, t_end: i64) -> Vec<i64> {
+        self.graph.vertex_timestamps_window(v, t_start, t_end)
+    }
+
+    fn temporal_vertex_prop_vec_window(
+        &self,
+        v: VertexRef,
+        name: String,
+        t_start: i64,
+        t_end: i64,
+    ) -> Vec<(i64, Prop)> {
+        self.graph
+            .temporal_vertex_prop_vec_window(v, name, t_start, t_end)
+    }
+
+    fn temporal_vertex_props(&self, v: VertexRef) -> HashMap<String, Vec<(i64, Prop)>> {
+        self.graph.temporal_vertex_props(v)
+    }
+
+    fn temporal_vertex_props_window(
+        &self,
+        v: VertexRef,
+        t_start: i64,
+        t_end: i64,
+    ) -> HashMap<String, Vec<(i64, Prop)>> {
+        self.graph.temporal_vertex_props_window(v, t_start, t_end)
+    }
+
+    fn static_edge_prop(&self, e: EdgeRef, name: String) -> Option<Prop> {
+        self.graph.static_edge_prop(e, name)
+    }
+
+    fn static_edge_prop_names(&self, e: EdgeRef) -> Vec<String> {
+        self.graph.static_edge_prop_names(e)
+    }
+
+    fn temporal_edge_prop_names(&self, e: EdgeRef) -> Vec<String> {
+        self.graph.temporal_edge_prop_names(e)
+    }
+
+    fn temporal_edge_props_vec(&self, e: EdgeRef, name: String) -> Vec<(i64, Prop)> {
+        self.graph.temporal_edge_props_vec(e, name)
+    }
+
+    fn temporal_edge_props_vec_window(
+        &self,
+        e: EdgeRef,
+        name: String,
+        t_start: i64,
+        t_end: i64,
+    ) -> Vec<(i64, Prop)> {
+        self.graph
+            .temporal_edge_props_vec_window(e, name, t_start, t_end)
+    }
+
+    fn edge_timestamps(&self, e: EdgeRef, window: Option<Range<i64>>) -> Vec<i64> {
+        self.graph.edge_timestamps(e, window)
+    }
+
+    fn temporal_edge_props(&self, e: EdgeRef) -> HashMap<String, Vec<(i64, Prop)>> {
+        self.graph.temporal_edge_props(e)
+    }
+
+    fn temporal_edge_props_window(
+        &self,
+        e: EdgeRef,
+        t_start: i64,
+        t_end: i64,
+    ) -> HashMap<String, Vec<(i64, Prop)>> {
+        self.graph.temporal_edge_props_window(e, t_start, t_end)
+    }
+
+    fn num_shards(&self) -> usize {
+        self.graph.num_shards()
+    }
+
+    fn vertices_shard(&self, shard_id: usize) -> Box<dyn Iterator<Item = VertexRef> + Send> {
+        self.filter_vertex_refs(self.graph.vertices_shard(shard_id))
+    }
+
+    fn vertices_shard_window(
+        &self,
+        shard_id: usize,
+        t_start: i64,
+        t_end: i64,
+    ) -> Box<dyn Iterator<Item = VertexRef> + Send> {
+        self.filter_vertex_refs(self.graph.vertices_shard_window(shard_id, t_start, t_end))
+    }
+}
+
+#[cfg(test)]
+mod subgraph_tests {
+    use super::*;
+    use crate::db::graph::Graph;
+    use crate::db::view_api::*;
+
+    fn graph() -> Graph {
+        let g = Graph::new(2);
+        for (t, src, dst) in [(1, 1, 2), (2, 2, 3), (3, 3, 1), (4, 3, 4)] {
+            g.add_edge(t, src, dst, &vec![], None).unwrap();
+        }
+        g
+    }
+
+    #[test]
+    fn vertex_subgraph_drops_edges_to_missing_vertices() {
+        let g = graph();
+        let sg = SubGraph::new(g, Some(FxHashSet::from_iter([1, 3, 4])), None);
+
+        assert_eq!(sg.num_vertices(), 3);
+        assert_eq!(sg.num_edges(), 2);
+        assert!(!sg.has_vertex(2));
+        assert!(sg.has_edge(3, 1, None));
+        assert!(!sg.has_edge(1, 2, None));
+
+        let v = sg.vertex(3).unwrap();
+        assert_eq!(v.degree(), 2);
+        assert_eq!(v.in_degree(), 0);
+        let mut nbs = v.neighbours().id().collect::<Vec<_>>();
+        nbs.sort();
+        assert_eq!(nbs, vec![1, 4]);
+    }
+
+    #[test]
+    fn edge_subgraph_keeps_all_vertices() {
+        let g = graph();
+        let sg = SubGraph::new(g, None, Some(FxHashSet::from_iter([(1, 2), (3, 4)])));
+
+        assert_eq!(sg.num_vertices(), 4);
+        assert_eq!(sg.num_edges(), 2);
+        assert_eq!(sg.vertex(3).unwrap().degree(), 1);
+        assert_eq!(sg.window(0, 3).num_edges(), 1);
+    }
+}
@@ -1,6 +1,8 @@
 //! Defines the `Vertex` struct, which represents a vertex in the graph.
 
 use crate::core::tgraph::VertexRef;
+use crate::core::time::TimeUnit;
+use crate::core::vertex::VertexLookup;
 use crate::core::{Direction, Prop};
 use crate::db::edge::{EdgeList, EdgeView};
 use crate::db::path::{Operations, PathFromVertex};
@@ -28,6 +30,18 @@ impl<G: GraphViewOps> From<&VertexView<G>> for VertexRef {
     }
 }
 
+impl<G: GraphViewOps> VertexLookup for VertexView<G> {
+    fn vertex_ref(&self) -> VertexRef {
+        self.vertex
+    }
+}
+
+impl<G: GraphViewOps> VertexLookup for &VertexView<G> {
+    fn vertex_ref(&self) -> VertexRef {
+        self.vertex
+    }
+}
+
 impl<G: GraphViewOps> VertexView<G> {
     /// Creates a new `VertexView` wrapping a vertex reference and a graph.
     pub(crate) fn new(graph: G, vertex: VertexRef) -> VertexView<G> {
@@ -340,6 +354,10 @@ impl<G: GraphViewOps> TimeOps for VertexView<G> {
         }
     }
 
+    fn time_unit(&self) -> TimeUnit {
+        self.graph.time_unit()
+    }
+
     fn window(&self, t_start: i64, t_end: i64) -> Self::WindowedViewType {
         Self {
             graph: self.graph.clone(),
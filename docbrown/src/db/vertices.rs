@@ -1,4 +1,5 @@
-use crate::core::tgraph::VertexRef;
+use crate::core::time::TimeUnit;
+use crate::core::vertex::VertexLookup;
 use crate::core::{Direction, Prop};
 use crate::db::edge::EdgeView;
 use crate::db::path::{Operations, PathFromGraph};
@@ -38,7 +39,7 @@ impl<G: GraphViewOps> Vertices<G> {
         self.graph.is_empty()
     }
 
-    pub fn get<V: Into<VertexRef>>(&self, vertex: V) -> Option<VertexView<G>> {
+    pub fn get<V: VertexLookup>(&self, vertex: V) -> Option<VertexView<G>> {
         self.graph.vertex(vertex)
     }
 }
@@ -188,6 +189,10 @@ impl<G: GraphViewOps> TimeOps for Vertices<G> {
         }
     }
 
+    fn time_unit(&self) -> TimeUnit {
+        self.graph.time_unit()
+    }
+
     fn window(&self, t_start: i64, t_end: i64) -> Self::WindowedViewType {
         Self {
             graph: self.graph.clone(),
@@ -1,4 +1,6 @@
 use crate::core::tgraph::VertexRef;
+use crate::core::time::TimeUnit;
+use crate::core::vertex::VertexLookup;
 use crate::db::edge::EdgeView;
 use crate::db::graph_layer::LayeredGraph;
 use crate::db::graph_window::WindowedGraph;
@@ -29,24 +31,19 @@ pub trait GraphViewOps: Send + Sync + Sized + GraphViewInternalOps + 'static + C
     fn num_edges(&self) -> usize;
 
     /// Check if the graph contains a vertex `v`.
-    fn has_vertex<T: Into<VertexRef>>(&self, v: T) -> bool;
+    fn has_vertex<T: VertexLookup>(&self, v: T) -> bool;
 
     /// Check if the graph contains an edge given a pair of vertices `(src, dst)`.
-    fn has_edge<T: Into<VertexRef>>(&self, src: T, dst: T, layer: Option<&str>) -> bool;
+    fn has_edge<T: VertexLookup>(&self, src: T, dst: T, layer: Option<&str>) -> bool;
 
     /// Get a vertex `v`.
-    fn vertex<T: Into<VertexRef>>(&self, v: T) -> Option<VertexView<Self>>;
+    fn vertex<T: VertexLookup>(&self, v: T) -> Option<VertexView<Self>>;
 
     /// Return a View of the vertices in the Graph
     fn vertices(&self) -> Vertices<Self>;
 
     /// Get an edge `(src, dst)`.
-    fn edge<T: Into<VertexRef>>(
-        &self,
-        src: T,
-        dst: T,
-        layer: Option<&str>,
-    ) -> Option<EdgeView<Self>>;
+    fn edge<T: VertexLookup>(&self, src: T, dst: T, layer: Option<&str>) -> Option<EdgeView<Self>>;
 
     /// Return an iterator over all edges in the graph.
     fn edges(&self) -> Box<dyn Iterator<Item = EdgeView<Self>> + Send>;
@@ -75,19 +72,23 @@ impl<G: Send + Sync + Sized + GraphViewInternalOps + 'static + Clone> GraphViewO
         self.edges_len(None)
     }
 
-    fn has_vertex<T: Into<VertexRef>>(&self, v: T) -> bool {
-        self.has_vertex_ref(v.into())
+    fn has_vertex<T: VertexLookup>(&self, v: T) -> bool {
+        lookup(self, &v).map_or(false, |v| self.has_vertex_ref(v))
     }
 
-    fn has_edge<T: Into<VertexRef>>(&self, src: T, dst: T, layer: Option<&str>) -> bool {
-        match self.get_layer(layer) {
-            Some(layer_id) => self.has_edge_ref(src.into(), dst.into(), layer_id),
-            None => false,
+    fn has_edge<T: VertexLookup>(&self, src: T, dst: T, layer: Option<&str>) -> bool {
+        match (
+            self.get_layer(layer),
+            lookup(self, &src),
+            lookup(self, &dst),
+        ) {
+            (Some(layer_id), Some(src), Some(dst)) => self.has_edge_ref(src, dst, layer_id),
+            _ => false,
         }
     }
 
-    fn vertex<T: Into<VertexRef>>(&self, v: T) -> Option<VertexView<Self>> {
-        let v = v.into().g_id;
+    fn vertex<T: VertexLookup>(&self, v: T) -> Option<VertexView<Self>> {
+        let v = lookup(self, &v)?.g_id;
         self.vertex_ref(v).map(|v| VertexView::new(self.clone(), v))
     }
 
@@ -96,14 +97,9 @@ impl<G: Send + Sync + Sized + GraphViewInternalOps + 'static + Clone> GraphViewO
         Vertices::new(graph)
     }
 
-    fn edge<T: Into<VertexRef>>(
-        &self,
-        src: T,
-        dst: T,
-        layer: Option<&str>,
-    ) -> Option<EdgeView<Self>> {
+    fn edge<T: VertexLookup>(&self, src: T, dst: T, layer: Option<&str>) -> Option<EdgeView<Self>> {
         let layer_id = self.get_layer(layer)?;
-        self.edge_ref(src.into(), dst.into(), layer_id)
+        self.edge_ref(lookup(self, &src)?, lookup(self, &dst)?, layer_id)
             .map(|e| EdgeView::new(self.clone(), e))
     }
 
@@ -121,6 +117,14 @@ impl<G: Send + Sync + Sized + GraphViewInternalOps + 'static + Clone> GraphViewO
     }
 }
 
+/// The vertex `v` of `g`, with vertices given by name looked up in the names of `g`.
+fn lookup<G: GraphViewInternalOps, T: VertexLookup>(g: &G, v: &T) -> Option<VertexRef> {
+    match v.lookup_name() {
+        Some(name) => g.vertex_id_by_name(name).map(VertexRef::new_remote),
+        None => Some(v.vertex_ref()),
+    }
+}
+
 impl<G: GraphViewOps> TimeOps for G {
     type WindowedViewType = WindowedGraph<Self>;
 
@@ -132,6 +136,10 @@ impl<G: GraphViewOps> TimeOps for G {
         self.view_end()
     }
 
+    fn time_unit(&self) -> TimeUnit {
+        self.view_time_unit()
+    }
+
     fn window(&self, t_start: i64, t_end: i64) -> WindowedGraph<Self> {
         WindowedGraph::new(self.clone(), t_start, t_end)
     }
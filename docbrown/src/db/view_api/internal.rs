@@ -1,4 +1,5 @@
 use crate::core::tgraph::{EdgeRef, VertexRef};
+use crate::core::time::TimeUnit;
 use crate::core::{Direction, Prop};
 use rayon::prelude::*;
 use std::collections::HashMap;
@@ -16,6 +17,9 @@ pub trait GraphViewInternalOps {
     /// Returns the default end time for perspectives over the view
     fn view_end(&self) -> Option<i64>;
 
+    /// Returns the unit of the timestamps of the view
+    fn view_time_unit(&self) -> TimeUnit;
+
     /// Returns the timestamp for the earliest activity
     fn earliest_time_global(&self) -> Option<i64>;
 
@@ -116,6 +120,10 @@ pub trait GraphViewInternalOps {
         layer: Option<usize>,
     ) -> usize;
 
+    /// Returns the id of the vertex with the name `name`, see `docbrown::core::vertex_names`.
+    /// Returns None if the name has no id in the graph.
+    fn vertex_id_by_name(&self, name: &str) -> Option<u64>;
+
     /// Returns the VertexRef that corresponds to the specified vertex ID (v).
     /// Returns None if the vertex ID is not present in the graph.
     /// # Arguments
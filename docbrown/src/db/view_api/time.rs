@@ -1,5 +1,6 @@
+use crate::core::cancel::{Budget, Budgeted};
 use crate::core::time::error::ParseTimeError;
-use crate::core::time::Interval;
+use crate::core::time::{Interval, TimeUnit};
 use std::cmp::{max, min};
 
 /// Trait defining time query operations
@@ -12,6 +13,9 @@ pub trait TimeOps {
     /// Return the timestamp of the default for perspectives of the view (if any).
     fn end(&self) -> Option<i64>;
 
+    /// Return the unit of the timestamps of the view, which intervals are converted to.
+    fn time_unit(&self) -> TimeUnit;
+
     /// the larger of `t_start` and `self.start()` (useful for creating nested windows)
     fn actual_start(&self, t_start: i64) -> i64 {
         match self.start() {
@@ -49,6 +53,7 @@ pub trait TimeOps {
         match (self.start(), self.end()) {
             (Some(start), Some(end)) => {
                 let step: Interval = step.try_into()?;
+                let step = step.to_unit(self.time_unit())?;
 
                 Ok(WindowSet::new(parent, start, end, step, None))
             }
@@ -69,8 +74,9 @@ pub trait TimeOps {
         match (self.start(), self.end()) {
             (Some(start), Some(end)) => {
                 let window: Interval = window.try_into()?;
-                let step: Interval = match step {
-                    Some(step) => step.try_into()?,
+                let window = window.to_unit(self.time_unit())?;
+                let step = match step {
+                    Some(step) => step.try_into()?.to_unit(self.time_unit())?,
                     None => window,
                 };
                 Ok(WindowSet::new(parent, start, end, step, Some(window)))
@@ -84,12 +90,12 @@ pub struct WindowSet<T: TimeOps> {
     view: T,
     cursor: i64,
     end: i64,
-    step: Interval,
-    window: Option<Interval>,
+    step: i64,
+    window: Option<i64>,
 }
 
 impl<T: TimeOps> WindowSet<T> {
-    fn new(view: T, start: i64, end: i64, step: Interval, window: Option<Interval>) -> Self {
+    fn new(view: T, start: i64, end: i64, step: i64, window: Option<i64>) -> Self {
         // let cursor_start = if step.epoch_alignment {
         //     let step = step.to_millis().unwrap() as i64;
         //     let prev_perspective_exclusive_end = (timeline_start / step) * step; // timeline.start 5 step 3 -> 3, timeline.start 6 step 3 -> 6, timeline.start 7 step 3 -> 6
@@ -110,7 +116,12 @@ impl<T: TimeOps> WindowSet<T> {
 
     fn empty(view: T) -> Self {
         // timeline_start is greater than end, so no windows to return, even with end inclusive
-        WindowSet::new(view, 1, 0, Default::default(), None)
+        WindowSet::new(view, 1, 0, 1, None)
+    }
+
+    /// Yields the windows until `budget` runs out, then yields the error and stops.
+    pub fn with_budget(self, budget: &Budget) -> Budgeted<Self> {
+        budget.guard(self)
     }
 }
 
@@ -121,7 +132,7 @@ impl<T: TimeOps> Iterator for WindowSet<T> {
             let window_end = self.cursor + 1;
             let window_start = self.window.map(|w| window_end - w).unwrap_or(i64::MIN);
             let window = self.view.window(window_start, window_end);
-            self.cursor = self.cursor + self.step;
+            self.cursor += self.step;
             Some(window)
         } else {
             None
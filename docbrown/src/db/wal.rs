@@ -0,0 +1,230 @@
+//! An append-only write-ahead log of the changes made to a `Graph` since its last checkpoint.
+//!
+//! Each entry is written as its length, its crc32 checksum and the bincode encoded entry. An
+//! entry that was only partly written when the process died fails the checksum and is dropped,
+//! together with anything after it, when the log is opened again.
+
+use crate::core::schema::Schema;
+use crate::core::tgraph_shard::errors::GraphError;
+use crate::core::vertex::ResolvedVertex;
+use crate::core::Prop;
+use crate::db::graph::Graph;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// The name of the log file in the graph directory.
+pub(crate) const WAL_FILE: &str = "wal";
+
+// length and checksum
+const HEADER_LEN: usize = 8;
+
+/// A change that was made to the graph, with properties already coerced to the schema.
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) enum WalEntry {
+    AddVertex {
+        t: i64,
+        v: ResolvedVertex,
+        props: Vec<(String, Prop)>,
+    },
+    AddVertexProperties {
+        v: u64,
+        props: Vec<(String, Prop)>,
+    },
+    AddEdge {
+        t: i64,
+        src: ResolvedVertex,
+        dst: ResolvedVertex,
+        props: Vec<(String, Prop)>,
+        layer: Option<String>,
+    },
+    AddEdgeProperties {
+        src: u64,
+        dst: u64,
+        props: Vec<(String, Prop)>,
+        layer: Option<String>,
+    },
+    SetSchema(Schema),
+}
+
+impl WalEntry {
+    /// Makes the change again on `g`.
+    pub(crate) fn apply(self, g: &Graph) -> Result<(), GraphError> {
+        // names keep the ids they were given, which depend on the order names were first added in
+        // when the graph has dense ids
+        match &self {
+            WalEntry::AddVertex { v, .. } => g.register_name(v)?,
+            WalEntry::AddEdge { src, dst, .. } => {
+                g.register_name(src)?;
+                g.register_name(dst)?;
+            }
+            _ => {}
+        }
+        match self {
+            WalEntry::AddVertex { t, v, props } => g.add_vertex(t, v, &props),
+            WalEntry::AddVertexProperties { v, props } => g.add_vertex_properties(v, &props),
+            WalEntry::AddEdge {
+                t,
+                src,
+                dst,
+                props,
+                layer,
+            } => g.add_edge(t, src, dst, &props, layer.as_deref()),
+            WalEntry::AddEdgeProperties {
+                src,
+                dst,
+                props,
+                layer,
+            } => g.add_edge_properties(src, dst, &props, layer.as_deref()),
+            WalEntry::SetSchema(schema) => g.set_schema(schema),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Wal {
+    dir: PathBuf,
+    file: parking_lot::Mutex<File>,
+}
+
+impl Wal {
+    /// Starts an empty log in `dir`, replacing any log that is already there.
+    pub(crate) fn create(dir: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(dir.join(WAL_FILE))?;
+        file.sync_all()?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            file: parking_lot::Mutex::new(file),
+        })
+    }
+
+    /// Opens the log in `dir` to append to it, returning the entries it already holds.
+    pub(crate) fn open(dir: &Path) -> io::Result<(Self, Vec<WalEntry>)> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .write(true)
+            .truncate(false)
+            .open(dir.join(WAL_FILE))?;
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)?;
+
+        let mut entries = vec![];
+        let mut offset = 0;
+        while let Some(payload) = Self::next_payload(&bytes[offset..]) {
+            let entry = bincode::deserialize(payload)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            entries.push(entry);
+            offset += HEADER_LEN + payload.len();
+        }
+        // drop a partly written entry so that new entries are appended after the last good one
+        file.set_len(offset as u64)?;
+        file.seek(SeekFrom::End(0))?;
+
+        let wal = Self {
+            dir: dir.to_path_buf(),
+            file: parking_lot::Mutex::new(file),
+        };
+        Ok((wal, entries))
+    }
+
+    fn next_payload(bytes: &[u8]) -> Option<&[u8]> {
+        if bytes.len() < HEADER_LEN {
+            return None;
+        }
+        let len = u32::from_le_bytes(bytes[0..4].try_into().unwrap()) as usize;
+        let checksum = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        let payload = bytes.get(HEADER_LEN..HEADER_LEN + len)?;
+        (crc32fast::hash(payload) == checksum).then_some(payload)
+    }
+
+    /// The directory of the graph the log belongs to.
+    pub(crate) fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Appends `entries`, waits for them to reach the disk and then makes the change they record
+    /// with `apply`.
+    ///
+    /// No other entries are appended until `apply` returns, so that entries are in the log in the
+    /// order their changes were made in. Nothing is applied if the entries cannot be written.
+    pub(crate) fn append_then<A, F>(&self, entries: &[WalEntry], apply: F) -> io::Result<A>
+    where
+        F: FnOnce() -> A,
+    {
+        let mut bytes = vec![];
+        for entry in entries {
+            let payload = bincode::serialize(entry)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+            bytes.extend_from_slice(&payload);
+        }
+        let mut file = self.file.lock();
+        if !bytes.is_empty() {
+            let len = file.stream_position()?;
+            if let Err(e) = file.write_all(&bytes).and_then(|_| file.sync_data()) {
+                // drop what was written so that later entries do not follow a broken one
+                file.set_len(len)?;
+                file.seek(SeekFrom::Start(len))?;
+                return Err(e);
+            }
+        }
+        Ok(apply())
+    }
+
+    /// Appends `entries` and waits for them to reach the disk.
+    #[cfg(test)]
+    fn append(&self, entries: &[WalEntry]) -> io::Result<()> {
+        self.append_then(entries, || ())
+    }
+
+    /// Empties the log, once its entries are part of a snapshot.
+    pub(crate) fn truncate(&self) -> io::Result<()> {
+        let mut file = self.file.lock();
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.sync_all()
+    }
+}
+
+#[cfg(test)]
+mod wal_tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn partly_written_entries_are_dropped() {
+        let dir = TempDir::new("docbrown").unwrap();
+        let wal = Wal::create(dir.path()).unwrap();
+        let entry = |v| WalEntry::AddVertexProperties { v, props: vec![] };
+        wal.append(&[entry(1), entry(2)]).unwrap();
+        drop(wal);
+
+        // simulate a crash half way through writing the third entry
+        let path = dir.path().join(WAL_FILE);
+        let len = std::fs::metadata(&path).unwrap().len();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[42, 0, 0, 0, 1, 2]).unwrap();
+
+        let (wal, entries) = Wal::open(dir.path()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), len);
+
+        wal.append(&[entry(3)]).unwrap();
+        let (_, entries) = Wal::open(dir.path()).unwrap();
+        let ids = entries
+            .into_iter()
+            .map(|e| match e {
+                WalEntry::AddVertexProperties { v, .. } => v,
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+}
@@ -59,13 +59,16 @@
 //! ```
 //!
 
+use crate::core::Prop;
 /// Module for loading CSV files into a graph.
 use bzip2::read::BzDecoder;
+use chrono::DateTime;
 use flate2; // 1.0
 use flate2::read::GzDecoder;
 use rayon::prelude::*;
 use regex::Regex;
-use serde::de::DeserializeOwned;
+use serde::de::{self, DeserializeOwned};
+use serde::{Deserialize, Deserializer};
 use std::collections::VecDeque;
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
@@ -400,6 +403,95 @@ impl CsvLoader {
     }
 }
 
+/// Deserialises a CSV field holding JSON into a `Prop`, use it on the fields of a record with
+/// `#[serde(deserialize_with = "json_prop")]`. Arrays become `Prop::List` and objects become
+/// `Prop::Map`, integers become `Prop::I64` unless they only fit in a `u64`. Properties can't be
+/// null, so fields holding a null anywhere, e.g. `[1, null]`, are rejected.
+///
+/// # Examples
+///
+/// ```rust
+/// use docbrown::core::Prop;
+/// use docbrown::graph_loader::source::csv_loader::{hex_prop, json_prop, rfc3339_prop};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, Debug)]
+/// struct Transaction {
+///     src: u64,
+///     dst: u64,
+///     #[serde(deserialize_with = "json_prop")]
+///     tags: Prop,
+///     #[serde(deserialize_with = "rfc3339_prop")]
+///     booked: Prop,
+///     #[serde(deserialize_with = "hex_prop")]
+///     signature: Prop,
+/// }
+///
+/// let data = "src,dst,tags,booked,signature\n\
+///             1,2,\"[\"\"fx\"\", 3]\",2023-04-01T10:00:00+02:00,cafe\n";
+/// let mut reader = csv::Reader::from_reader(data.as_bytes());
+/// let tx: Transaction = reader.deserialize().next().unwrap().unwrap();
+///
+/// assert_eq!(tx.tags, Prop::List(vec![Prop::Str("fx".to_string()), Prop::I64(3)]));
+/// assert_eq!(tx.booked.to_string(), "2023-04-01T10:00:00+02:00");
+/// assert_eq!(tx.signature, Prop::Bytes(vec![0xca, 0xfe]));
+/// ```
+pub fn json_prop<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Prop, D::Error> {
+    let field = String::deserialize(deserializer)?;
+    let value: serde_json::Value = serde_json::from_str(&field).map_err(de::Error::custom)?;
+    prop_from_json(value).ok_or_else(|| de::Error::custom("null is not a valid property"))
+}
+
+// returns `None` if the value is or holds a null
+
+fn prop_from_json(value: serde_json::Value) -> Option<Prop> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::Bool(b) => Some(Prop::Bool(b)),
+        serde_json::Value::Number(n) => n
+            .as_i64()
+            .map(Prop::I64)
+            .or_else(|| n.as_u64().map(Prop::U64))
+            .or_else(|| n.as_f64().map(Prop::F64)),
+        serde_json::Value::String(s) => Some(Prop::Str(s)),
+        serde_json::Value::Array(values) => values
+            .into_iter()
+            .map(prop_from_json)
+            .collect::<Option<_>>()
+            .map(Prop::List),
+        serde_json::Value::Object(values) => values
+            .into_iter()
+            .map(|(k, v)| prop_from_json(v).map(|v| (k, v)))
+            .collect::<Option<_>>()
+            .map(|values| Prop::Map(Box::new(values))),
+    }
+}
+
+/// Deserialises a CSV field holding an RFC 3339 timestamp, e.g. `2023-04-01T10:00:00+02:00`,
+/// into a `Prop::DTime`, see `json_prop`.
+pub fn rfc3339_prop<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Prop, D::Error> {
+    let field = String::deserialize(deserializer)?;
+    DateTime::parse_from_rfc3339(&field)
+        .map(Prop::DTime)
+        .map_err(de::Error::custom)
+}
+
+/// Deserialises a CSV field holding hex encoded bytes into a `Prop::Bytes`, see `json_prop`.
+pub fn hex_prop<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Prop, D::Error> {
+    let field = String::deserialize(deserializer)?;
+    if field.len() % 2 != 0 {
+        return Err(de::Error::custom(
+            "hex encoded bytes need an even number of digits",
+        ));
+    }
+    (0..field.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(field.get(i..i + 2).unwrap_or("-"), 16))
+        .collect::<Result<Vec<_>, _>>()
+        .map(Prop::Bytes)
+        .map_err(de::Error::custom)
+}
+
 #[cfg(test)]
 mod csv_loader_test {
     use crate::core::utils::calculate_hash;
@@ -553,4 +645,27 @@ mod csv_loader_test {
         let delimiter = ".";
         lotr_test(g, csv_loader, has_header, delimiter, r);
     }
+
+    #[test]
+    fn json_props_reject_nested_nulls() {
+        #[derive(Deserialize, Debug)]
+        struct Record {
+            #[serde(deserialize_with = "super::json_prop")]
+            tags: Prop,
+        }
+
+        let read = |field: &str| {
+            let data = format!("tags\n\"{}\"\n", field.replace('"', "\"\""));
+            let mut reader = csv::Reader::from_reader(data.as_bytes());
+            reader.deserialize::<Record>().next().unwrap()
+        };
+
+        assert_eq!(
+            read("[1, 2]").unwrap().tags,
+            Prop::List(vec![Prop::I64(1), Prop::I64(2)])
+        );
+        assert!(read("null").is_err());
+        assert!(read("[1, null]").is_err());
+        assert!(read(r#"{"a": {"b": null}}"#).is_err());
+    }
 }
@@ -6,6 +6,7 @@ use std::thread::JoinHandle;
 use std::{env, thread};
 
 use chrono::{DateTime, Utc};
+use docbrown::algorithms::temporal_cycles::temporal_cycles;
 use docbrown::core::tgraph::TemporalGraph;
 use docbrown::core::utils;
 use docbrown::core::{Direction, Prop};
@@ -130,6 +131,21 @@ fn main() {
 
     assert_eq!(deg_out, 22);
     assert_eq!(deg_in, 1);
+
+    // look for money flowing back to its origin within a day, losing at most 10% on every hop
+    let now = Instant::now();
+    let cycles = temporal_cycles(
+        &windowed_graph,
+        4,
+        Some(24 * 60 * 60),
+        Some(("amount", 0.9)),
+    );
+
+    println!(
+        "Found {} round-tripping cycles, took {} seconds",
+        cycles.len(),
+        now.elapsed().as_secs()
+    );
 }
 
 mod custom_date_format {
@@ -0,0 +1,92 @@
+//! Implements a custom scoring algorithm on top of the `Program` engine.
+//!
+//! Every vertex is scored with the largest degree found within `hops` hops of it, a cheap way to
+//! tell how close a vertex is to a hub. The score spreads one hop per step and the program stops
+//! as soon as no score changes.
+//!
+//! Usage: `custom_program [vertices] [hops]`
+
+use docbrown::algorithms::algorithm_result::AlgorithmResult;
+use docbrown::core::state;
+use docbrown::db::graph::Graph;
+use docbrown::db::program::{GlobalEvalState, LocalState, Program};
+use docbrown::db::view_api::*;
+use docbrown::graphgen::preferential_attachment::ba_preferential_attachment;
+use std::collections::HashMap;
+use std::{env, time::Instant};
+
+struct HubProximity;
+
+impl Program for HubProximity {
+    type Out = AlgorithmResult<usize>;
+
+    fn local_eval<G: GraphViewOps>(&self, c: &LocalState<G>) {
+        let score = c.agg(state::def::max::<usize>(0));
+
+        c.step(|v| {
+            v.update(&score, v.neighbours().count());
+
+            let my_score = v.read(&score);
+            for n in v.neighbours() {
+                n.update(&score, my_score);
+            }
+        })
+    }
+
+    fn post_eval<G: GraphViewOps>(&self, c: &mut GlobalEvalState<G>) {
+        let score = c.agg(state::def::max::<usize>(0));
+
+        // only the vertices whose score changed stay active
+        c.step(|v| v.read(&score) != v.read_prev(&score))
+    }
+
+    fn produce_output<G: GraphViewOps>(&self, g: &G, gs: &GlobalEvalState<G>) -> Self::Out
+    where
+        Self: Sync,
+    {
+        let agg = state::def::max::<usize>(0);
+
+        let mut scores = HashMap::new();
+        (0..g.num_shards()).fold(&mut scores, |res, part_id| {
+            gs.fold_state(&agg, part_id, res, |res, v_id, score| {
+                res.insert(*v_id, score);
+                res
+            })
+        });
+
+        AlgorithmResult::new(g, scores.into_iter().collect())
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let vertices = args.get(1).and_then(|n| n.parse().ok()).unwrap_or(10_000);
+    let hops = args.get(2).and_then(|n| n.parse().ok()).unwrap_or(3);
+
+    let graph = Graph::new(4);
+    ba_preferential_attachment(&graph, vertices, 2);
+    println!(
+        "Generated a graph with {} vertices and {} edges",
+        graph.num_vertices(),
+        graph.num_edges()
+    );
+
+    let now = Instant::now();
+    let program = HubProximity;
+    let gs = program.run(&graph, true, hops);
+    let scores = program.produce_output(&graph, &gs);
+
+    println!(
+        "Scored {} vertices in {} steps and {} ms",
+        scores.len(),
+        gs.ss,
+        now.elapsed().as_millis()
+    );
+
+    for (v_id, score) in scores.top_k(5) {
+        println!(
+            "vertex {} is within {} hops of degree {}",
+            v_id, hops, score
+        );
+    }
+}
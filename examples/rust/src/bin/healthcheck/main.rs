@@ -15,7 +15,6 @@ mod test {
         view_api::{internal::GraphViewInternalOps, GraphViewOps},
     };
     use docbrown::graph_loader::source::csv_loader::CsvLoader;
-    use itertools::Itertools;
     use serde::de::DeserializeOwned;
 
     trait TestEdge {
@@ -255,29 +254,18 @@ mod test {
             let gn = Graph::new(n_parts);
             load::<PairNoTime>(&g1, &gn, csv_path.clone());
 
-            let iter_count = 50;
-            let cc1 = weakly_connected_components(&g1, iter_count);
-            let ccn = weakly_connected_components(&gn, iter_count);
+            let cc1 = weakly_connected_components(&g1);
+            let ccn = weakly_connected_components(&gn);
 
             // get LCC
-            let counts = cc1.iter().counts_by(|(_, cc)| cc);
-            let max_1 = counts
-                .into_iter()
-                .sorted_by(|l, r| l.1.cmp(&r.1))
-                .rev()
-                .take(1)
-                .next();
-
-            // get LCC
-            let counts = ccn.iter().counts_by(|(_, cc)| cc);
-            let max_n = counts
-                .into_iter()
-                .sorted_by(|l, r| l.1.cmp(&r.1))
-                .rev()
-                .take(1)
-                .next();
-
-            assert_eq!(max_1, Some((&6, 1039)));
+            let max_1 = cc1
+                .largest_component()
+                .map(|cc| (cc, cc1.members(cc).unwrap().len()));
+            let max_n = ccn
+                .largest_component()
+                .map(|cc| (cc, ccn.members(cc).unwrap().len()));
+
+            assert_eq!(max_1, Some((6, 1039)));
             assert_eq!(max_1, max_n);
             println!("{:?}", max_1);
         }
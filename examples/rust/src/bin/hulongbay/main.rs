@@ -133,17 +133,16 @@ fn try_main() -> Result<(), Box<dyn Error>> {
     );
 
     let now = Instant::now();
-    let components = weakly_connected_components(&graph, 5);
+    let components = weakly_connected_components(&graph);
 
     components
-        .into_iter()
-        .counts_by(|(_, cc)| cc)
+        .components()
         .iter()
-        .sorted_by(|l, r| l.1.cmp(r.1))
+        .sorted_by(|l, r| l.1.len().cmp(&r.1.len()))
         .rev()
         .take(50)
-        .for_each(|(cc, count)| {
-            println!("CC {} has {} vertices", cc, count);
+        .for_each(|(cc, members)| {
+            println!("CC {} has {} vertices", cc, members.len());
         });
 
     println!(
@@ -1,4 +1,6 @@
-use docbrown::algorithms::triangle_count::{TriangleCountS1, TriangleCountS2, TriangleCountSlowS2};
+use docbrown::algorithms::triangle_count::{
+    TemporalTriangleCountS2, TriangleCountS1, TriangleCountSlowS2,
+};
 use docbrown::core::Prop;
 use docbrown::core::{state, utils};
 use docbrown::db::graph::Graph;
@@ -105,8 +107,8 @@ fn main() {
     assert!(graph.has_vertex(gandalf));
 
     let program_s1 = TriangleCountS1 {};
-    let program_s2 = TriangleCountS2 {};
-    let agg = state::def::sum::<u64>(1);
+    let program_s2 = TemporalTriangleCountS2 { delta: None };
+    let agg = state::def::sum::<usize>(1);
 
     let mut gs = GlobalEvalState::new(graph.clone(), false);
 
@@ -17,6 +17,7 @@ use docbrown::algorithms::local_triangle_count::local_triangle_count as local_tr
 use docbrown::algorithms::reciprocity::{
     all_local_reciprocity as all_local_reciprocity_rs, global_reciprocity as global_reciprocity_rs,
 };
+use docbrown::db::view_api::*;
 use pyo3::prelude::*;
 
 /// Local triangle count - calculates the number of triangles (a cycle of length 3) for a node.
@@ -38,7 +39,9 @@ use pyo3::prelude::*;
 #[pyfunction]
 pub(crate) fn local_triangle_count(g: &PyGraphView, v: &PyAny) -> PyResult<Option<usize>> {
     let v = utils::extract_vertex_ref(v)?;
-    Ok(local_triangle_count_rs(&g.graph, v.g_id))
+    Ok(g.graph
+        .vertex(v)
+        .and_then(|v| local_triangle_count_rs(&g.graph, v.id())))
 }
 
 /// Local Clustering coefficient - measures the degree to which nodes in a graph tend to cluster together.
@@ -65,7 +68,9 @@ pub(crate) fn local_triangle_count(g: &PyGraphView, v: &PyAny) -> PyResult<Optio
 #[pyfunction]
 pub(crate) fn local_clustering_coefficient(g: &PyGraphView, v: &PyAny) -> PyResult<Option<f32>> {
     let v = utils::extract_vertex_ref(v)?;
-    Ok(local_clustering_coefficient_rs(&g.graph, v.g_id))
+    Ok(g.graph
+        .vertex(v)
+        .and_then(|v| local_clustering_coefficient_rs(&g.graph, v.id())))
 }
 
 /// Graph density - measures how dense or sparse a graph is.
@@ -133,7 +138,7 @@ pub(crate) fn global_reciprocity(g: &PyGraphView) -> f64 {
 }
 
 /// Reciprocity - measure of the symmetry of relationships in a graph.
-/// the reciprocity of every vertex in the graph as a tuple of vector id and the reciprocity
+/// Returns the reciprocity of every vertex in the graph as a dict keyed by vertex id.
 /// This calculates the number of reciprocal connections (edges that go in both directions) in a
 /// graph and normalizes it by the total number of edges.
 ///
@@ -151,7 +156,7 @@ pub(crate) fn global_reciprocity(g: &PyGraphView) -> f64 {
 ///
 #[pyfunction]
 pub(crate) fn all_local_reciprocity(g: &PyGraphView) -> HashMap<u64, f64> {
-    all_local_reciprocity_rs(&g.graph)
+    all_local_reciprocity_rs(&g.graph).into_iter().collect()
 }
 
 /// Computes the number of both open and closed triplets within a graph
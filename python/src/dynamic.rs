@@ -1,4 +1,5 @@
 use docbrown::core::tgraph::{EdgeRef, VertexRef};
+use docbrown::core::time::TimeUnit;
 use docbrown::core::{Direction, Prop};
 use docbrown::db::graph::Graph;
 use docbrown::db::view_api::internal::GraphViewInternalOps;
@@ -39,6 +40,10 @@ impl GraphViewInternalOps for DynamicGraph {
         self.0.view_end()
     }
 
+    fn view_time_unit(&self) -> TimeUnit {
+        self.0.view_time_unit()
+    }
+
     fn earliest_time_global(&self) -> Option<i64> {
         self.0.earliest_time_global()
     }
@@ -121,6 +126,10 @@ impl GraphViewInternalOps for DynamicGraph {
         self.0.degree_window(v, t_start, t_end, d, layer)
     }
 
+    fn vertex_id_by_name(&self, name: &str) -> Option<u64> {
+        self.0.vertex_id_by_name(name)
+    }
+
     fn vertex_ref(&self, v: u64) -> Option<VertexRef> {
         self.0.vertex_ref(v)
     }
@@ -8,12 +8,17 @@
 use crate::dynamic::DynamicGraph;
 use crate::graph_view::PyGraphView;
 use crate::utils::adapt_result;
+use crate::vertex::PyVertex;
 use crate::wrappers::prop::Prop;
 use docbrown::core as dbc;
+use docbrown::core::schema::Schema;
+use docbrown::core::time::TimeUnit;
 use docbrown::core::vertex::InputVertex;
+use docbrown::core::vertex_names::VertexIds;
 use docbrown::db::graph::Graph;
+use docbrown::db::view_api::*;
 use itertools::Itertools;
-use pyo3::exceptions::{PyException, PyTypeError};
+use pyo3::exceptions::{PyException, PyTypeError, PyValueError};
 use pyo3::prelude::*;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -44,16 +49,33 @@ impl PyGraph {
 /// A temporal graph.
 #[pymethods]
 impl PyGraph {
+    /// Creates a new graph.
+    ///
+    /// Arguments:
+    ///    nr_shards (int): The number of shards of the graph.
+    ///    vertex_ids (str): How vertices given by name get their ids, one of "hashed" (the
+    ///                      default), "checked" or "dense".
+    ///    time_unit (str): The unit times are counted in, which intervals such as "1 day" are
+    ///                     converted to, one of "milliseconds" (the default), "microseconds",
+    ///                     "nanoseconds" or "integer" for times without a unit.
     #[new]
-    #[pyo3(signature = (nr_shards=1))]
-    pub fn py_new(nr_shards: usize) -> (Self, PyGraphView) {
-        let graph = Graph::new(nr_shards);
-        (
+    #[pyo3(signature = (nr_shards=1, vertex_ids="hashed", time_unit="milliseconds"))]
+    pub fn py_new(
+        nr_shards: usize,
+        vertex_ids: &str,
+        time_unit: &str,
+    ) -> PyResult<(Self, PyGraphView)> {
+        let graph = Graph::new_with(
+            nr_shards,
+            Self::extract_vertex_ids(vertex_ids)?,
+            Self::extract_time_unit(time_unit)?,
+        );
+        Ok((
             Self {
                 graph: graph.clone(),
             },
             PyGraphView::from(DynamicGraph::from(graph)),
-        )
+        ))
     }
 
     /// Adds a new vertex with the given id and properties to the graph.
@@ -159,6 +181,82 @@ impl PyGraph {
         adapt_result(result)
     }
 
+    /// Gets the vertex that was added with the given name.
+    ///
+    /// Arguments:
+    ///    name (str): The name of the vertex.
+    ///
+    /// Returns:
+    ///    Vertex: The vertex, or None if the graph has no vertex with that name.
+    pub fn vertex_by_name(&self, name: &str) -> Option<PyVertex> {
+        DynamicGraph::from(self.graph.clone())
+            .vertex(name)
+            .map(|v| v.into())
+    }
+
+    //******  Schema  ******//
+
+    /// Declares the types of vertex and edge properties, values of a declared property are
+    /// converted to the declared type if that loses nothing and rejected otherwise.
+    ///
+    /// Types are given by name, one of "str", "i32", "i64", "u32", "u64", "f32", "f64", "bool",
+    /// "dtime", "list", "map" or "bytes".
+    ///
+    /// Integers are given to "i32", "u32" and "u64" properties if they are in the range of that
+    /// type, and floats to "f32" properties if an f32 holds the same value.
+    ///
+    /// Arguments:
+    ///    vertex (dict): The types of vertex properties by property name.
+    ///    edges (dict): The types of edge properties by layer name (None for the default layer)
+    ///                  and property name.
+    ///
+    /// Returns:
+    ///   None
+    #[pyo3(signature = (vertex=None, edges=None))]
+    pub fn set_schema(
+        &self,
+        vertex: Option<HashMap<String, String>>,
+        edges: Option<HashMap<Option<String>, HashMap<String, String>>>,
+    ) -> PyResult<()> {
+        let mut schema = Schema::new();
+        for (name, dtype) in vertex.unwrap_or_default() {
+            schema = schema.vertex_prop(&name, Self::extract_prop_type(&dtype)?);
+        }
+        for (layer, props) in edges.unwrap_or_default() {
+            for (name, dtype) in props {
+                schema =
+                    schema.edge_prop(layer.as_deref(), &name, Self::extract_prop_type(&dtype)?);
+            }
+        }
+        adapt_result(self.graph.set_schema(schema))
+    }
+
+    /// Returns the declared property types of the graph.
+    ///
+    /// Returns:
+    ///   dict: The vertex property types under "vertex" and the edge property types by layer
+    ///         under "edges", in the format taken by `set_schema`.
+    pub fn schema(&self, py: Python) -> PyObject {
+        let schema = self.graph.schema();
+        let type_names = |props: &HashMap<String, dbc::PropType>| {
+            props
+                .iter()
+                .map(|(name, dtype)| (name.clone(), dtype.to_string().to_lowercase()))
+                .collect::<HashMap<_, _>>()
+        };
+        let edges = schema
+            .layers()
+            .map(|layer| {
+                let props = schema.edge_props(layer).unwrap();
+                (layer.map(|l| l.to_string()), type_names(props))
+            })
+            .collect::<HashMap<_, _>>();
+        let mut result = HashMap::new();
+        result.insert("vertex", type_names(schema.vertex_props()).into_py(py));
+        result.insert("edges", edges.into_py(py));
+        result.into_py(py)
+    }
+
     //******  Saving And Loading  ******//
 
     // Alternative constructors are tricky, see: https://gist.github.com/redshiftzero/648e4feeff3843ffd9924f13625f839c
@@ -210,6 +308,55 @@ impl PyGraph {
             .collect_vec()
     }
 
+    fn extract_prop_type(name: &str) -> PyResult<dbc::PropType> {
+        let dtype = match name.to_lowercase().as_str() {
+            "str" => dbc::PropType::Str,
+            "i32" => dbc::PropType::I32,
+            "i64" => dbc::PropType::I64,
+            "u32" => dbc::PropType::U32,
+            "u64" => dbc::PropType::U64,
+            "f32" => dbc::PropType::F32,
+            "f64" => dbc::PropType::F64,
+            "bool" => dbc::PropType::Bool,
+            "dtime" => dbc::PropType::DTime,
+            "list" => dbc::PropType::List,
+            "map" => dbc::PropType::Map,
+            "bytes" => dbc::PropType::Bytes,
+            _ => {
+                return Err(PyTypeError::new_err(format!(
+                    "Unknown property type '{}'",
+                    name
+                )))
+            }
+        };
+        Ok(dtype)
+    }
+
+    fn extract_vertex_ids(name: &str) -> PyResult<VertexIds> {
+        match name.to_lowercase().as_str() {
+            "hashed" => Ok(VertexIds::Hashed),
+            "checked" => Ok(VertexIds::Checked),
+            "dense" => Ok(VertexIds::Dense),
+            _ => Err(PyValueError::new_err(format!(
+                "Unknown vertex ids '{}'",
+                name
+            ))),
+        }
+    }
+
+    fn extract_time_unit(name: &str) -> PyResult<TimeUnit> {
+        match name.to_lowercase().as_str() {
+            "milliseconds" => Ok(TimeUnit::Milliseconds),
+            "microseconds" => Ok(TimeUnit::Microseconds),
+            "nanoseconds" => Ok(TimeUnit::Nanoseconds),
+            "integer" => Ok(TimeUnit::Integer),
+            _ => Err(PyValueError::new_err(format!(
+                "Unknown time unit '{}'",
+                name
+            ))),
+        }
+    }
+
     /// Extracts the id from the given python vertex
     ///
     /// Arguments:
@@ -6,30 +6,54 @@ use crate::vertex::PyVertex;
 use docbrown::core::tgraph::VertexRef;
 use docbrown::core::time::error::ParseTimeError;
 use docbrown::core::time::Interval;
+use docbrown::core::vertex::VertexLookup;
 use docbrown::db::view_api::time::WindowSet;
 use docbrown::db::view_api::TimeOps;
 use pyo3::exceptions::{PyException, PyTypeError};
 use pyo3::prelude::*;
 use std::error::Error;
 
-/// Extract a `VertexRef` from a Python object.
+/// A vertex given from Python to one of the functions that look vertices up in a graph, either
+/// by reference or by name.
+pub(crate) enum PyVertexRef {
+    Ref(VertexRef),
+    Name(String),
+}
+
+impl VertexLookup for PyVertexRef {
+    fn vertex_ref(&self) -> VertexRef {
+        match self {
+            PyVertexRef::Ref(v) => *v,
+            PyVertexRef::Name(name) => name.vertex_ref(),
+        }
+    }
+
+    fn lookup_name(&self) -> Option<&str> {
+        match self {
+            PyVertexRef::Ref(_) => None,
+            PyVertexRef::Name(name) => Some(name),
+        }
+    }
+}
+
+/// Extract a `PyVertexRef` from a Python object.
 /// The object can be a `str`, `u64` or `PyVertex`.
 /// If the object is a `PyVertex`, the `VertexRef` is extracted from the `PyVertex`.
-/// If the object is a `str`, the `VertexRef` is created from the `str`.
+/// If the object is a `str`, it is looked up by name in the graph.
 /// If the object is a `int`, the `VertexRef` is created from the `int`.
 ///
 /// Arguments
-///     vref: The Python object to extract the `VertexRef` from.
+///     vref: The Python object to extract the `PyVertexRef` from.
 ///
 /// Returns
-///    A `VertexRef` extracted from the Python object.
-pub(crate) fn extract_vertex_ref(vref: &PyAny) -> PyResult<VertexRef> {
+///    A `PyVertexRef` extracted from the Python object.
+pub(crate) fn extract_vertex_ref(vref: &PyAny) -> PyResult<PyVertexRef> {
     if let Ok(s) = vref.extract::<String>() {
-        Ok(s.into())
+        Ok(PyVertexRef::Name(s))
     } else if let Ok(gid) = vref.extract::<u64>() {
-        Ok(gid.into())
+        Ok(PyVertexRef::Ref(gid.into()))
     } else if let Ok(v) = vref.extract::<PyVertex>() {
-        Ok(v.into())
+        Ok(PyVertexRef::Ref(v.into()))
     } else {
         Err(PyTypeError::new_err("Not a valid vertex"))
     }
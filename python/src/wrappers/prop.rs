@@ -1,9 +1,13 @@
 use crate::types::repr::Repr;
+use chrono::{DateTime, FixedOffset};
 use docbrown::core as db_c;
-use pyo3::{FromPyObject, IntoPy, PyObject, Python};
+use itertools::Itertools;
+use pyo3::types::PyBytes;
+use pyo3::{FromPyObject, IntoPy, PyAny, PyObject, PyResult, Python};
 use std::collections::HashMap;
 use std::{fmt, i64};
 
+// variants are tried in order, bytes have to come before lists as they are a sequence of ints
 #[derive(FromPyObject, Debug, Clone)]
 pub enum Prop {
     Str(String),
@@ -11,6 +15,14 @@ pub enum Prop {
     I64(i64),
     U64(u64),
     F64(f64),
+    DTime(DateTime<FixedOffset>),
+    Bytes(#[pyo3(from_py_with = "extract_bytes")] Vec<u8>),
+    List(Vec<Prop>),
+    Map(HashMap<String, Prop>),
+}
+
+fn extract_bytes(obj: &PyAny) -> PyResult<Vec<u8>> {
+    Ok(obj.downcast::<PyBytes>()?.as_bytes().to_vec())
 }
 
 impl fmt::Display for Prop {
@@ -21,6 +33,18 @@ impl fmt::Display for Prop {
             Prop::I64(value) => write!(f, "{}", value),
             Prop::U64(value) => write!(f, "{}", value),
             Prop::F64(value) => write!(f, "{}", value),
+            Prop::DTime(value) => write!(f, "{}", value.to_rfc3339()),
+            Prop::Bytes(value) => write!(f, "{:?}", value),
+            Prop::List(values) => write!(f, "[{}]", values.iter().join(", ")),
+            Prop::Map(values) => {
+                // sort the keys so the same map is always displayed the same way
+                let entries = values
+                    .iter()
+                    .sorted_by(|(a, _), (b, _)| a.cmp(b))
+                    .map(|(k, v)| format!("{}: {}", k, v))
+                    .join(", ");
+                write!(f, "{{{}}}", entries)
+            }
         }
     }
 }
@@ -33,6 +57,10 @@ impl IntoPy<PyObject> for Prop {
             Prop::I64(i64) => i64.into_py(py),
             Prop::U64(u64) => u64.into_py(py),
             Prop::F64(f64) => f64.into_py(py),
+            Prop::DTime(dtime) => dtime.into_py(py),
+            Prop::Bytes(bytes) => PyBytes::new(py, &bytes).into(),
+            Prop::List(list) => list.into_py(py),
+            Prop::Map(map) => map.into_py(py),
         }
     }
 }
@@ -45,6 +73,12 @@ impl From<Prop> for db_c::Prop {
             Prop::I64(i64) => db_c::Prop::I64(i64),
             Prop::U64(u64) => db_c::Prop::U64(u64),
             Prop::F64(f64) => db_c::Prop::F64(f64),
+            Prop::DTime(dtime) => db_c::Prop::DTime(dtime),
+            Prop::Bytes(bytes) => db_c::Prop::Bytes(bytes),
+            Prop::List(list) => db_c::Prop::List(list.into_iter().map(|p| p.into()).collect()),
+            Prop::Map(map) => db_c::Prop::Map(Box::new(
+                map.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            )),
         }
     }
 }
@@ -60,6 +94,12 @@ impl From<db_c::Prop> for Prop {
             db_c::Prop::U64(u64) => Prop::U64(u64),
             db_c::Prop::F64(f64) => Prop::F64(f64),
             db_c::Prop::F32(f32) => Prop::F64(f32 as f64),
+            db_c::Prop::DTime(dtime) => Prop::DTime(dtime),
+            db_c::Prop::Bytes(bytes) => Prop::Bytes(bytes),
+            db_c::Prop::List(list) => Prop::List(list.into_iter().map(|p| p.into()).collect()),
+            db_c::Prop::Map(map) => {
+                Prop::Map(map.into_iter().map(|(k, v)| (k, v.into())).collect())
+            }
         }
     }
 }
@@ -75,6 +115,10 @@ impl Repr for db_c::Prop {
             db_c::Prop::I32(v) => v.repr(),
             db_c::Prop::U32(v) => v.repr(),
             db_c::Prop::F32(v) => v.repr(),
+            db_c::Prop::DTime(v) => v.to_rfc3339(),
+            db_c::Prop::Bytes(v) => format!("{:?}", v),
+            db_c::Prop::List(v) => v.repr(),
+            db_c::Prop::Map(v) => v.as_ref().repr(),
         }
     }
 }
@@ -680,6 +680,95 @@ def test_all_edge_window():
     assert sorted(list(map(lambda e: e.id(), v.window(t_start=1).edges()))) == [1, 2, 3, 5, 6]
 
 
+def test_nested_properties():
+    from datetime import datetime, timedelta, timezone
+
+    booked = datetime(2023, 4, 1, 10, tzinfo=timezone(timedelta(hours=2)))
+    props = {
+        "booked": booked,
+        "tags": ["fx", 3],
+        "meta": {"desk": [True]},
+        "signature": b"\xca\xfe",
+    }
+
+    g = Graph(1)
+    g.add_vertex(1, 1, props)
+
+    for key, value in props.items():
+        assert g.vertex(1).property(key) == value
+
+    with tempfile.TemporaryDirectory() as tmpdirname:
+        g.save_to_file(tmpdirname)
+        g = Graph.load_from_file(tmpdirname)
+
+    assert g.vertex(1).property_history("tags") == [(1, ["fx", 3])]
+
+
+def test_schema():
+    g = Graph(2)
+    g.set_schema(vertex={"age": "i64"}, edges={"trades": {"amount": "f64"}})
+
+    g.add_vertex(1, 1, {"age": 40})
+    g.add_edge(1, 1, 2, {"amount": 2.5}, layer="trades")
+
+    with pytest.raises(Exception):
+        g.add_vertex(1, 1, {"age": "forty"})
+    # an i64 doesn't always fit into an f64
+    with pytest.raises(Exception):
+        g.add_edge(2, 1, 2, {"amount": 3}, layer="trades")
+
+    with tempfile.TemporaryDirectory() as tmpdirname:
+        g.save_to_file(tmpdirname)
+        g = Graph.load_from_file(tmpdirname)
+
+    assert g.schema() == {"vertex": {"age": "i64"}, "edges": {"trades": {"amount": "f64"}}}
+
+
+def test_schema_narrows_values_in_range():
+    g = Graph(1)
+    g.set_schema(vertex={"small": "i32", "count": "u64", "ratio": "f32"})
+
+    g.add_vertex(1, 1, {"small": -3, "count": 3, "ratio": 0.5})
+    assert g.vertex(1).property("small") == -3
+    assert g.vertex(1).property("count") == 3
+    assert g.vertex(1).property("ratio") == 0.5
+
+    with pytest.raises(Exception):
+        g.add_vertex(2, 1, {"small": 2**40})
+    with pytest.raises(Exception):
+        g.add_vertex(2, 1, {"count": -1})
+    with pytest.raises(Exception):
+        g.add_vertex(2, 1, {"ratio": 0.1})
+
+
+def test_dense_vertex_ids():
+    g = Graph(2, vertex_ids="dense")
+    g.add_edge(1, "Alice", "Bob", {})
+
+    assert g.vertex_by_name("Alice").id() == 0
+    assert g.vertex("Bob").id() == 1
+    assert g.has_edge("Alice", "Bob")
+    assert g.vertex_by_name("Carol") is None
+
+    with pytest.raises(ValueError):
+        Graph(1, vertex_ids="sequential")
+
+
+def test_time_unit():
+    g = Graph(1, time_unit="microseconds")
+    g.add_vertex(0, 1)
+    g.add_vertex(3000000, 1)
+    assert len(list(g.rolling("1 second"))) == len(list(g.rolling(1000000)))
+
+    g = Graph(1, time_unit="integer")
+    g.add_vertex(0, 1)
+    with pytest.raises(Exception):
+        g.rolling("1 second")
+
+    with pytest.raises(ValueError):
+        Graph(1, time_unit="fortnights")
+
+
 def test_static_prop_change():
     # with pytest.raises(Exception):
     g = Graph(1)
@@ -725,6 +814,19 @@ def test_global_clustering_coeffficient():
     assert algorithms.global_clustering_coefficient(v) == 0.5454545454545454
 
 
+def test_all_local_reciprocity():
+    g = Graph(1)
+
+    g.add_edge(0, 1, 2, {})
+    g.add_edge(0, 2, 1, {})
+    g.add_edge(0, 1, 3, {})
+
+    reciprocity = algorithms.all_local_reciprocity(g)
+    assert set(reciprocity.keys()) == {1, 2, 3}
+    assert reciprocity[2] == 1.0
+    assert reciprocity[3] == 0.0
+
+
 def test_edge_time_apis():
     g = Graph(1)
 